@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE clients
+DROP COLUMN calendar_token;
+
+ALTER TABLE workouts
+DROP COLUMN scheduled_date;
+
+ALTER TABLE programs
+DROP COLUMN start_date,
+DROP COLUMN training_days;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+ALTER TABLE programs
+ADD COLUMN start_date DATE,
+-- ISO weekdays the client trains on, 1 = Monday ... 7 = Sunday
+ADD COLUMN training_days INT[] NOT NULL DEFAULT '{}';
+
+ALTER TABLE workouts
+-- Set when a workout is rescheduled, overrides the date derived from the program schedule
+ADD COLUMN scheduled_date DATE;
+
+ALTER TABLE clients
+ADD COLUMN calendar_token VARCHAR(64) UNIQUE;
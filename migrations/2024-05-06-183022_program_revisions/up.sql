@@ -0,0 +1,18 @@
+-- Your SQL goes here
+
+CREATE TABLE program_revisions (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Relationships
+    program_id uuid NOT NULL REFERENCES programs(id) ON DELETE CASCADE,
+    author_id uuid REFERENCES users(id) ON DELETE SET NULL,
+
+    -- Fields
+    revision INT NOT NULL,
+    -- the program, its workouts and their exercises at the time of the revision
+    snapshot JSONB NOT NULL,
+
+    UNIQUE(program_id, revision)
+);
@@ -0,0 +1,12 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE program_claims;
+
+ALTER TABLE programs
+DROP COLUMN published,
+DROP COLUMN published_at,
+DROP COLUMN price_tier,
+DROP COLUMN level;
+
+DROP TYPE price_tiers;
+DROP TYPE program_levels;
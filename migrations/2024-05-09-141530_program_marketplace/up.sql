@@ -0,0 +1,30 @@
+-- Your SQL goes here
+
+CREATE TYPE price_tiers AS ENUM ('free', 'basic', 'premium');
+CREATE TYPE program_levels AS ENUM ('beginner', 'intermediate', 'advanced');
+
+ALTER TABLE programs
+-- Published templates show up in the marketplace
+ADD COLUMN published BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN published_at TIMESTAMPTZ,
+ADD COLUMN price_tier price_tiers NOT NULL DEFAULT 'free',
+ADD COLUMN level program_levels NOT NULL DEFAULT 'beginner';
+
+CREATE TABLE program_claims (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Relationships
+    -- the published listing, kept as null if the listing is deleted later on
+    program_id uuid REFERENCES programs(id) ON DELETE SET NULL,
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- the user's own copy of the program
+    copy_program_id uuid REFERENCES programs(id) ON DELETE SET NULL,
+
+    -- Fields
+    -- tier of the listing at the time it was claimed
+    price_tier price_tiers NOT NULL,
+
+    UNIQUE(program_id, user_id)
+);
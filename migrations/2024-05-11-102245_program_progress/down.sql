@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE program_progress;
+
+ALTER TABLE workouts
+DROP COLUMN completed_at;
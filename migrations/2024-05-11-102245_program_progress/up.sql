@@ -0,0 +1,24 @@
+-- Your SQL goes here
+
+ALTER TABLE workouts
+ADD COLUMN completed_at TIMESTAMPTZ;
+
+CREATE TABLE program_progress (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Relationships
+    program_id uuid NOT NULL UNIQUE REFERENCES programs(id) ON DELETE CASCADE,
+    next_workout_id uuid REFERENCES workouts(id) ON DELETE SET NULL,
+
+    -- Fields
+    total_workouts INT NOT NULL DEFAULT 0,
+    completed_workouts INT NOT NULL DEFAULT 0,
+    current_streak INT NOT NULL DEFAULT 0,
+    longest_streak INT NOT NULL DEFAULT 0,
+    last_completed_at TIMESTAMPTZ,
+    -- completion of every week of the program
+    weeks JSONB NOT NULL DEFAULT '[]'
+);
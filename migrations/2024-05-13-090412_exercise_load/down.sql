@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE exercises
+DROP COLUMN load,
+DROP COLUMN load_unit;
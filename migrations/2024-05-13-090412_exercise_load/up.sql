@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+ALTER TABLE exercises
+-- Prescribed weight, used by the progression generator to increase load week over week
+ADD COLUMN load DOUBLE PRECISION,
+ADD COLUMN load_unit VARCHAR(10) NOT NULL DEFAULT '';
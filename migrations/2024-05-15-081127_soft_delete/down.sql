@@ -0,0 +1,14 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS programs_archived_at_idx;
+DROP INDEX IF EXISTS workouts_archived_at_idx;
+DROP INDEX IF EXISTS exercises_archived_at_idx;
+
+ALTER TABLE programs
+DROP COLUMN archived_at;
+
+ALTER TABLE workouts
+DROP COLUMN archived_at;
+
+ALTER TABLE exercises
+DROP COLUMN archived_at;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+-- Archived rows are hidden from listings and purged for good by the retention job
+ALTER TABLE programs
+ADD COLUMN archived_at TIMESTAMPTZ;
+
+ALTER TABLE workouts
+ADD COLUMN archived_at TIMESTAMPTZ;
+
+ALTER TABLE exercises
+ADD COLUMN archived_at TIMESTAMPTZ;
+
+CREATE INDEX programs_archived_at_idx ON programs (archived_at) WHERE archived_at IS NOT NULL;
+CREATE INDEX workouts_archived_at_idx ON workouts (archived_at) WHERE archived_at IS NOT NULL;
+CREATE INDEX exercises_archived_at_idx ON exercises (archived_at) WHERE archived_at IS NOT NULL;
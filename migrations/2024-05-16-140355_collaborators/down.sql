@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS client_collaborators;
+DROP TABLE IF EXISTS program_collaborators;
+
+DROP TYPE IF EXISTS collaborator_roles;
@@ -0,0 +1,39 @@
+-- Your SQL goes here
+
+CREATE TYPE collaborator_roles AS ENUM ('viewer', 'editor');
+
+CREATE TABLE program_collaborators (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Relationships
+    program_id uuid NOT NULL REFERENCES programs(id) ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    granted_by uuid REFERENCES users(id) ON DELETE SET NULL,
+
+    -- Fields
+    role collaborator_roles NOT NULL DEFAULT 'viewer',
+
+    UNIQUE(program_id, user_id)
+);
+
+-- Grants on a client relationship apply to every program assigned to the client
+CREATE TABLE client_collaborators (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Relationships
+    client_id uuid NOT NULL REFERENCES clients(id) ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    granted_by uuid REFERENCES users(id) ON DELETE SET NULL,
+
+    -- Fields
+    role collaborator_roles NOT NULL DEFAULT 'viewer',
+
+    UNIQUE(client_id, user_id)
+);
+
+CREATE INDEX program_collaborators_user_id_idx ON program_collaborators (user_id);
+CREATE INDEX client_collaborators_user_id_idx ON client_collaborators (user_id);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS workout_tags;
+DROP TABLE IF EXISTS program_tags;
+DROP TABLE IF EXISTS tags;
+
+DROP TYPE IF EXISTS tag_categories;
@@ -0,0 +1,76 @@
+-- Your SQL goes here
+
+CREATE TYPE tag_categories AS ENUM ('focus_area', 'audience', 'workout_type', 'equipment');
+
+-- Vocabulary curated by admins
+CREATE TABLE tags (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Fields
+    category tag_categories NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    slug VARCHAR(60) NOT NULL,
+
+    UNIQUE(category, slug)
+);
+
+CREATE TABLE program_tags (
+    program_id uuid NOT NULL REFERENCES programs(id) ON DELETE CASCADE,
+    tag_id uuid NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+
+    PRIMARY KEY(program_id, tag_id)
+);
+
+CREATE TABLE workout_tags (
+    workout_id uuid NOT NULL REFERENCES workouts(id) ON DELETE CASCADE,
+    tag_id uuid NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+
+    PRIMARY KEY(workout_id, tag_id)
+);
+
+CREATE INDEX program_tags_tag_id_idx ON program_tags (tag_id);
+CREATE INDEX workout_tags_tag_id_idx ON workout_tags (tag_id);
+
+-- Parse the existing comma separated strings into tags, slugs match `tag_slug` in the API
+CREATE FUNCTION pg_temp.tag_slug(val TEXT) RETURNS TEXT AS $$
+    SELECT trim(BOTH '-' FROM regexp_replace(lower(trim(val)), '[^a-z0-9]+', '-', 'g'))
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE TEMPORARY TABLE parsed_tags AS
+SELECT 'programs' AS source, p.id AS row_id, 'focus_area'::tag_categories AS category, trim(part) AS name
+FROM programs p, unnest(string_to_array(p.focus_areas, ',')) AS part
+UNION ALL
+SELECT 'programs', p.id, 'audience', trim(part)
+FROM programs p, unnest(string_to_array(p.target_audience, ',')) AS part
+UNION ALL
+SELECT 'workouts', w.id, 'workout_type', trim(part)
+FROM workouts w, unnest(string_to_array(w.workout_type, ',')) AS part
+UNION ALL
+SELECT 'workouts', w.id, 'equipment', trim(part)
+FROM workouts w, unnest(string_to_array(w.equipment_needed, ',')) AS part;
+
+DELETE FROM parsed_tags WHERE pg_temp.tag_slug(name) = '' OR length(name) > 50;
+
+INSERT INTO tags (category, name, slug)
+SELECT DISTINCT ON (category, pg_temp.tag_slug(name)) category, name, pg_temp.tag_slug(name)
+FROM parsed_tags
+ORDER BY category, pg_temp.tag_slug(name), name
+ON CONFLICT DO NOTHING;
+
+INSERT INTO program_tags (program_id, tag_id)
+SELECT DISTINCT parsed.row_id, t.id
+FROM parsed_tags parsed
+JOIN tags t ON t.category = parsed.category AND t.slug = pg_temp.tag_slug(parsed.name)
+WHERE parsed.source = 'programs'
+ON CONFLICT DO NOTHING;
+
+INSERT INTO workout_tags (workout_id, tag_id)
+SELECT DISTINCT parsed.row_id, t.id
+FROM parsed_tags parsed
+JOIN tags t ON t.category = parsed.category AND t.slug = pg_temp.tag_slug(parsed.name)
+WHERE parsed.source = 'workouts'
+ON CONFLICT DO NOTHING;
+
+DROP TABLE parsed_tags;
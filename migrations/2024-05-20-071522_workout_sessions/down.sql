@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS set_logs;
+DROP TABLE IF EXISTS workout_sessions;
@@ -0,0 +1,45 @@
+-- Your SQL goes here
+
+-- A single run through a workout by one user
+CREATE TABLE workout_sessions (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Relationships
+    workout_id uuid NOT NULL REFERENCES workouts(id) ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+
+    -- Fields
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ,
+    notes TEXT NOT NULL DEFAULT ''
+);
+
+-- Only one session of a workout can be in progress per user
+CREATE UNIQUE INDEX workout_sessions_open_idx ON workout_sessions (workout_id, user_id)
+    WHERE finished_at IS NULL;
+CREATE INDEX workout_sessions_user_id_idx ON workout_sessions (user_id);
+
+-- What was actually done for one set of an exercise
+CREATE TABLE set_logs (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Relationships
+    session_id uuid NOT NULL REFERENCES workout_sessions(id) ON DELETE CASCADE,
+    exercise_id uuid NOT NULL REFERENCES exercises(id) ON DELETE CASCADE,
+
+    -- Fields
+    set_number INTEGER NOT NULL CHECK (set_number > 0),
+    reps INTEGER NOT NULL CHECK (reps >= 0),
+    load DOUBLE PRECISION,
+    load_unit VARCHAR(10) NOT NULL DEFAULT '',
+    rpe DOUBLE PRECISION CHECK (rpe BETWEEN 1 AND 10),
+    notes TEXT NOT NULL DEFAULT '',
+
+    UNIQUE(session_id, exercise_id, set_number)
+);
+
+CREATE INDEX set_logs_exercise_id_idx ON set_logs (exercise_id);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS workout_alerts;
+
+DROP TYPE IF EXISTS workout_alert_kinds;
+
+ALTER TABLE users DROP COLUMN IF EXISTS timezone;
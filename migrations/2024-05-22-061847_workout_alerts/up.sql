@@ -0,0 +1,21 @@
+-- Your SQL goes here
+
+-- IANA name, reminders are sent relative to the user's local day
+ALTER TABLE users ADD COLUMN timezone VARCHAR(64) NOT NULL DEFAULT 'UTC';
+
+CREATE TYPE workout_alert_kinds AS ENUM ('reminder', 'missed');
+
+-- Reminders and missed-workout notices that were already sent, so each one goes out once
+CREATE TABLE workout_alerts (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Relationships
+    workout_id uuid NOT NULL REFERENCES workouts(id) ON DELETE CASCADE,
+
+    -- Fields
+    kind workout_alert_kinds NOT NULL,
+
+    UNIQUE(workout_id, kind)
+);
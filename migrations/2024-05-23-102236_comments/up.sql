@@ -0,0 +1,23 @@
+-- Your SQL goes here
+
+-- Threads on a workout, or on one of its exercises when `exercise_id` is set. Replies point at
+-- the first comment of their thread.
+CREATE TABLE comments (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    edited_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ,
+
+    -- Relationships
+    workout_id uuid NOT NULL REFERENCES workouts(id) ON DELETE CASCADE,
+    exercise_id uuid REFERENCES exercises(id) ON DELETE CASCADE,
+    parent_id uuid REFERENCES comments(id) ON DELETE CASCADE,
+    author_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+
+    -- Fields
+    body TEXT NOT NULL
+);
+
+CREATE INDEX comments_workout_id_idx ON comments (workout_id, created_at);
+CREATE INDEX comments_parent_id_idx ON comments (parent_id);
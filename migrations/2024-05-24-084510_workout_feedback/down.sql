@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS exercise_discomfort;
+DROP TABLE IF EXISTS workout_feedback;
+
+DROP TYPE IF EXISTS discomfort_kinds;
@@ -0,0 +1,38 @@
+-- Your SQL goes here
+
+CREATE TYPE discomfort_kinds AS ENUM ('discomfort', 'pain');
+
+-- How a finished workout felt to the user who did it, one per user and workout
+CREATE TABLE workout_feedback (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Relationships
+    workout_id uuid NOT NULL REFERENCES workouts(id) ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+
+    -- Fields
+    session_rpe INTEGER NOT NULL CHECK (session_rpe BETWEEN 1 AND 10),
+    enjoyment INTEGER NOT NULL CHECK (enjoyment BETWEEN 1 AND 5),
+    notes TEXT NOT NULL DEFAULT '',
+
+    UNIQUE(workout_id, user_id)
+);
+
+-- Exercises that hurt or felt off during the workout
+CREATE TABLE exercise_discomfort (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+
+    -- Relationships
+    feedback_id uuid NOT NULL REFERENCES workout_feedback(id) ON DELETE CASCADE,
+    exercise_id uuid NOT NULL REFERENCES exercises(id) ON DELETE CASCADE,
+
+    -- Fields
+    kind discomfort_kinds NOT NULL,
+    note VARCHAR(255) NOT NULL DEFAULT '',
+
+    UNIQUE(feedback_id, exercise_id)
+);
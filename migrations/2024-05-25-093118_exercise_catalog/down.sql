@@ -0,0 +1,50 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE exercises
+    ADD COLUMN name VARCHAR(50) NOT NULL DEFAULT '',
+    ADD COLUMN description VARCHAR(255) NOT NULL DEFAULT '',
+    ADD COLUMN equipment VARCHAR(255) NOT NULL DEFAULT '',
+    ADD COLUMN muscle_groups VARCHAR(255) NOT NULL DEFAULT '',
+    ADD COLUMN image VARCHAR(255) NOT NULL DEFAULT '',
+    ADD COLUMN video VARCHAR(255) NOT NULL DEFAULT '',
+    ADD COLUMN instructions VARCHAR(500) NOT NULL DEFAULT '';
+
+UPDATE exercises e
+SET name = c.name,
+    description = c.description,
+    equipment = c.equipment,
+    muscle_groups = c.muscle_groups,
+    image = c.image,
+    video = c.video,
+    instructions = c.instructions
+FROM exercise_catalog c
+WHERE c.id = e.catalog_id;
+
+UPDATE program_revisions r
+SET snapshot = jsonb_set(r.snapshot, '{workouts}', (
+    SELECT COALESCE(jsonb_agg(
+        jsonb_set(w, '{exercises}', (
+            SELECT COALESCE(jsonb_agg(
+                (ex - 'catalog' - 'catalog_id')
+                || jsonb_build_object(
+                    'name', ex->'catalog'->'name',
+                    'description', ex->'catalog'->'description',
+                    'equipment', ex->'catalog'->'equipment',
+                    'muscle_groups', ex->'catalog'->'muscle_groups',
+                    'image', ex->'catalog'->'image',
+                    'video', ex->'catalog'->'video',
+                    'instructions', ex->'catalog'->'instructions'
+                )
+                ORDER BY e_idx
+            ), '[]'::jsonb)
+            FROM jsonb_array_elements(w->'exercises') WITH ORDINALITY AS e(ex, e_idx)
+        ))
+        ORDER BY w_idx
+    ), '[]'::jsonb)
+    FROM jsonb_array_elements(r.snapshot->'workouts') WITH ORDINALITY AS wk(w, w_idx)
+))
+WHERE jsonb_typeof(r.snapshot->'workouts') = 'array';
+
+ALTER TABLE exercises DROP COLUMN catalog_id;
+
+DROP TABLE IF EXISTS exercise_catalog;
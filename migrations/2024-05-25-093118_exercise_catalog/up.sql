@@ -0,0 +1,117 @@
+-- Your SQL goes here
+
+-- Canonical exercises, workouts reference them and only carry the prescription. Entries without
+-- an owner are part of the library curated by admins, slugs are unique per owner.
+CREATE TABLE exercise_catalog (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Relationships
+    owner_id uuid REFERENCES users(id) ON DELETE SET NULL,
+
+    -- Fields
+    name VARCHAR(50) NOT NULL,
+    slug VARCHAR(60) NOT NULL,
+    description VARCHAR(255) NOT NULL DEFAULT '',
+    equipment VARCHAR(255) NOT NULL DEFAULT '',
+    muscle_groups VARCHAR(255) NOT NULL DEFAULT '',
+    image VARCHAR(255) NOT NULL DEFAULT '',
+    video VARCHAR(255) NOT NULL DEFAULT '',
+    instructions VARCHAR(500) NOT NULL DEFAULT ''
+);
+
+CREATE INDEX exercise_catalog_owner_id_idx ON exercise_catalog (owner_id);
+
+CREATE UNIQUE INDEX exercise_catalog_owner_slug_idx
+    ON exercise_catalog (COALESCE(owner_id, '00000000-0000-0000-0000-000000000000'), slug);
+
+-- Deduplicate the existing exercises into the catalog, slugs match `catalog_slug` in the API
+CREATE FUNCTION pg_temp.catalog_slug(val TEXT) RETURNS TEXT AS $$
+    SELECT COALESCE(
+        NULLIF(trim(BOTH '-' FROM regexp_replace(lower(trim(val)), '[^a-z0-9]+', '-', 'g')), ''),
+        'exercise'
+    )
+$$ LANGUAGE SQL IMMUTABLE;
+
+-- Exercises that only exist in program revisions are included so every snapshot can be
+-- rewritten below
+CREATE TEMPORARY TABLE catalog_sources AS
+SELECT e.owner_id, e.created_at, regexp_replace(trim(e.name), '\s+', ' ', 'g') AS name,
+    e.description, e.equipment, e.muscle_groups, e.image, e.video, e.instructions
+FROM exercises e
+UNION ALL
+SELECT (ex->>'owner_id')::uuid, (ex->>'created_at')::timestamptz,
+    regexp_replace(COALESCE(trim(ex->>'name'), ''), '\s+', ' ', 'g'),
+    COALESCE(ex->>'description', ''), COALESCE(ex->>'equipment', ''),
+    COALESCE(ex->>'muscle_groups', ''), COALESCE(ex->>'image', ''),
+    COALESCE(ex->>'video', ''), COALESCE(ex->>'instructions', '')
+FROM program_revisions r,
+    jsonb_array_elements(r.snapshot->'workouts') AS w,
+    jsonb_array_elements(w->'exercises') AS ex;
+
+-- Every trainer gets their own entries, only exercises without an owner become library entries.
+-- The most common spelling of the name is kept, the details come from the exercise with the most
+-- media (ties go to the oldest).
+INSERT INTO exercise_catalog (owner_id, created_at, name, slug, description, equipment,
+    muscle_groups, image, video, instructions)
+SELECT
+    best.owner_id, best.created_at, spelling.name, best.slug, best.description, best.equipment,
+    best.muscle_groups, best.image, best.video, best.instructions
+FROM (
+    SELECT DISTINCT ON (owner_id, pg_temp.catalog_slug(name))
+        pg_temp.catalog_slug(name) AS slug, *
+    FROM catalog_sources
+    ORDER BY owner_id, pg_temp.catalog_slug(name),
+        (image <> '')::int + (video <> '')::int + (instructions <> '')::int DESC,
+        created_at
+) best
+JOIN (
+    SELECT owner_id, pg_temp.catalog_slug(name) AS slug,
+        mode() WITHIN GROUP (ORDER BY name) AS name
+    FROM catalog_sources
+    GROUP BY 1, 2
+) spelling ON spelling.slug = best.slug AND spelling.owner_id IS NOT DISTINCT FROM best.owner_id;
+
+DROP TABLE catalog_sources;
+
+ALTER TABLE exercises ADD COLUMN catalog_id uuid REFERENCES exercise_catalog(id);
+
+UPDATE exercises e
+SET catalog_id = c.id
+FROM exercise_catalog c
+WHERE c.slug = pg_temp.catalog_slug(e.name) AND c.owner_id IS NOT DISTINCT FROM e.owner_id;
+
+ALTER TABLE exercises ALTER COLUMN catalog_id SET NOT NULL;
+
+CREATE INDEX exercises_catalog_id_idx ON exercises (catalog_id);
+
+-- Snapshots are read back when restoring a revision, so they get the same shape as the API
+UPDATE program_revisions r
+SET snapshot = jsonb_set(r.snapshot, '{workouts}', (
+    SELECT COALESCE(jsonb_agg(
+        jsonb_set(w, '{exercises}', (
+            SELECT COALESCE(jsonb_agg(
+                (ex - 'name' - 'description' - 'equipment' - 'muscle_groups' - 'image' - 'video'
+                    - 'instructions')
+                || jsonb_build_object('catalog_id', c.id, 'catalog', to_jsonb(c))
+                ORDER BY e_idx
+            ), '[]'::jsonb)
+            FROM jsonb_array_elements(w->'exercises') WITH ORDINALITY AS e(ex, e_idx)
+            JOIN exercise_catalog c ON c.slug = pg_temp.catalog_slug(ex->>'name')
+                AND c.owner_id IS NOT DISTINCT FROM (ex->>'owner_id')::uuid
+        ))
+        ORDER BY w_idx
+    ), '[]'::jsonb)
+    FROM jsonb_array_elements(r.snapshot->'workouts') WITH ORDINALITY AS wk(w, w_idx)
+))
+WHERE jsonb_typeof(r.snapshot->'workouts') = 'array';
+
+ALTER TABLE exercises
+    DROP COLUMN name,
+    DROP COLUMN description,
+    DROP COLUMN equipment,
+    DROP COLUMN muscle_groups,
+    DROP COLUMN image,
+    DROP COLUMN video,
+    DROP COLUMN instructions;
@@ -0,0 +1,12 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE exercises
+    DROP COLUMN reps_min,
+    DROP COLUMN reps_max,
+    DROP COLUMN amrap,
+    DROP COLUMN duration_seconds,
+    DROP COLUMN tut_seconds,
+    DROP COLUMN tempo,
+    DROP COLUMN distance_meters,
+    DROP COLUMN rest_seconds,
+    DROP COLUMN percent_1rm;
@@ -0,0 +1,144 @@
+-- Your SQL goes here
+
+-- Typed form of reps, duration and rest_period, the strings stay the display form
+ALTER TABLE exercises
+    ADD COLUMN reps_min INTEGER,
+    ADD COLUMN reps_max INTEGER,
+    ADD COLUMN amrap BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN duration_seconds INTEGER,
+    ADD COLUMN tut_seconds INTEGER,
+    ADD COLUMN tempo VARCHAR(20) NOT NULL DEFAULT '',
+    ADD COLUMN distance_meters DOUBLE PRECISION,
+    ADD COLUMN rest_seconds INTEGER,
+    ADD COLUMN percent_1rm DOUBLE PRECISION;
+
+-- Numbers that don't fit an INTEGER are left unparsed, like in the API, instead of failing the
+-- migration
+CREATE FUNCTION pg_temp.to_int(val NUMERIC) RETURNS INTEGER AS $$
+    SELECT CASE WHEN val BETWEEN -2147483648 AND 2147483647 THEN val::INTEGER END
+$$ LANGUAGE SQL IMMUTABLE;
+
+-- Same rules as `Prescription::parse` in the API
+CREATE FUNCTION pg_temp.parse_seconds(val TEXT) RETURNS INTEGER AS $$
+DECLARE
+    total NUMERIC;
+    m TEXT[];
+BEGIN
+    val := lower(trim(val));
+
+    IF val ~ '^\d+$' THEN
+        RETURN pg_temp.to_int(val::NUMERIC);
+    END IF;
+
+    IF val ~ '^\d+:\d{2}$' THEN
+        RETURN pg_temp.to_int(
+            split_part(val, ':', 1)::NUMERIC * 60 + split_part(val, ':', 2)::NUMERIC
+        );
+    END IF;
+
+    FOR m IN
+        SELECT regexp_matches(
+            val, '(\d+(?:\.\d+)?)\s*(hours?|hrs?|h|minutes?|mins?|seconds?|secs?|s)\y', 'g'
+        )
+    LOOP
+        total := COALESCE(total, 0) + m[1]::NUMERIC * CASE left(m[2], 1)
+            WHEN 'h' THEN 3600
+            WHEN 'm' THEN 60
+            ELSE 1
+        END;
+    END LOOP;
+
+    RETURN pg_temp.to_int(round(total));
+END
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+CREATE FUNCTION pg_temp.parse_prescription(
+    reps TEXT,
+    duration TEXT,
+    rest TEXT,
+    OUT reps_min INTEGER,
+    OUT reps_max INTEGER,
+    OUT amrap BOOLEAN,
+    OUT duration_seconds INTEGER,
+    OUT tut_seconds INTEGER,
+    OUT tempo TEXT,
+    OUT distance_meters DOUBLE PRECISION,
+    OUT rest_seconds INTEGER,
+    OUT percent_1rm DOUBLE PRECISION
+) AS $$
+DECLARE
+    percent_re CONSTANT TEXT := '@\s*(\d+(?:\.\d+)?)\s*%';
+    tempo_re CONSTANT TEXT := 'tempo\s*:?\s*([0-9x])-?([0-9x])-?([0-9x])-?([0-9x])';
+    distance_re CONSTANT TEXT := '(\d+(?:\.\d+)?)\s*(km|mi|m)\y';
+    tut_re CONSTANT TEXT := 'tut\s*:?\s*((?:\d+(?:\.\d+)?\s*[a-z]*\s*)+)';
+    m TEXT[];
+BEGIN
+    reps := regexp_replace(lower(trim(reps)), '^\d+\s*[x×]\s*', ' ');
+    duration := lower(trim(duration));
+    amrap := FALSE;
+    tempo := '';
+
+    m := regexp_match(reps, percent_re);
+    IF m IS NOT NULL THEN
+        percent_1rm := m[1]::DOUBLE PRECISION;
+        reps := regexp_replace(reps, percent_re, ' ');
+    END IF;
+
+    m := regexp_match(reps, tempo_re);
+    IF m IS NOT NULL THEN
+        reps := regexp_replace(reps, tempo_re, ' ');
+    ELSE
+        m := regexp_match(duration, tempo_re);
+        duration := regexp_replace(duration, tempo_re, ' ');
+    END IF;
+    IF m IS NOT NULL THEN
+        tempo := array_to_string(m, '-');
+    END IF;
+
+    m := regexp_match(reps, distance_re);
+    IF m IS NOT NULL THEN
+        reps := regexp_replace(reps, distance_re, ' ');
+    ELSE
+        m := regexp_match(duration, distance_re);
+        duration := regexp_replace(duration, distance_re, ' ');
+    END IF;
+    IF m IS NOT NULL THEN
+        distance_meters := m[1]::DOUBLE PRECISION * CASE m[2]
+            WHEN 'km' THEN 1000
+            WHEN 'mi' THEN 1609.344
+            ELSE 1
+        END;
+    END IF;
+
+    IF reps ~ '\d+\s*\+' THEN
+        reps_min := pg_temp.to_int((regexp_match(reps, '(\d+)\s*\+'))[1]::NUMERIC);
+        amrap := TRUE;
+    ELSIF reps ~ '\y(amrap|max|failure)\y' THEN
+        amrap := TRUE;
+    ELSIF reps ~ '\d+\s*(?:-|–|to)\s*\d+' THEN
+        m := regexp_match(reps, '(\d+)\s*(?:-|–|to)\s*(\d+)');
+        reps_min := pg_temp.to_int(m[1]::NUMERIC);
+        reps_max := pg_temp.to_int(m[2]::NUMERIC);
+    ELSIF reps ~ '\d' THEN
+        reps_min := pg_temp.to_int((regexp_match(reps, '\d+'))[1]::NUMERIC);
+        reps_max := reps_min;
+    END IF;
+
+    m := regexp_match(duration, tut_re);
+    IF m IS NOT NULL THEN
+        tut_seconds := pg_temp.parse_seconds(m[1]);
+        duration := regexp_replace(duration, tut_re, ' ');
+    END IF;
+
+    duration_seconds := pg_temp.parse_seconds(duration);
+    rest_seconds := pg_temp.parse_seconds(rest);
+END
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+UPDATE exercises e
+SET (reps_min, reps_max, amrap, duration_seconds, tut_seconds, tempo, distance_meters,
+        rest_seconds, percent_1rm) = (
+    SELECT p.reps_min, p.reps_max, p.amrap, p.duration_seconds, p.tut_seconds, p.tempo,
+        p.distance_meters, p.rest_seconds, p.percent_1rm
+    FROM pg_temp.parse_prescription(e.reps, e.duration, e.rest_period) p
+);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE exercises DROP COLUMN block_id;
+
+DROP TABLE workout_blocks;
+
+DROP TYPE block_types;
@@ -0,0 +1,32 @@
+-- Your SQL goes here
+
+CREATE TYPE block_types AS ENUM ('straight', 'superset', 'circuit', 'emom', 'interval');
+
+-- Groups exercises of a workout that are done together, exercises without a block are done as
+-- straight sets in sequence order
+CREATE TABLE workout_blocks (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Relationships
+    workout_id uuid NOT NULL REFERENCES workouts(id) ON DELETE CASCADE,
+
+    -- Fields
+    block_type block_types NOT NULL DEFAULT 'straight',
+    name VARCHAR(50) NOT NULL DEFAULT '',
+    sequence INTEGER NOT NULL DEFAULT 0,
+    rounds INTEGER NOT NULL DEFAULT 1 CHECK (rounds >= 1),
+    -- Length of an interval, or of each EMOM slot
+    work_seconds INTEGER CHECK (work_seconds > 0),
+    -- Rest between the exercises of a round, or after each interval
+    rest_seconds INTEGER CHECK (rest_seconds >= 0),
+    round_rest_seconds INTEGER CHECK (round_rest_seconds >= 0)
+);
+
+CREATE INDEX workout_blocks_workout_id_idx ON workout_blocks (workout_id);
+
+ALTER TABLE exercises
+    ADD COLUMN block_id uuid REFERENCES workout_blocks(id) ON DELETE SET NULL;
+
+CREATE INDEX exercises_block_id_idx ON exercises (block_id);
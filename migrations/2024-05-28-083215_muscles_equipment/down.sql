@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS catalog_equipment;
+DROP TABLE IF EXISTS catalog_muscles;
+DROP TABLE IF EXISTS equipment;
+DROP TABLE IF EXISTS muscles;
+
+DROP TYPE IF EXISTS muscle_roles;
@@ -0,0 +1,93 @@
+-- Your SQL goes here
+
+CREATE TYPE muscle_roles AS ENUM ('primary', 'secondary');
+
+-- Reference tables curated by admins
+CREATE TABLE muscles (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Fields
+    name VARCHAR(50) NOT NULL,
+    slug VARCHAR(60) NOT NULL UNIQUE
+);
+
+CREATE TABLE equipment (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Fields
+    name VARCHAR(50) NOT NULL,
+    slug VARCHAR(60) NOT NULL UNIQUE
+);
+
+CREATE TABLE catalog_muscles (
+    catalog_id uuid NOT NULL REFERENCES exercise_catalog(id) ON DELETE CASCADE,
+    muscle_id uuid NOT NULL REFERENCES muscles(id) ON DELETE CASCADE,
+    role muscle_roles NOT NULL DEFAULT 'primary',
+
+    PRIMARY KEY(catalog_id, muscle_id)
+);
+
+CREATE TABLE catalog_equipment (
+    catalog_id uuid NOT NULL REFERENCES exercise_catalog(id) ON DELETE CASCADE,
+    equipment_id uuid NOT NULL REFERENCES equipment(id) ON DELETE CASCADE,
+
+    PRIMARY KEY(catalog_id, equipment_id)
+);
+
+CREATE INDEX catalog_muscles_muscle_id_idx ON catalog_muscles (muscle_id);
+CREATE INDEX catalog_equipment_equipment_id_idx ON catalog_equipment (equipment_id);
+
+-- Parse the comma separated strings of the catalog, slugs match `taxonomy_slug` in the API. The
+-- first muscle group of an entry is its primary muscle, the other ones are secondary.
+CREATE FUNCTION pg_temp.taxonomy_slug(val TEXT) RETURNS TEXT AS $$
+    SELECT trim(BOTH '-' FROM regexp_replace(lower(trim(val)), '[^a-z0-9]+', '-', 'g'))
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE TEMPORARY TABLE parsed_muscles AS
+SELECT c.id AS catalog_id, trim(part) AS name, position
+FROM exercise_catalog c, unnest(string_to_array(c.muscle_groups, ',')) WITH ORDINALITY AS t(part, position);
+
+CREATE TEMPORARY TABLE parsed_equipment AS
+SELECT c.id AS catalog_id, trim(part) AS name
+FROM exercise_catalog c, unnest(string_to_array(c.equipment, ',')) AS part;
+
+DELETE FROM parsed_muscles WHERE pg_temp.taxonomy_slug(name) = '' OR length(name) > 50;
+DELETE FROM parsed_equipment WHERE pg_temp.taxonomy_slug(name) = '' OR length(name) > 50;
+
+INSERT INTO muscles (name, slug)
+SELECT DISTINCT ON (pg_temp.taxonomy_slug(name)) name, pg_temp.taxonomy_slug(name)
+FROM parsed_muscles
+ORDER BY pg_temp.taxonomy_slug(name), name
+ON CONFLICT DO NOTHING;
+
+INSERT INTO equipment (name, slug)
+SELECT DISTINCT ON (pg_temp.taxonomy_slug(name)) name, pg_temp.taxonomy_slug(name)
+FROM parsed_equipment
+ORDER BY pg_temp.taxonomy_slug(name), name
+ON CONFLICT DO NOTHING;
+
+INSERT INTO catalog_muscles (catalog_id, muscle_id, role)
+SELECT DISTINCT ON (parsed.catalog_id, m.id)
+    parsed.catalog_id,
+    m.id,
+    CASE WHEN parsed.position = first.position THEN 'primary' ELSE 'secondary' END::muscle_roles
+FROM parsed_muscles parsed
+JOIN muscles m ON m.slug = pg_temp.taxonomy_slug(parsed.name)
+JOIN (
+    SELECT catalog_id, min(position) AS position FROM parsed_muscles GROUP BY catalog_id
+) first ON first.catalog_id = parsed.catalog_id
+ORDER BY parsed.catalog_id, m.id, parsed.position
+ON CONFLICT DO NOTHING;
+
+INSERT INTO catalog_equipment (catalog_id, equipment_id)
+SELECT DISTINCT parsed.catalog_id, e.id
+FROM parsed_equipment parsed
+JOIN equipment e ON e.slug = pg_temp.taxonomy_slug(parsed.name)
+ON CONFLICT DO NOTHING;
+
+DROP TABLE parsed_muscles;
+DROP TABLE parsed_equipment;
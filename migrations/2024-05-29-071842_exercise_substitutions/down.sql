@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE clients DROP COLUMN IF EXISTS equipment_recorded;
+
+DROP TABLE IF EXISTS client_equipment;
+
+ALTER TABLE exercise_catalog
+    DROP COLUMN IF EXISTS movement_pattern,
+    DROP COLUMN IF EXISTS intensity;
+
+DROP TYPE IF EXISTS movement_patterns;
@@ -0,0 +1,32 @@
+-- Your SQL goes here
+
+CREATE TYPE movement_patterns AS ENUM (
+    'squat',
+    'hinge',
+    'lunge',
+    'horizontal_push',
+    'vertical_push',
+    'horizontal_pull',
+    'vertical_pull',
+    'carry',
+    'core',
+    'conditioning'
+);
+
+ALTER TABLE exercise_catalog
+    ADD COLUMN movement_pattern movement_patterns,
+    ADD COLUMN intensity intensity_choices;
+
+-- Equipment the client has access to, substitutes are only suggested within it
+CREATE TABLE client_equipment (
+    client_id uuid NOT NULL REFERENCES clients(id) ON DELETE CASCADE,
+    equipment_id uuid NOT NULL REFERENCES equipment(id) ON DELETE CASCADE,
+
+    PRIMARY KEY(client_id, equipment_id)
+);
+
+CREATE INDEX client_equipment_equipment_id_idx ON client_equipment (equipment_id);
+
+-- Until the client's equipment is set it's unknown and substitutes aren't filtered on it, once set
+-- an empty list means bodyweight only
+ALTER TABLE clients ADD COLUMN equipment_recorded BOOLEAN NOT NULL DEFAULT false;
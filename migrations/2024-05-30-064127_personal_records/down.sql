@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS personal_records;
+
+DROP TYPE IF EXISTS record_kinds;
+
+ALTER TABLE set_logs DROP COLUMN IF EXISTS duration_seconds;
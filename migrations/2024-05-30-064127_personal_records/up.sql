@@ -0,0 +1,69 @@
+-- Your SQL goes here
+
+ALTER TABLE set_logs ADD COLUMN duration_seconds INT CHECK (duration_seconds >= 0);
+
+CREATE TYPE record_kinds AS ENUM ('rep_max', 'epley_1rm', 'brzycki_1rm', 'volume', 'duration');
+
+-- Every time a user beats one of their records a row is added, the best row of a
+-- (user, catalog entry, kind, reps) is the current record. Loads are in kg. Records go away with
+-- the set that set them.
+CREATE TABLE personal_records (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Relationships
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    catalog_id uuid NOT NULL REFERENCES exercise_catalog(id) ON DELETE CASCADE,
+    set_log_id uuid NOT NULL REFERENCES set_logs(id) ON DELETE CASCADE,
+
+    -- Fields
+    kind record_kinds NOT NULL,
+    -- Rep count of a rep max, 0 for the other kinds
+    reps INT NOT NULL DEFAULT 0,
+    value FLOAT8 NOT NULL,
+    previous_value FLOAT8,
+    achieved_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX personal_records_user_id_idx ON personal_records (user_id, catalog_id, kind, reps);
+CREATE INDEX personal_records_catalog_id_idx ON personal_records (catalog_id, kind, reps);
+
+-- Current records of the sets logged so far, the formulas match `record_candidates` in the API
+CREATE TEMPORARY TABLE logged_sets AS
+SELECT
+    s.id AS set_log_id,
+    ws.user_id,
+    e.catalog_id,
+    s.reps,
+    s.created_at,
+    CASE
+        WHEN lower(trim(s.load_unit)) IN ('', 'kg', 'kgs') THEN s.load
+        WHEN lower(trim(s.load_unit)) IN ('lb', 'lbs') THEN s.load * 0.45359237
+    END AS load_kg
+FROM set_logs s
+JOIN workout_sessions ws ON ws.id = s.session_id
+JOIN exercises e ON e.id = s.exercise_id;
+
+DELETE FROM logged_sets WHERE load_kg IS NULL OR load_kg <= 0 OR reps < 1;
+
+INSERT INTO personal_records (user_id, catalog_id, set_log_id, kind, reps, value, achieved_at)
+SELECT DISTINCT ON (user_id, catalog_id, kind, reps)
+    user_id, catalog_id, set_log_id, kind, reps, value, created_at
+FROM (
+    SELECT set_log_id, user_id, catalog_id, created_at, 'rep_max'::record_kinds AS kind, reps, load_kg AS value
+    FROM logged_sets
+    UNION ALL
+    SELECT set_log_id, user_id, catalog_id, created_at, 'epley_1rm', 0,
+        CASE WHEN reps = 1 THEN load_kg ELSE load_kg * (1 + reps / 30.0) END
+    FROM logged_sets WHERE reps <= 12
+    UNION ALL
+    SELECT set_log_id, user_id, catalog_id, created_at, 'brzycki_1rm', 0, load_kg * 36.0 / (37 - reps)
+    FROM logged_sets WHERE reps <= 12
+    UNION ALL
+    SELECT set_log_id, user_id, catalog_id, created_at, 'volume', 0, reps * load_kg
+    FROM logged_sets
+) candidates
+ORDER BY user_id, catalog_id, kind, reps, value DESC, created_at ASC;
+
+DROP TABLE logged_sets;
@@ -0,0 +1,43 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE workout_data
+    ADD COLUMN legacy_duration VARCHAR(50) NOT NULL DEFAULT '',
+    ADD COLUMN legacy_distance VARCHAR(50) NOT NULL DEFAULT '',
+    ADD COLUMN legacy_avg_heart_rate VARCHAR(50) NOT NULL DEFAULT '',
+    ADD COLUMN legacy_active_energy_burned VARCHAR(50) NOT NULL DEFAULT '';
+
+UPDATE workout_data SET
+    legacy_duration = COALESCE(hk_workout_duration_secs::TEXT, ''),
+    legacy_distance = COALESCE(hk_workout_distance_meters::TEXT, ''),
+    legacy_avg_heart_rate = COALESCE(hk_workout_avg_heart_rate_bpm::TEXT, ''),
+    legacy_active_energy_burned = COALESCE(hk_workout_active_energy_burned_kcal::TEXT, '');
+
+-- Values that didn't parse go back where they came from
+CREATE FUNCTION pg_temp.unparsed(data_id uuid, data_field TEXT) RETURNS TEXT AS $$
+    SELECT value FROM workout_data_unparsed WHERE workout_data_id = data_id AND field = data_field
+$$ LANGUAGE SQL STABLE;
+
+UPDATE workout_data SET
+    legacy_duration = COALESCE(pg_temp.unparsed(id, 'hk_workout_duration_secs'), legacy_duration),
+    legacy_distance = COALESCE(pg_temp.unparsed(id, 'hk_workout_distance'), legacy_distance),
+    legacy_avg_heart_rate = COALESCE(
+        pg_temp.unparsed(id, 'hk_workout_avg_heart_rate'),
+        legacy_avg_heart_rate
+    ),
+    legacy_active_energy_burned = COALESCE(
+        pg_temp.unparsed(id, 'hk_workout_active_energy_burned'),
+        legacy_active_energy_burned
+    );
+
+DROP TABLE workout_data_unparsed;
+
+ALTER TABLE workout_data
+    DROP COLUMN hk_workout_duration_secs,
+    DROP COLUMN hk_workout_distance_meters,
+    DROP COLUMN hk_workout_avg_heart_rate_bpm,
+    DROP COLUMN hk_workout_active_energy_burned_kcal;
+
+ALTER TABLE workout_data RENAME COLUMN legacy_duration TO hk_workout_duration_secs;
+ALTER TABLE workout_data RENAME COLUMN legacy_distance TO hk_workout_distance;
+ALTER TABLE workout_data RENAME COLUMN legacy_avg_heart_rate TO hk_workout_avg_heart_rate;
+ALTER TABLE workout_data RENAME COLUMN legacy_active_energy_burned TO hk_workout_active_energy_burned;
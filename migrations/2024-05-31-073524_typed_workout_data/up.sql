@@ -0,0 +1,127 @@
+-- Your SQL goes here
+
+-- The HealthKit measurements were free text, they become numbers in a single unit each
+ALTER TABLE workout_data RENAME COLUMN hk_workout_duration_secs TO legacy_duration;
+ALTER TABLE workout_data RENAME COLUMN hk_workout_distance TO legacy_distance;
+ALTER TABLE workout_data RENAME COLUMN hk_workout_avg_heart_rate TO legacy_avg_heart_rate;
+ALTER TABLE workout_data RENAME COLUMN hk_workout_active_energy_burned TO legacy_active_energy_burned;
+
+ALTER TABLE workout_data
+    ADD COLUMN hk_workout_duration_secs FLOAT8 CHECK (hk_workout_duration_secs >= 0),
+    ADD COLUMN hk_workout_distance_meters FLOAT8 CHECK (hk_workout_distance_meters >= 0),
+    ADD COLUMN hk_workout_avg_heart_rate_bpm FLOAT8 CHECK (hk_workout_avg_heart_rate_bpm >= 0),
+    ADD COLUMN hk_workout_active_energy_burned_kcal FLOAT8
+        CHECK (hk_workout_active_energy_burned_kcal >= 0);
+
+-- Same rules as `parse_with_units` in the API, `units` and `factors` go in pairs
+CREATE FUNCTION pg_temp.parse_with_units(val TEXT, units TEXT[], factors FLOAT8[])
+RETURNS FLOAT8 AS $$
+DECLARE
+    amount_re CONSTANT TEXT := '(\d+(?:\.\d+)?)\s*([a-z/]*)';
+    total FLOAT8 := 0;
+    idx INTEGER;
+    m TEXT[];
+BEGIN
+    val := lower(trim(val));
+
+    IF val = '' OR regexp_replace(val, amount_re, '', 'g') !~ '^\s*$' THEN
+        RETURN NULL;
+    END IF;
+
+    FOR m IN SELECT regexp_matches(val, amount_re, 'g') LOOP
+        idx := array_position(units, m[2]);
+
+        IF idx IS NULL THEN
+            RETURN NULL;
+        END IF;
+
+        total := total + m[1]::FLOAT8 * factors[idx];
+    END LOOP;
+
+    RETURN total;
+END
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+CREATE FUNCTION pg_temp.parse_duration_secs(val TEXT) RETURNS FLOAT8 AS $$
+DECLARE
+    total FLOAT8 := 0;
+    part TEXT;
+BEGIN
+    val := trim(val);
+
+    IF val LIKE '%:%' THEN
+        IF val !~ '^\d+(\.\d+)?(:\d{2}(\.\d+)?){1,2}$' THEN
+            RETURN NULL;
+        END IF;
+
+        FOREACH part IN ARRAY string_to_array(val, ':') LOOP
+            total := total * 60 + part::FLOAT8;
+        END LOOP;
+
+        RETURN total;
+    END IF;
+
+    RETURN pg_temp.parse_with_units(
+        val,
+        ARRAY['', 's', 'sec', 'secs', 'second', 'seconds',
+            'm', 'min', 'mins', 'minute', 'minutes', 'h', 'hr', 'hrs', 'hour', 'hours'],
+        ARRAY[1, 1, 1, 1, 1, 1, 60, 60, 60, 60, 60, 3600, 3600, 3600, 3600, 3600]
+    );
+END
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+UPDATE workout_data SET
+    hk_workout_duration_secs = pg_temp.parse_duration_secs(legacy_duration),
+    hk_workout_distance_meters = pg_temp.parse_with_units(
+        legacy_distance,
+        ARRAY['', 'm', 'meter', 'meters', 'metre', 'metres', 'km', 'kilometer', 'kilometers',
+            'mi', 'mile', 'miles', 'yd', 'yard', 'yards', 'ft', 'foot', 'feet'],
+        ARRAY[1, 1, 1, 1, 1, 1, 1000, 1000, 1000, 1609.344, 1609.344, 1609.344,
+            0.9144, 0.9144, 0.9144, 0.3048, 0.3048, 0.3048]
+    ),
+    hk_workout_avg_heart_rate_bpm = pg_temp.parse_with_units(
+        legacy_avg_heart_rate,
+        ARRAY['', 'bpm', 'count/min'],
+        ARRAY[1, 1, 1]
+    ),
+    hk_workout_active_energy_burned_kcal = pg_temp.parse_with_units(
+        legacy_active_energy_burned,
+        ARRAY['', 'kcal', 'cal', 'cals', 'calories', 'kj'],
+        ARRAY[1, 1, 1, 1, 1, 1 / 4.184]
+    );
+
+-- Values that didn't parse are kept here, by the name of their old column, so they can be fixed
+-- by hand
+CREATE TABLE workout_data_unparsed (
+    -- Meta
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Relationships
+    workout_data_id uuid NOT NULL REFERENCES workout_data(id) ON DELETE CASCADE,
+
+    -- Fields
+    field VARCHAR(50) NOT NULL,
+    value VARCHAR(50) NOT NULL
+);
+
+CREATE INDEX workout_data_unparsed_workout_data_id_idx ON workout_data_unparsed (workout_data_id);
+
+INSERT INTO workout_data_unparsed (workout_data_id, field, value)
+SELECT id, 'hk_workout_duration_secs', legacy_duration FROM workout_data
+WHERE trim(legacy_duration) <> '' AND hk_workout_duration_secs IS NULL
+UNION ALL
+SELECT id, 'hk_workout_distance', legacy_distance FROM workout_data
+WHERE trim(legacy_distance) <> '' AND hk_workout_distance_meters IS NULL
+UNION ALL
+SELECT id, 'hk_workout_avg_heart_rate', legacy_avg_heart_rate FROM workout_data
+WHERE trim(legacy_avg_heart_rate) <> '' AND hk_workout_avg_heart_rate_bpm IS NULL
+UNION ALL
+SELECT id, 'hk_workout_active_energy_burned', legacy_active_energy_burned FROM workout_data
+WHERE trim(legacy_active_energy_burned) <> '' AND hk_workout_active_energy_burned_kcal IS NULL;
+
+ALTER TABLE workout_data
+    DROP COLUMN legacy_duration,
+    DROP COLUMN legacy_distance,
+    DROP COLUMN legacy_avg_heart_rate,
+    DROP COLUMN legacy_active_energy_burned;
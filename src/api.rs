@@ -1,9 +1,23 @@
 pub mod common;
+pub mod feeds;
 pub mod v1;
 use self::v1::{
-    analytics::analytics_routes, beta::beta_routes, certifications::certification_routes,
-    clients::client_routes, exercises::exercise_routes, feedback::feedback_routes,
-    notification::notification_routes, programs::program_routes, users::user_routes,
+    analytics::analytics_routes,
+    beta::beta_routes,
+    catalog::catalog_routes,
+    certifications::certification_routes,
+    clients::client_routes,
+    comments::comment_routes,
+    exercises::exercise_routes,
+    feedback::feedback_routes,
+    marketplace::marketplace_routes,
+    notification::notification_routes,
+    programs::program_routes,
+    records::record_routes,
+    sessions::session_routes,
+    tags::tag_routes,
+    taxonomy::{equipment_routes, muscle_routes},
+    users::user_routes,
     workouts::workout_routes,
 };
 use crate::server::AppState;
@@ -18,8 +32,16 @@ pub fn v1_routes() -> Router<Arc<AppState>> {
         .nest("/programs", program_routes())
         .nest("/workouts", workout_routes())
         .nest("/exercises", exercise_routes())
+        .nest("/catalog", catalog_routes())
+        .nest("/muscles", muscle_routes())
+        .nest("/equipment", equipment_routes())
         .nest("/clients", client_routes())
         .nest("/notifications", notification_routes())
         .nest("/feedback", feedback_routes())
         .nest("/analytics", analytics_routes())
+        .nest("/marketplace", marketplace_routes())
+        .nest("/tags", tag_routes())
+        .nest("/sessions", session_routes())
+        .nest("/records", record_routes())
+        .nest("/comments", comment_routes())
 }
@@ -0,0 +1,75 @@
+use crate::{
+    db::{
+        models::{client::Client, program::Program},
+        programs::load_program_schedule,
+    },
+    error::not_found,
+    server::AppState,
+    types::AppResult,
+    util::{
+        calendar::{render_ics, CalendarEvent},
+        extractors::Path,
+    },
+};
+use axum::{extract::State, routing::get, Router};
+use diesel::prelude::*;
+use http::header;
+use std::sync::Arc;
+
+/// Routes that live outside of the auth middleware, they are protected by the token in the path
+/// instead so calendar apps can subscribe to them.
+pub fn feed_routes() -> Router<Arc<AppState>> {
+    Router::new().route("/calendar/:token", get(client_calendar_feed))
+}
+
+async fn client_calendar_feed(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> AppResult<([(header::HeaderName, &'static str); 1], String)> {
+    use crate::schema::{clients::dsl as c_dsl, programs::dsl as p_dsl};
+
+    let token = token.strip_suffix(".ics").unwrap_or(&token);
+
+    let mut conn = state.db_pool.get_conn();
+
+    let client: Client = c_dsl::clients
+        .filter(c_dsl::calendar_token.eq(token))
+        .select(Client::as_select())
+        .first(&mut conn)
+        .map_err(|_| not_found())?;
+
+    let client_programs: Vec<Program> = p_dsl::programs
+        .filter(
+            p_dsl::client_id
+                .eq(client.id)
+                .and(p_dsl::template.eq(false))
+                .and(p_dsl::start_date.is_not_null())
+                .and(p_dsl::archived_at.is_null()),
+        )
+        .select(Program::as_select())
+        .load(&mut conn)?;
+
+    let mut events: Vec<CalendarEvent> = vec![];
+
+    for program in client_programs {
+        let schedule = load_program_schedule(&mut conn, &program)?.unwrap_or_default();
+
+        events.extend(schedule.into_iter().map(|scheduled| {
+            CalendarEvent {
+                uid: format!("{}@trainton.com", scheduled.workout.id),
+                date: scheduled.date,
+                summary: scheduled.workout.name,
+                description: format!("{}\n\n{}", program.name, scheduled.workout.description)
+                    .trim()
+                    .to_string(),
+            }
+        }));
+    }
+
+    let body = render_ics("Trainton Workouts", &events, chrono::Utc::now());
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        body,
+    ))
+}
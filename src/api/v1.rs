@@ -1,10 +1,19 @@
 pub mod analytics;
 pub mod beta;
+pub mod blocks;
+pub mod catalog;
 pub mod certifications;
 pub mod clients;
+pub mod comments;
 pub mod exercises;
 pub mod feedback;
+pub mod marketplace;
 pub mod notification;
 pub mod programs;
+pub mod records;
+pub mod sessions;
+pub mod substitutions;
+pub mod tags;
+pub mod taxonomy;
 pub mod users;
 pub mod workouts;
@@ -0,0 +1,149 @@
+use crate::{
+    api::v1::workouts::guard_workout_owner_or_program_client,
+    db::{
+        models::{
+            collaborator::CollaboratorRoles,
+            workout_block::{NewWorkoutBlock, WorkoutBlock},
+        },
+        programs::record_workout_program_revision,
+    },
+    error::{bad_request, BoxedAppError},
+    server::AppState,
+    types::AppResult,
+    util::extractors::{JsonExtractor, Path, UserIdExtractor},
+};
+use axum::{extract::State, Json};
+use diesel::{insert_into, prelude::*, update};
+use std::sync::Arc;
+
+/// Blocks of the workout in the order they are done.
+pub async fn list_workout_blocks(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_workout_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Vec<WorkoutBlock>>> {
+    use crate::schema::workout_blocks::dsl::*;
+
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        path_workout_id,
+        state.db_pool.get_conn(),
+        CollaboratorRoles::Viewer,
+    )
+    .await?;
+
+    let res = workout_blocks
+        .filter(workout_id.eq(path_workout_id))
+        .order((sequence.asc(), id.asc()))
+        .select(WorkoutBlock::as_select())
+        .load(&mut state.db_pool.get_conn())?;
+
+    Ok(Json(res))
+}
+
+/// Exercises are added to the block by setting their `block_id`.
+pub async fn create_workout_block(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_workout_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<NewWorkoutBlock>,
+) -> AppResult<Json<WorkoutBlock>> {
+    use crate::schema::workout_blocks::dsl::*;
+
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        path_workout_id,
+        state.db_pool.get_conn(),
+        CollaboratorRoles::Editor,
+    )
+    .await?;
+
+    body.validate().map_err(bad_request)?;
+
+    let res = state
+        .db_pool
+        .get_conn()
+        .transaction::<_, BoxedAppError, _>(|conn| {
+            let res = insert_into(workout_blocks)
+                .values((&body, workout_id.eq(path_workout_id)))
+                .returning(WorkoutBlock::as_returning())
+                .get_result(conn)?;
+
+            record_workout_program_revision(conn, path_workout_id, req_user_id)?;
+
+            Ok(res)
+        })?;
+
+    Ok(Json(res))
+}
+
+pub async fn update_workout_block(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path((path_workout_id, block_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+    JsonExtractor(body): JsonExtractor<NewWorkoutBlock>,
+) -> AppResult<Json<WorkoutBlock>> {
+    use crate::schema::workout_blocks::dsl::*;
+
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        path_workout_id,
+        state.db_pool.get_conn(),
+        CollaboratorRoles::Editor,
+    )
+    .await?;
+
+    body.validate().map_err(bad_request)?;
+
+    let res = state
+        .db_pool
+        .get_conn()
+        .transaction::<_, BoxedAppError, _>(|conn| {
+            let res = update(workout_blocks)
+                .filter(id.eq(block_id).and(workout_id.eq(path_workout_id)))
+                .set(&body)
+                .returning(WorkoutBlock::as_returning())
+                .get_result(conn)?;
+
+            record_workout_program_revision(conn, path_workout_id, req_user_id)?;
+
+            Ok(res)
+        })?;
+
+    Ok(Json(res))
+}
+
+/// The exercises of the block stay in the workout, they just don't belong to a block anymore.
+pub async fn delete_workout_block(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path((path_workout_id, block_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+) -> AppResult<Json<serde_json::Value>> {
+    use crate::schema::workout_blocks::dsl::*;
+
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        path_workout_id,
+        state.db_pool.get_conn(),
+        CollaboratorRoles::Editor,
+    )
+    .await?;
+
+    let res = state
+        .db_pool
+        .get_conn()
+        .transaction::<_, BoxedAppError, _>(|conn| {
+            let res = diesel::delete(
+                workout_blocks.filter(id.eq(block_id).and(workout_id.eq(path_workout_id))),
+            )
+            .execute(conn)?;
+
+            if res > 0 {
+                record_workout_program_revision(conn, path_workout_id, req_user_id)?;
+            }
+
+            Ok(res)
+        })?;
+
+    Ok(Json(serde_json::json!({"deleted": res})))
+}
@@ -0,0 +1,361 @@
+use crate::{
+    db::{
+        catalog::visible_catalog_ids,
+        models::{
+            exercise_catalog::{
+                catalog_slug, CatalogExercise, CatalogListingParams, NewCatalogExercise,
+                PatchCatalogExercise,
+            },
+            taxonomy::{CatalogMuscle, Equipment, SetCatalogMuscles, SetEquipment},
+            user::UserType,
+        },
+        taxonomy::{
+            guard_equipment_ids, guard_muscle_ids, link_catalog_from_text, load_catalog_equipment,
+            load_catalog_muscles, replace_catalog_equipment, replace_catalog_muscles,
+        },
+        users::get_user,
+        DbConnection,
+    },
+    error::{bad_request, unauthorized, BoxedAppError},
+    pagination::*,
+    server::AppState,
+    types::AppResult,
+    util::{
+        contains_pattern,
+        extractors::{JsonExtractor, Path, QueryExtractor, UserIdExtractor},
+    },
+};
+use axum::{extract::State, routing::get, Json, Router};
+use diesel::{
+    insert_into,
+    prelude::*,
+    result::{DatabaseErrorKind, Error as DieselError},
+};
+use std::sync::Arc;
+
+/// Everyone can browse the library (entries without an owner), their own entries and the ones used
+/// by the exercises they can read. Trainers add their own entries, admins curate the library and
+/// can read and edit any entry.
+pub fn catalog_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_catalog).post(create_catalog_entry))
+        .route(
+            "/:entry_id",
+            get(get_catalog_entry)
+                .patch(update_catalog_entry)
+                .delete(delete_catalog_entry),
+        )
+        .route(
+            "/:entry_id/equipment",
+            get(get_catalog_equipment).put(set_catalog_equipment),
+        )
+        .route(
+            "/:entry_id/muscles",
+            get(get_catalog_muscles).put(set_catalog_muscles),
+        )
+}
+
+async fn list_catalog(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    params: QueryExtractor<CatalogListingParams>,
+    pagination: QueryExtractor<PaginationParams>,
+) -> AppResult<Json<PaginatedResponse<CatalogExercise>>> {
+    use crate::schema::exercise_catalog::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let mut base = exercise_catalog.into_boxed();
+
+    if !get_user(req_user_id, &mut conn)?.is_admin {
+        base = base.filter(id.eq_any(visible_catalog_ids(req_user_id)));
+    }
+
+    if let Some(search) = params.0.search.as_deref().filter(|s| !s.trim().is_empty()) {
+        let pattern = contains_pattern(search);
+
+        base = base.filter(
+            name.ilike(pattern.clone())
+                .or(equipment.ilike(pattern.clone()))
+                .or(muscle_groups.ilike(pattern)),
+        );
+    }
+
+    if let Some(owner) = params.0.owner {
+        base = base.filter(owner_id.eq(owner));
+    }
+
+    match params.0.library {
+        Some(true) => base = base.filter(owner_id.is_null()),
+        Some(false) => base = base.filter(owner_id.is_not_null()),
+        None => {}
+    }
+
+    let data: Paginated<CatalogExercise> = base
+        .order_by((name.asc(), id.asc()))
+        .select(CatalogExercise::as_select())
+        .pages_pagination(PaginationOptions::new(pagination.0)?)
+        .load(&mut conn)?;
+
+    let total = data.total();
+
+    Ok(Json(PaginatedResponse::new(
+        data.into_iter().collect(),
+        total,
+    )))
+}
+
+async fn get_catalog_entry(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(entry_id): Path<uuid::Uuid>,
+) -> AppResult<Json<CatalogExercise>> {
+    use crate::schema::exercise_catalog::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_catalog_viewer(&mut conn, req_user_id, entry_id)?;
+
+    let res = exercise_catalog
+        .find(entry_id)
+        .select(CatalogExercise::as_select())
+        .first(&mut conn)?;
+
+    Ok(Json(res))
+}
+
+/// Entries created by admins go into the library, the ones created by trainers are theirs.
+///
+/// The muscles and equipment named in `muscle_groups` and `equipment` are linked to the new entry,
+/// the first muscle group is the primary one. Later changes go through the muscles and equipment
+/// endpoints of the entry.
+async fn create_catalog_entry(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    JsonExtractor(body): JsonExtractor<NewCatalogExercise>,
+) -> AppResult<Json<CatalogExercise>> {
+    use crate::schema::exercise_catalog::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let user = get_user(req_user_id, &mut conn)?;
+
+    if !user.is_admin && user.user_type != UserType::Trainer {
+        return Err(unauthorized());
+    }
+
+    body.validate().map_err(bad_request)?;
+
+    let entry_owner = (!user.is_admin).then_some(user.id);
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let res: CatalogExercise = insert_into(exercise_catalog)
+            .values((
+                NewCatalogExercise {
+                    name: body.name.trim().to_string(),
+                    ..body
+                },
+                owner_id.eq(entry_owner),
+                slug.eq(catalog_slug(&body.name)),
+            ))
+            .returning(CatalogExercise::as_returning())
+            .get_result(conn)
+            .map_err(duplicate_entry_error)?;
+
+        link_catalog_from_text(conn, res.id, &res.muscle_groups, &res.equipment)?;
+
+        Ok(res)
+    })?;
+
+    Ok(Json(res))
+}
+
+async fn update_catalog_entry(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(entry_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<PatchCatalogExercise>,
+) -> AppResult<Json<CatalogExercise>> {
+    use crate::schema::exercise_catalog::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_catalog_editor(&mut conn, req_user_id, entry_id)?;
+
+    body.validate().map_err(bad_request)?;
+
+    let body = PatchCatalogExercise {
+        name: body.name.as_deref().map(|n| n.trim().to_string()),
+        ..body
+    };
+
+    let res = diesel::update(exercise_catalog.find(entry_id))
+        .set(&body)
+        .returning(CatalogExercise::as_returning())
+        .get_result(&mut conn)?;
+
+    Ok(Json(res))
+}
+
+/// Entries can only be deleted once no exercise refers to them anymore, archived ones included.
+async fn delete_catalog_entry(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(entry_id): Path<uuid::Uuid>,
+) -> AppResult<Json<serde_json::Value>> {
+    use crate::schema::{exercise_catalog::dsl::*, exercises::dsl as e_dsl};
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_catalog_editor(&mut conn, req_user_id, entry_id)?;
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let references: i64 = e_dsl::exercises
+            .filter(e_dsl::catalog_id.eq(entry_id))
+            .count()
+            .get_result(conn)?;
+
+        if references > 0 {
+            return Err(bad_request(format!(
+                "The entry is still used by {references} exercise(s)."
+            )));
+        }
+
+        Ok(diesel::delete(exercise_catalog.find(entry_id)).execute(conn)?)
+    })?;
+
+    Ok(Json(serde_json::json!({"deleted": res})))
+}
+
+async fn get_catalog_muscles(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(entry_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Vec<CatalogMuscle>>> {
+    let mut conn = state.db_pool.get_conn();
+
+    guard_catalog_viewer(&mut conn, req_user_id, entry_id)?;
+
+    let res = load_catalog_muscles(&mut conn, entry_id)?;
+
+    Ok(Json(res))
+}
+
+/// Replaces the primary and secondary muscles of the entry.
+async fn set_catalog_muscles(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(entry_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<SetCatalogMuscles>,
+) -> AppResult<Json<Vec<CatalogMuscle>>> {
+    let mut conn = state.db_pool.get_conn();
+
+    guard_catalog_editor(&mut conn, req_user_id, entry_id)?;
+    guard_muscle_ids(&mut conn, &body)?;
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        replace_catalog_muscles(conn, entry_id, &body)?;
+
+        Ok(load_catalog_muscles(conn, entry_id)?)
+    })?;
+
+    Ok(Json(res))
+}
+
+async fn get_catalog_equipment(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(entry_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Vec<Equipment>>> {
+    let mut conn = state.db_pool.get_conn();
+
+    guard_catalog_viewer(&mut conn, req_user_id, entry_id)?;
+
+    let res = load_catalog_equipment(&mut conn, entry_id)?;
+
+    Ok(Json(res))
+}
+
+/// Replaces the equipment the entry needs, an empty list means bodyweight only.
+async fn set_catalog_equipment(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(entry_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<SetEquipment>,
+) -> AppResult<Json<Vec<Equipment>>> {
+    let mut conn = state.db_pool.get_conn();
+
+    guard_catalog_editor(&mut conn, req_user_id, entry_id)?;
+    guard_equipment_ids(&mut conn, &body.equipment_ids)?;
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        replace_catalog_equipment(conn, entry_id, &body.equipment_ids)?;
+
+        Ok(load_catalog_equipment(conn, entry_id)?)
+    })?;
+
+    Ok(Json(res))
+}
+
+/// Admins can read every entry, everyone else the ones of `visible_catalog_ids`.
+fn guard_catalog_viewer(
+    conn: &mut DbConnection,
+    req_user_id: uuid::Uuid,
+    entry_id: uuid::Uuid,
+) -> AppResult<()> {
+    use crate::schema::exercise_catalog::dsl::*;
+
+    let entry_owner: Option<uuid::Uuid> = exercise_catalog
+        .find(entry_id)
+        .select(owner_id)
+        .first(conn)?;
+
+    if entry_owner.is_none() || entry_owner == Some(req_user_id) {
+        return Ok(());
+    }
+
+    let visible: i64 = exercise_catalog
+        .filter(
+            id.eq(entry_id)
+                .and(id.eq_any(visible_catalog_ids(req_user_id))),
+        )
+        .count()
+        .get_result(conn)?;
+
+    if visible > 0 || get_user(req_user_id, conn)?.is_admin {
+        return Ok(());
+    }
+
+    Err(unauthorized())
+}
+
+/// Admins can edit every entry, trainers only their own.
+fn guard_catalog_editor(
+    conn: &mut DbConnection,
+    req_user_id: uuid::Uuid,
+    entry_id: uuid::Uuid,
+) -> AppResult<()> {
+    use crate::schema::exercise_catalog::dsl::*;
+
+    let entry_owner: Option<uuid::Uuid> = exercise_catalog
+        .find(entry_id)
+        .select(owner_id)
+        .first(conn)?;
+
+    if entry_owner == Some(req_user_id) || get_user(req_user_id, conn)?.is_admin {
+        return Ok(());
+    }
+
+    Err(unauthorized())
+}
+
+/// Slugs are unique per owner, the library counting as one, surface that as a 400 instead of a
+/// 500.
+fn duplicate_entry_error(err: DieselError) -> BoxedAppError {
+    match err {
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => bad_request(
+            "The owner of the entry already has an exercise with this name in the catalog.",
+        ),
+        _ => err.into(),
+    }
+}
@@ -1,74 +1,97 @@
 use crate::{
-    db::models::certification::{Certification, NewCertification},
-    error::api_error,
+    db::{
+        models::certification::{Certification, NewCertification, PatchCertification},
+        DbConnection,
+    },
+    error::{bad_request, unauthorized, BoxedAppError},
+    pagination::*,
     server::AppState,
-    types::{self, AppResult},
-    util::extractors::{JsonExtractor, QueryHmExt, UserIdExtractor},
+    types::AppResult,
+    util::extractors::{JsonExtractor, Path, QueryExtractor, QueryHmExt, UserIdExtractor},
 };
 use axum::{extract::State, routing::get, Json};
-use diesel::{insert_into, prelude::*};
+use diesel::{
+    insert_into,
+    prelude::*,
+    result::{DatabaseErrorKind, Error as DieselError},
+};
 use std::sync::Arc;
 
 pub fn certification_routes() -> axum::Router<Arc<AppState>> {
-    axum::Router::new().route(
-        "/",
-        get(get_certifications)
-            .post(create_certification)
-            .delete(delete_certifications),
-    )
+    axum::Router::new()
+        .route(
+            "/",
+            get(list_certifications)
+                .post(create_certification)
+                .put(replace_certifications),
+        )
+        .route(
+            "/:certification_id",
+            get(get_certification)
+                .patch(update_certification)
+                .delete(delete_certification),
+        )
 }
 
-async fn get_certifications(
-    hm: QueryHmExt,
+async fn list_certifications(
     State(state): State<Arc<AppState>>,
-) -> types::DBResult<Vec<Certification>> {
-    use crate::schema::{certifications::dsl as cert_dsl, users::dsl::*};
+    hm: QueryHmExt,
+    pagination: QueryExtractor<PaginationParams>,
+) -> AppResult<Json<PaginatedResponse<Certification>>> {
+    use crate::schema::{certifications::dsl as cert_dsl, users::dsl as user_dsl};
 
-    let mut exp = cert_dsl::certifications.into_boxed();
+    let mut conn = state.db_pool.get_conn();
 
-    let name_query_param = hm.0.get("user").map(|val| val.as_str());
+    let mut base = cert_dsl::certifications.into_boxed();
 
-    exp = match name_query_param {
-        Some(val) => {
-            let db_user_id = users
-                .select(id)
-                .filter(user_name.eq(val))
-                .first::<uuid::Uuid>(&mut state.db_pool.get_conn())
-                .map_err(api_error)?;
+    // `user` can be either the username or the id of the user
+    if let Some(val) = hm.0.get("user") {
+        let db_user_id = match uuid::Uuid::parse_str(val) {
+            Ok(u_id) => u_id,
+            Err(_) => user_dsl::users
+                .filter(user_dsl::user_name.eq(val))
+                .select(user_dsl::id)
+                .first::<uuid::Uuid>(&mut conn)?,
+        };
 
-            exp.filter(cert_dsl::user_id.eq(Some(db_user_id)))
-        }
-        None => exp,
-    };
+        base = base.filter(cert_dsl::user_id.eq(db_user_id));
+    }
 
-    let res = exp
-        .load::<Certification>(&mut state.db_pool.get_conn())
-        .map_err(api_error)?;
+    let query = base
+        .order_by(cert_dsl::created_at.desc())
+        .select(Certification::as_select())
+        .pages_pagination(PaginationOptions::new(pagination.0)?);
 
-    Ok(Json(res))
+    let data: Paginated<Certification> = query.load(&mut conn)?;
+
+    Ok(Json(data.into()))
 }
 
-async fn create_certification(
+async fn get_certification(
     UserIdExtractor(u_id): UserIdExtractor,
     State(state): State<Arc<AppState>>,
-    JsonExtractor(body): JsonExtractor<NewCertification>,
-) -> types::DBResult<Certification> {
+    Path(certification_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Certification>> {
     use crate::schema::certifications::dsl::*;
 
-    // NOTE: This needs to be properly handled on frontend, but for now
-    // I am just going to delete any name dupes so I can do a simple POST to
-    // handle ALL changes in cert list
-    //
     let mut conn = state.db_pool.get_conn();
 
-    let existing_name = certifications
-        .filter(name.eq(body.name.clone()).and(user_id.eq(u_id)))
-        .select(id)
-        .first::<uuid::Uuid>(&mut conn);
+    guard_certification_owner(u_id, certification_id, &mut conn)?;
 
-    if let Ok(existing_cert_id) = existing_name {
-        let _ = diesel::delete(certifications.filter(id.eq(existing_cert_id))).execute(&mut conn);
-    }
+    let res = certifications
+        .filter(id.eq(certification_id))
+        .select(Certification::as_select())
+        .first(&mut conn)?;
+
+    Ok(Json(res))
+}
+
+async fn create_certification(
+    UserIdExtractor(u_id): UserIdExtractor,
+    State(state): State<Arc<AppState>>,
+    JsonExtractor(body): JsonExtractor<NewCertification>,
+) -> AppResult<Json<Certification>> {
+    use crate::schema::certifications::dsl::*;
 
     let val = NewCertification {
         user_id: Some(u_id),
@@ -79,19 +102,115 @@ async fn create_certification(
         .values(&val)
         .returning(Certification::as_returning())
         .get_result(&mut state.db_pool.get_conn())
-        .map_err(api_error)?;
+        .map_err(duplicate_name_error)?;
 
     Ok(Json(cert))
 }
 
-async fn delete_certifications(
+/// Replaces ALL of the requesting user's certifications with the ones in the body.
+///
+/// Runs in a single transaction, so if any of the new certifications fail to insert the
+/// existing list is left untouched.
+async fn replace_certifications(
+    UserIdExtractor(u_id): UserIdExtractor,
     State(state): State<Arc<AppState>>,
+    JsonExtractor(body): JsonExtractor<Vec<NewCertification>>,
+) -> AppResult<Json<Vec<Certification>>> {
+    use crate::schema::certifications::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let vals = body
+        .into_iter()
+        .map(|cert| NewCertification {
+            user_id: Some(u_id),
+            ..cert
+        })
+        .collect::<Vec<_>>();
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        diesel::delete(certifications.filter(user_id.eq(u_id))).execute(conn)?;
+
+        let created = insert_into(certifications)
+            .values(&vals)
+            .returning(Certification::as_returning())
+            .get_results(conn)
+            .map_err(duplicate_name_error)?;
+
+        Ok(created)
+    })?;
+
+    Ok(Json(res))
+}
+
+async fn update_certification(
+    UserIdExtractor(u_id): UserIdExtractor,
+    State(state): State<Arc<AppState>>,
+    Path(certification_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<PatchCertification>,
+) -> AppResult<Json<Certification>> {
+    use crate::schema::certifications::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_certification_owner(u_id, certification_id, &mut conn)?;
+
+    if body.is_empty() {
+        return Err(bad_request("Nothing to update."));
+    }
+
+    let res = diesel::update(certifications)
+        .filter(id.eq(certification_id))
+        .set(body)
+        .returning(Certification::as_returning())
+        .get_result(&mut conn)
+        .map_err(duplicate_name_error)?;
+
+    Ok(Json(res))
+}
+
+async fn delete_certification(
     UserIdExtractor(u_id): UserIdExtractor,
+    State(state): State<Arc<AppState>>,
+    Path(certification_id): Path<uuid::Uuid>,
 ) -> AppResult<Json<serde_json::Value>> {
     use crate::schema::certifications::dsl::*;
 
     let mut conn = state.db_pool.get_conn();
-    let res: usize = diesel::delete(certifications.filter(user_id.eq(u_id))).execute(&mut conn)?;
+
+    guard_certification_owner(u_id, certification_id, &mut conn)?;
+
+    let res: usize =
+        diesel::delete(certifications.filter(id.eq(certification_id))).execute(&mut conn)?;
 
     Ok(Json(serde_json::json!({"deleted": res})))
 }
+
+fn guard_certification_owner(
+    req_user_id: uuid::Uuid,
+    certification_id: uuid::Uuid,
+    conn: &mut DbConnection,
+) -> AppResult<()> {
+    use crate::schema::certifications::dsl::*;
+
+    let cert_owner: Option<uuid::Uuid> = certifications
+        .filter(id.eq(certification_id))
+        .select(user_id)
+        .first(conn)?;
+
+    if cert_owner != Some(req_user_id) {
+        return Err(unauthorized());
+    }
+
+    Ok(())
+}
+
+/// Certifications are unique per (user, name), surface that as a 400 instead of a 500.
+fn duplicate_name_error(err: DieselError) -> BoxedAppError {
+    match err {
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            bad_request("A certification with this name already exists.")
+        }
+        _ => err.into(),
+    }
+}
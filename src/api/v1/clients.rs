@@ -1,14 +1,21 @@
 use crate::{
     db::{
+        collaborators::client_role,
         models::{
             client::{Client, ClientWithUser, InviteStates, NewClient, PatchClient},
             client_form::{ClientForm, NewClientForm},
+            collaborator::{
+                ClientCollaborator, CollaboratorRoles, CollaboratorWithUser, NewCollaborator,
+            },
             notification::NewNotification,
+            taxonomy::{Equipment, SetEquipment},
             user::{PublicUser, User, PUBLIC_USER_COLUMNS},
         },
+        reminders::ATTENTION_STATUS,
+        taxonomy::{guard_equipment_ids, load_client_equipment, replace_client_equipment},
         users::guard_admin,
     },
-    error::{bad_request, unauthorized},
+    error::{bad_request, unauthorized, BoxedAppError},
     pagination::*,
     server::AppState,
     types::AppResult,
@@ -38,6 +45,22 @@ pub fn client_routes() -> Router<Arc<AppState>> {
             "/:client_id/forms",
             post(create_client_form).get(get_client_form),
         )
+        .route(
+            "/:client_id/calendar",
+            post(create_calendar_token).delete(delete_calendar_token),
+        )
+        .route(
+            "/:client_id/collaborators",
+            get(list_client_collaborators).post(add_client_collaborator),
+        )
+        .route(
+            "/:client_id/collaborators/:user_id",
+            delete(remove_client_collaborator),
+        )
+        .route(
+            "/:client_id/equipment",
+            get(get_client_equipment).put(set_client_equipment),
+        )
         .route("/summary", get(get_clients_summary))
         .route("/invite", post(invite_client))
 }
@@ -48,7 +71,8 @@ pub async fn list_clients(
     pagination: QueryExtractor<PaginationParams>,
 ) -> AppResult<Json<PaginatedResponse<ClientWithUser>>> {
     use crate::schema::{
-        clients as base_clients_schema, clients::dsl as client_dsl, users::dsl as user_dsl,
+        client_collaborators::dsl as cc_dsl, clients as base_clients_schema,
+        clients::dsl as client_dsl, users::dsl as user_dsl,
     };
     let conn = &mut state.db_pool.get_conn();
 
@@ -56,9 +80,18 @@ pub async fn list_clients(
 
     let query = client_dsl::clients.into_boxed();
 
+    // the trainer's own clients and the ones other trainers shared with them
+    let shared_client_ids = cc_dsl::client_collaborators
+        .filter(cc_dsl::user_id.eq(user_id))
+        .select(cc_dsl::client_id);
+
     let query = query
         .order(client_dsl::created_at.desc())
-        .filter(client_dsl::trainer_id.eq(user_id))
+        .filter(
+            client_dsl::trainer_id
+                .eq(user_id)
+                .or(client_dsl::id.eq_any(shared_client_ids)),
+        )
         .inner_join(user_dsl::users.on(client_dsl::user_id.eq(user_dsl::id.nullable())))
         .select((base_clients_schema::all_columns, PUBLIC_USER_COLUMNS))
         .pages_pagination(PaginationOptions::new(page_info)?);
@@ -129,7 +162,7 @@ pub async fn get_clients_summary(
     let attention_needed: i64 = client_dsl::clients
         .filter(
             client_dsl::status
-                .eq("attention")
+                .eq(ATTENTION_STATUS)
                 .and(client_dsl::trainer_id.eq(user_id)),
         )
         .count()
@@ -376,7 +409,10 @@ async fn get_client_form(
     // client_id from path must match the client's id that the user belongs to, only the user should
     // create forms for their own client.
 
-    if path_client.user_id != Some(req_user_id) && path_client.trainer_id != Some(req_user_id) {
+    if path_client.user_id != Some(req_user_id)
+        && path_client.trainer_id != Some(req_user_id)
+        && client_role(&mut conn, req_user_id, path_client.id)?.is_none()
+    {
         return Err(unauthorized());
     }
 
@@ -388,3 +424,259 @@ async fn get_client_form(
 
     Ok(Json(res))
 }
+
+async fn get_client_equipment(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_client_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Vec<Equipment>>> {
+    use crate::schema::clients::dsl as client_dsl;
+
+    let mut conn = state.db_pool.get_conn();
+    let path_client: Client = client_dsl::clients
+        .filter(client_dsl::id.eq(path_client_id))
+        .select(Client::as_select())
+        .first(&mut conn)?;
+
+    if path_client.user_id != Some(req_user_id)
+        && path_client.trainer_id != Some(req_user_id)
+        && client_role(&mut conn, req_user_id, path_client.id)?.is_none()
+    {
+        return Err(unauthorized());
+    }
+
+    let res = load_client_equipment(&mut conn, path_client.id)?;
+
+    Ok(Json(res))
+}
+
+/// Replaces the equipment the client has, exercise substitutes are only suggested within it. An
+/// empty list means bodyweight only, clients whose equipment was never set aren't filtered.
+async fn set_client_equipment(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_client_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<SetEquipment>,
+) -> AppResult<Json<Vec<Equipment>>> {
+    use crate::schema::clients::dsl as client_dsl;
+
+    let mut conn = state.db_pool.get_conn();
+    let path_client: Client = client_dsl::clients
+        .filter(client_dsl::id.eq(path_client_id))
+        .select(Client::as_select())
+        .first(&mut conn)?;
+
+    if path_client.user_id != Some(req_user_id)
+        && path_client.trainer_id != Some(req_user_id)
+        && client_role(&mut conn, req_user_id, path_client.id)?
+            .is_none_or(|role| role < CollaboratorRoles::Editor)
+    {
+        return Err(unauthorized());
+    }
+
+    guard_equipment_ids(&mut conn, &body.equipment_ids)?;
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        replace_client_equipment(conn, path_client.id, &body.equipment_ids)?;
+
+        Ok(load_client_equipment(conn, path_client.id)?)
+    })?;
+
+    Ok(Json(res))
+}
+
+/// Creates (or rotates) the token used to subscribe to the client's workout calendar feed.
+async fn create_calendar_token(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_client_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Value>> {
+    use crate::schema::clients::dsl::*;
+    use rand::{distributions::Alphanumeric, Rng};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let path_client: Client = clients
+        .filter(id.eq(path_client_id))
+        .select(Client::as_select())
+        .first(&mut conn)?;
+
+    if path_client.user_id != Some(req_user_id) && path_client.trainer_id != Some(req_user_id) {
+        return Err(unauthorized());
+    }
+
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(48)
+        .map(char::from)
+        .collect();
+
+    diesel::update(clients.filter(id.eq(path_client.id)))
+        .set(calendar_token.eq(&token))
+        .execute(&mut conn)?;
+
+    Ok(Json(json!({
+        "token": token,
+        "path": format!("/feeds/calendar/{token}.ics"),
+    })))
+}
+
+async fn delete_calendar_token(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_client_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Value>> {
+    use crate::schema::clients::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let rows: usize = diesel::update(
+        clients.filter(
+            id.eq(path_client_id)
+                .and(user_id.eq(req_user_id).or(trainer_id.eq(req_user_id))),
+        ),
+    )
+    .set(calendar_token.eq(None::<String>))
+    .execute(&mut conn)?;
+
+    Ok(Json(json!({"deleted": rows})))
+}
+
+async fn list_client_collaborators(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_client_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Vec<CollaboratorWithUser<ClientCollaborator>>>> {
+    use crate::schema::{
+        client_collaborators::dsl as cc_dsl, clients::dsl as client_dsl, users::dsl as user_dsl,
+    };
+
+    let mut conn = state.db_pool.get_conn();
+
+    let path_client: Client = client_dsl::clients
+        .filter(client_dsl::id.eq(path_client_id))
+        .select(Client::as_select())
+        .first(&mut conn)?;
+
+    if path_client.trainer_id != Some(req_user_id)
+        && client_role(&mut conn, req_user_id, path_client.id)?.is_none()
+    {
+        return Err(unauthorized());
+    }
+
+    let res = cc_dsl::client_collaborators
+        .filter(cc_dsl::client_id.eq(path_client.id))
+        .inner_join(user_dsl::users.on(cc_dsl::user_id.eq(user_dsl::id)))
+        .order(cc_dsl::created_at.asc())
+        .select((ClientCollaborator::as_select(), PUBLIC_USER_COLUMNS))
+        .load::<(ClientCollaborator, PublicUser)>(&mut conn)?
+        .into_iter()
+        .map(|(collaborator, user)| CollaboratorWithUser { collaborator, user })
+        .collect();
+
+    Ok(Json(res))
+}
+
+/// Shares a client with another trainer, the grant applies to every program assigned to the
+/// client. Only the client's trainer can share them.
+async fn add_client_collaborator(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_client_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<NewCollaborator>,
+) -> AppResult<Json<CollaboratorWithUser<ClientCollaborator>>> {
+    use crate::schema::{
+        client_collaborators::dsl as cc_dsl, clients::dsl as client_dsl, users::dsl as user_dsl,
+    };
+
+    let mut conn = state.db_pool.get_conn();
+
+    let path_client: Client = client_dsl::clients
+        .filter(client_dsl::id.eq(path_client_id))
+        .select(Client::as_select())
+        .first(&mut conn)?;
+
+    if path_client.trainer_id != Some(req_user_id) {
+        return Err(unauthorized());
+    }
+
+    if body.user_id == req_user_id || path_client.user_id == Some(body.user_id) {
+        return Err(bad_request(
+            "Clients can only be shared with other trainers.",
+        ));
+    }
+
+    let user: PublicUser = user_dsl::users
+        .filter(user_dsl::id.eq(body.user_id))
+        .select(PUBLIC_USER_COLUMNS)
+        .first(&mut conn)?;
+
+    let collaborator = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let collaborator = insert_into(cc_dsl::client_collaborators)
+            .values((
+                cc_dsl::client_id.eq(path_client.id),
+                cc_dsl::user_id.eq(body.user_id),
+                cc_dsl::granted_by.eq(req_user_id),
+                cc_dsl::role.eq(body.role),
+            ))
+            .on_conflict((cc_dsl::client_id, cc_dsl::user_id))
+            .do_update()
+            .set((
+                cc_dsl::role.eq(body.role),
+                cc_dsl::granted_by.eq(req_user_id),
+            ))
+            .returning(ClientCollaborator::as_returning())
+            .get_result::<ClientCollaborator>(conn)?;
+
+        let d: serde_json::Value = serde_json::json!({
+            "client_id": path_client.id.to_string(),
+            "role": collaborator.role,
+        });
+
+        let new_noti = NewNotification::new(
+            req_user_id,
+            body.user_id,
+            "A client was shared with you".into(),
+            "You can now collaborate on one of your colleague's clients.".into(),
+            "client".into(),
+            "unread".into(),
+            Some(d),
+        );
+
+        new_noti.send(conn)?;
+
+        Ok(collaborator)
+    })?;
+
+    Ok(Json(CollaboratorWithUser { collaborator, user }))
+}
+
+/// The client's trainer can remove anyone, collaborators can remove themselves.
+async fn remove_client_collaborator(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path((path_client_id, path_user_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+) -> AppResult<Json<usize>> {
+    use crate::schema::{client_collaborators::dsl as cc_dsl, clients::dsl as client_dsl};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let path_client: Client = client_dsl::clients
+        .filter(client_dsl::id.eq(path_client_id))
+        .select(Client::as_select())
+        .first(&mut conn)?;
+
+    if path_client.trainer_id != Some(req_user_id) && path_user_id != req_user_id {
+        return Err(unauthorized());
+    }
+
+    let res = diesel::delete(cc_dsl::client_collaborators)
+        .filter(
+            cc_dsl::client_id
+                .eq(path_client.id)
+                .and(cc_dsl::user_id.eq(path_user_id)),
+        )
+        .execute(&mut conn)?;
+
+    Ok(Json(res))
+}
@@ -0,0 +1,335 @@
+use crate::{
+    api::v1::workouts::guard_workout_owner_or_program_client,
+    db::{
+        models::{
+            collaborator::CollaboratorRoles,
+            comment::{
+                parse_mentions, Comment, CommentListingParams, CommentThread, CommentWithAuthor,
+                NewComment, PatchComment, MAX_COMMENT_LENGTH,
+            },
+            notification::NewNotification,
+            user::{PublicUser, PUBLIC_USER_COLUMNS},
+        },
+        DbConnection,
+    },
+    error::{bad_request, unauthorized},
+    pagination::*,
+    server::AppState,
+    types::AppResult,
+    util::extractors::{JsonExtractor, Path, QueryExtractor, UserIdExtractor},
+};
+use axum::{extract::State, routing::patch, Json, Router};
+use diesel::{insert_into, prelude::*, update};
+use std::sync::Arc;
+
+// mentions are parsed lowercase, user names can be mixed case
+sql_function!(fn lower(x: diesel::sql_types::Text) -> diesel::sql_types::Text);
+
+pub fn comment_routes() -> Router<Arc<AppState>> {
+    Router::new().route("/:comment_id", patch(update_comment).delete(delete_comment))
+}
+
+/// Threads on the workout and its exercises, oldest first, with all of their replies.
+pub async fn list_workout_comments(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_workout_id): Path<uuid::Uuid>,
+    params: QueryExtractor<CommentListingParams>,
+    pagination: QueryExtractor<PaginationParams>,
+) -> AppResult<Json<PaginatedResponse<CommentThread>>> {
+    use crate::schema::{comments::dsl::*, users::dsl as u_dsl};
+
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        path_workout_id,
+        state.db_pool.get_conn(),
+        CollaboratorRoles::Viewer,
+    )
+    .await?;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let mut base = comments
+        .inner_join(u_dsl::users)
+        .filter(workout_id.eq(path_workout_id).and(parent_id.is_null()))
+        .into_boxed();
+
+    if let Some(exercise_filter) = params.0.exercise {
+        base = base.filter(exercise_id.eq(exercise_filter));
+    }
+
+    let data: Paginated<(Comment, PublicUser)> = base
+        .order_by(created_at.asc())
+        .select((crate::schema::comments::all_columns, PUBLIC_USER_COLUMNS))
+        .pages_pagination(PaginationOptions::new(pagination.0)?)
+        .load(&mut conn)?;
+
+    let total = data.total();
+    let (threads, authors): (Vec<Comment>, Vec<PublicUser>) = data.into_iter().unzip();
+
+    let replies: Vec<(Comment, PublicUser)> = Comment::belonging_to(&threads)
+        .inner_join(u_dsl::users)
+        .order_by(created_at.asc())
+        .select((Comment::as_select(), PUBLIC_USER_COLUMNS))
+        .load(&mut conn)?;
+
+    let grouped_replies = replies.grouped_by(&threads);
+
+    let res = threads
+        .into_iter()
+        .zip(authors)
+        .zip(grouped_replies)
+        .map(|((comment, author), replies)| CommentThread {
+            comment: CommentWithAuthor { comment, author },
+            replies: replies
+                .into_iter()
+                .map(|(comment, author)| CommentWithAuthor { comment, author })
+                .collect(),
+        })
+        .collect();
+
+    Ok(Json(PaginatedResponse::new(res, total)))
+}
+
+pub async fn create_workout_comment(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_workout_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<NewComment>,
+) -> AppResult<Json<CommentWithAuthor>> {
+    use crate::schema::{comments::dsl as c_dsl, exercises::dsl as e_dsl};
+
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        path_workout_id,
+        state.db_pool.get_conn(),
+        CollaboratorRoles::Viewer,
+    )
+    .await?;
+
+    let comment_body = validate_comment_body(&body.body)?;
+
+    let mut conn = state.db_pool.get_conn();
+
+    // replies go in the thread of the comment they answer, on the same exercise
+    let (thread_id, thread_exercise_id) = match body.parent_id {
+        Some(reply_to) => {
+            let parent: Comment = c_dsl::comments
+                .filter(
+                    c_dsl::id
+                        .eq(reply_to)
+                        .and(c_dsl::workout_id.eq(path_workout_id)),
+                )
+                .select(Comment::as_select())
+                .first(&mut conn)
+                .optional()?
+                .ok_or_else(|| bad_request("The comment isn't part of this workout."))?;
+
+            (
+                Some(parent.parent_id.unwrap_or(parent.id)),
+                parent.exercise_id,
+            )
+        }
+        None => (None, body.exercise_id),
+    };
+
+    if let Some(thread_exercise_id) = thread_exercise_id {
+        let exercise_in_workout: bool = diesel::select(diesel::dsl::exists(
+            e_dsl::exercises.filter(
+                e_dsl::id
+                    .eq(thread_exercise_id)
+                    .and(e_dsl::workout_id.eq(path_workout_id)),
+            ),
+        ))
+        .get_result(&mut conn)?;
+
+        if !exercise_in_workout {
+            return Err(bad_request("The exercise isn't part of this workout."));
+        }
+    }
+
+    let comment: Comment = insert_into(c_dsl::comments)
+        .values((
+            c_dsl::workout_id.eq(path_workout_id),
+            c_dsl::exercise_id.eq(thread_exercise_id),
+            c_dsl::parent_id.eq(thread_id),
+            c_dsl::author_id.eq(req_user_id),
+            c_dsl::body.eq(comment_body),
+        ))
+        .returning(Comment::as_returning())
+        .get_result(&mut conn)?;
+
+    notify_mentions(&state, &comment, &[]).await?;
+
+    Ok(Json(with_author(&mut conn, comment)?))
+}
+
+/// Only the author can edit a comment. Users mentioned for the first time get notified.
+async fn update_comment(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(comment_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<PatchComment>,
+) -> AppResult<Json<CommentWithAuthor>> {
+    use crate::schema::comments::dsl as c_dsl;
+
+    let comment_body = validate_comment_body(&body.body)?;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let existing = load_comment(&mut conn, comment_id)?;
+
+    if existing.author_id != req_user_id || existing.deleted_at.is_some() {
+        return Err(unauthorized());
+    }
+
+    let comment: Comment = update(c_dsl::comments.filter(c_dsl::id.eq(comment_id)))
+        .set((
+            c_dsl::body.eq(comment_body),
+            c_dsl::edited_at.eq(chrono::Utc::now()),
+        ))
+        .returning(Comment::as_returning())
+        .get_result(&mut conn)?;
+
+    notify_mentions(&state, &comment, &parse_mentions(&existing.body)).await?;
+
+    Ok(Json(with_author(&mut conn, comment)?))
+}
+
+/// Clears the comment but keeps it in place so its replies still have a thread. The author and
+/// the owner of the workout can delete comments.
+async fn delete_comment(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(comment_id): Path<uuid::Uuid>,
+) -> AppResult<Json<serde_json::Value>> {
+    use crate::schema::{comments::dsl as c_dsl, workouts::dsl as w_dsl};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let existing = load_comment(&mut conn, comment_id)?;
+
+    let workout_owner: Option<uuid::Uuid> = w_dsl::workouts
+        .filter(w_dsl::id.eq(existing.workout_id))
+        .select(w_dsl::owner_id)
+        .first(&mut conn)?;
+
+    if existing.author_id != req_user_id && workout_owner != Some(req_user_id) {
+        return Err(unauthorized());
+    }
+
+    let res: usize =
+        update(c_dsl::comments.filter(c_dsl::id.eq(comment_id).and(c_dsl::deleted_at.is_null())))
+            .set((c_dsl::body.eq(""), c_dsl::deleted_at.eq(chrono::Utc::now())))
+            .execute(&mut conn)?;
+
+    Ok(Json(serde_json::json!({"deleted": res})))
+}
+
+fn validate_comment_body(raw: &str) -> AppResult<String> {
+    let comment_body = raw.trim();
+
+    if comment_body.is_empty() {
+        return Err(bad_request("Comments can't be empty."));
+    }
+
+    if comment_body.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(bad_request(format!(
+            "Comments can be at most {MAX_COMMENT_LENGTH} characters long."
+        )));
+    }
+
+    Ok(comment_body.to_string())
+}
+
+fn load_comment(conn: &mut DbConnection, comment_id: uuid::Uuid) -> QueryResult<Comment> {
+    use crate::schema::comments::dsl::*;
+
+    comments
+        .filter(id.eq(comment_id))
+        .select(Comment::as_select())
+        .first(conn)
+}
+
+fn with_author(conn: &mut DbConnection, comment: Comment) -> QueryResult<CommentWithAuthor> {
+    use crate::schema::users::dsl::*;
+
+    let author: PublicUser = users
+        .filter(id.eq(comment.author_id))
+        .select(PUBLIC_USER_COLUMNS)
+        .first(conn)?;
+
+    Ok(CommentWithAuthor { comment, author })
+}
+
+/// Notifies the users mentioned in the comment that weren't in `already_mentioned`. Mentions of
+/// users who can't see the workout are ignored.
+async fn notify_mentions(
+    state: &AppState,
+    comment: &Comment,
+    already_mentioned: &[String],
+) -> AppResult<()> {
+    use crate::schema::users::dsl::*;
+
+    let new_mentions: Vec<String> = parse_mentions(&comment.body)
+        .into_iter()
+        .filter(|m| !already_mentioned.contains(m))
+        .collect();
+
+    if new_mentions.is_empty() {
+        return Ok(());
+    }
+
+    let mut conn = state.db_pool.get_conn();
+
+    let mentioned: Vec<(uuid::Uuid, String)> = users
+        .filter(
+            lower(user_name)
+                .eq_any(new_mentions)
+                .and(id.ne(comment.author_id)),
+        )
+        .select((id, user_name))
+        .load(&mut conn)?;
+
+    let author_name: String = users
+        .filter(id.eq(comment.author_id))
+        .select(user_name)
+        .first(&mut conn)?;
+
+    for (mentioned_id, _) in mentioned {
+        let can_see_workout = guard_workout_owner_or_program_client(
+            mentioned_id,
+            comment.workout_id,
+            state.db_pool.get_conn(),
+            CollaboratorRoles::Viewer,
+        )
+        .await
+        .is_ok();
+
+        if !can_see_workout {
+            continue;
+        }
+
+        let d = serde_json::json!({
+            "comment_id": comment.id,
+            "workout_id": comment.workout_id,
+            "exercise_id": comment.exercise_id,
+        });
+
+        NewNotification::new(
+            comment.author_id,
+            mentioned_id,
+            "You were mentioned in a comment".into(),
+            format!("@{author_name}: {}", comment.body)
+                .chars()
+                .take(255)
+                .collect(),
+            "comment".into(),
+            "unread".into(),
+            Some(d),
+        )
+        .send(&mut conn)?;
+    }
+
+    Ok(())
+}
@@ -1,81 +1,233 @@
 use crate::{
-    db::models::exercise::{Exercise, NewExercise},
-    error::custom,
+    api::v1::substitutions::list_exercise_substitutes,
+    db::{
+        catalog::with_catalog,
+        collaborators::{can_view_program, visible_workout_ids},
+        models::{
+            exercise::{Exercise, ExerciseWithCatalog, NewExercise},
+            program::Program,
+            taxonomy::MuscleRoles,
+            workout::{Workout, WorkoutWithExercises},
+            IntensityChoices,
+        },
+        programs::record_workout_program_revision,
+        workouts::with_exercises,
+        DbConnection,
+    },
+    error::{bad_request, unauthorized, BoxedAppError},
+    pagination::*,
     server::AppState,
     types::AppResult,
     util::{
-        extractors::{JsonExtractor, Path, QueryHmExt, UserIdExtractor},
-        format_slug,
+        contains_pattern,
+        extractors::{JsonExtractor, Path, QueryExtractor, UserIdExtractor},
+        find_duplicate, format_slug, parse_slugs,
     },
 };
-use axum::{extract::State, routing::get, Json, Router};
-use diesel::{insert_into, prelude::*, update};
-use http::StatusCode;
-use std::{str::FromStr, sync::Arc};
+use axum::{
+    extract::State,
+    routing::{get, post, put},
+    Json, Router,
+};
+use diesel::{dsl::not, insert_into, prelude::*, update};
+use serde::Deserialize;
+use std::sync::Arc;
 
 pub fn exercise_routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(list_exercises).post(create_exercise))
+        .route("/order", put(reorder_exercises))
         .route(
             "/:exercise_id",
             get(get_exercise)
                 .delete(delete_exercise)
                 .put(update_exercise),
         )
+        .route("/:exercise_id/restore", post(restore_exercise))
+        .route("/:exercise_id/substitutes", get(list_exercise_substitutes))
 }
 
+/// Readable by the users who can read its workout, see `can_view_program`. Exercises
+/// outside of a workout are only readable by their owner.
 async fn get_exercise(
     State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
     Path(exercise_id): Path<uuid::Uuid>,
-) -> AppResult<Json<Exercise>> {
-    use crate::schema::exercises::dsl::*;
+) -> AppResult<Json<ExerciseWithCatalog>> {
+    use crate::schema::{exercises::dsl::*, programs::dsl as p_dsl, workouts::dsl as w_dsl};
+
+    let mut conn = state.db_pool.get_conn();
 
     let res = exercises
-        .select(Exercise::as_select())
+        .inner_join(crate::schema::exercise_catalog::table)
+        .select(ExerciseWithCatalog::as_select())
         .filter(id.eq(exercise_id))
-        .first::<Exercise>(&mut state.db_pool.get_conn())?;
+        .first::<ExerciseWithCatalog>(&mut conn)?;
+
+    if res.exercise.owner_id == Some(req_user_id) {
+        return Ok(Json(res));
+    }
+
+    let Some(exercise_workout_id) = res.exercise.workout_id else {
+        return Err(unauthorized());
+    };
+
+    let (workout_owner_id, workout_program_id): (Option<uuid::Uuid>, Option<uuid::Uuid>) =
+        w_dsl::workouts
+            .filter(w_dsl::id.eq(exercise_workout_id))
+            .select((w_dsl::owner_id, w_dsl::program_id))
+            .first(&mut conn)?;
+
+    if workout_owner_id == Some(req_user_id) {
+        return Ok(Json(res));
+    }
+
+    let Some(workout_program_id) = workout_program_id else {
+        return Err(unauthorized());
+    };
+
+    let program: Program = p_dsl::programs
+        .find(workout_program_id)
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    if !can_view_program(&mut conn, req_user_id, &program)? {
+        return Err(unauthorized());
+    }
 
     Ok(Json(res))
 }
 
+#[derive(Deserialize, Debug, Default)]
+struct ExerciseListingParams {
+    workout: Option<uuid::Uuid>,
+    owner: Option<uuid::Uuid>,
+    /// Matched against the name of the catalog entry
+    search: Option<String>,
+    intensity: Option<IntensityChoices>,
+    /// Comma separated muscle slugs
+    muscles: Option<String>,
+    /// `primary` or `secondary`, narrows down `muscles`
+    muscle_role: Option<String>,
+    /// Comma separated equipment slugs
+    equipment: Option<String>,
+    #[serde(default)]
+    include_archived: bool,
+}
+
+/// Exercises the user can read, the ones they own and the ones in the workouts of the programs
+/// they can read. Besides `workout`, `owner`, `search` and `intensity`, exercises can be filtered
+/// on the muscles and equipment of their catalog entry:
+///
+/// - `muscles=chest,triceps` keeps the exercises training any of the muscles, `muscle_role`
+///   (`primary` or `secondary`) only counts the muscles with that role
+/// - `equipment=barbell,bench` keeps the exercises that need nothing but that equipment, an empty
+///   `equipment=` only keeps bodyweight exercises
 async fn list_exercises(
     State(state): State<Arc<AppState>>,
-    hm: QueryHmExt,
-) -> AppResult<Json<Vec<Exercise>>> {
-    use crate::schema::exercises::dsl::*;
-    let mut base_q = exercises.into_boxed();
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    listing_params: QueryExtractor<ExerciseListingParams>,
+    pagination: QueryExtractor<PaginationParams>,
+) -> AppResult<Json<PaginatedResponse<ExerciseWithCatalog>>> {
+    use crate::schema::{
+        catalog_equipment, catalog_muscles, equipment as eq_table, exercise_catalog as ec,
+        exercises::dsl::*, muscles,
+    };
+
+    let params = listing_params.0;
+    let mut conn = state.db_pool.get_conn();
+
+    let mut base_q = exercises
+        .inner_join(ec::table)
+        .filter(
+            owner_id
+                .eq(req_user_id)
+                .or(workout_id.eq_any(visible_workout_ids(req_user_id))),
+        )
+        .into_boxed();
+
+    if let Some(workout_filter) = params.workout {
+        base_q = base_q.filter(workout_id.eq(workout_filter));
+    }
+
+    if let Some(owner) = params.owner {
+        base_q = base_q.filter(owner_id.eq(owner));
+    }
+
+    if let Some(search) = params.search.as_deref().filter(|s| !s.trim().is_empty()) {
+        base_q = base_q.filter(ec::name.ilike(contains_pattern(search)));
+    }
 
-    let workout_id_query =
-        hm.0.get("workout")
-            .map(|val| val.as_str())
-            .map(uuid::Uuid::from_str);
+    if let Some(exercise_intensity) = params.intensity {
+        base_q = base_q.filter(intensity.eq(exercise_intensity));
+    }
 
-    base_q = match workout_id_query {
-        Some(Ok(val)) => base_q.filter(workout_id.eq(val)),
-        Some(Err(e)) => {
-            return Err(custom(StatusCode::BAD_REQUEST, e.to_string()));
+    let muscle_role = match params.muscle_role.as_deref() {
+        Some("primary") => Some(MuscleRoles::Primary),
+        Some("secondary") => Some(MuscleRoles::Secondary),
+        Some(_) => {
+            return Err(bad_request("muscle_role must be primary or secondary."));
         }
-        None => base_q,
+        None => None,
     };
 
-    let res = base_q
-        .order(sequence.asc())
-        .select(Exercise::as_select())
-        .load::<Exercise>(&mut state.db_pool.get_conn())?;
+    if let Some(param) = params.muscles.as_deref() {
+        let mut trained = catalog_muscles::table
+            .inner_join(muscles::table)
+            .filter(muscles::slug.eq_any(parse_slugs(param)))
+            .select(catalog_muscles::catalog_id)
+            .into_boxed();
 
-    Ok(Json(res))
+        if let Some(role) = muscle_role {
+            trained = trained.filter(catalog_muscles::role.eq(role));
+        }
+
+        base_q = base_q.filter(catalog_id.eq_any(trained));
+    }
+
+    if let Some(param) = params.equipment.as_deref() {
+        let needs_other = catalog_equipment::table
+            .inner_join(eq_table::table)
+            .filter(eq_table::slug.ne_all(parse_slugs(param)))
+            .select(catalog_equipment::catalog_id);
+
+        base_q = base_q.filter(not(catalog_id.eq_any(needs_other)));
+    }
+
+    if !params.include_archived {
+        base_q = base_q.filter(archived_at.is_null());
+    }
+
+    // the id keeps the order stable across pages when the sequences tie
+    let data: Paginated<ExerciseWithCatalog> = base_q
+        .order((sequence.asc(), id.asc()))
+        .select(ExerciseWithCatalog::as_select())
+        .pages_pagination(PaginationOptions::new(pagination.0)?)
+        .load(&mut conn)?;
+
+    let total = data.total();
+
+    Ok(Json(PaginatedResponse::new(
+        data.into_iter().collect(),
+        total,
+    )))
 }
 
 async fn create_exercise(
     State(state): State<Arc<AppState>>,
     UserIdExtractor(u_id_ext): UserIdExtractor,
-    JsonExtractor(body): JsonExtractor<NewExercise>,
-) -> AppResult<Json<Exercise>> {
+    JsonExtractor(mut body): JsonExtractor<NewExercise>,
+) -> AppResult<Json<ExerciseWithCatalog>> {
     use crate::schema::exercises::dsl::*;
 
+    body.normalize().map_err(bad_request)?;
+
     let mut conn = state.db_pool.get_conn();
 
-    let mut new_slug = format_slug(body.name.clone());
+    let catalog_name = find_catalog_name(&mut conn, u_id_ext, body.catalog_id)?;
+    guard_block(&mut conn, body.block_id.flatten(), body.workout_id)?;
+
+    let mut new_slug = format_slug(catalog_name);
 
     // check for slug uniqueness
     let slug_count: i64 = exercises
@@ -87,14 +239,23 @@ async fn create_exercise(
         new_slug = format!("{}-{}", new_slug, &rand::random::<u32>().to_string());
     }
 
-    let res = insert_into(exercises)
-        .values((&body, owner_id.eq(u_id_ext), slug.eq(new_slug)))
-        .returning(Exercise::as_returning())
-        .get_result::<Exercise>(&mut conn)?;
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let res = insert_into(exercises)
+            .values((&body, owner_id.eq(u_id_ext), slug.eq(new_slug)))
+            .returning(Exercise::as_returning())
+            .get_result::<Exercise>(conn)?;
+
+        if let Some(wkt_id) = res.workout_id {
+            record_workout_program_revision(conn, wkt_id, u_id_ext)?;
+        }
+
+        Ok(with_catalog(conn, res)?)
+    })?;
 
     Ok(Json(res))
 }
 
+/// Archives the exercise until the retention job purges it.
 async fn delete_exercise(
     State(state): State<Arc<AppState>>,
     Path(exercise_id_to_delete): Path<uuid::Uuid>,
@@ -102,9 +263,55 @@ async fn delete_exercise(
 ) -> AppResult<Json<usize>> {
     use crate::schema::exercises::dsl::*;
 
-    let res = diesel::delete(exercises)
-        .filter(id.eq(exercise_id_to_delete).and(owner_id.eq(user_id_ext)))
-        .execute(&mut state.db_pool.get_conn())?;
+    let mut conn = state.db_pool.get_conn();
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let archived_from_workouts: Vec<Option<uuid::Uuid>> = update(exercises)
+            .filter(
+                id.eq(exercise_id_to_delete)
+                    .and(owner_id.eq(user_id_ext))
+                    .and(archived_at.is_null()),
+            )
+            .set(archived_at.eq(chrono::Utc::now()))
+            .returning(workout_id)
+            .get_results(conn)?;
+
+        for wkt_id in archived_from_workouts.iter().flatten() {
+            record_workout_program_revision(conn, *wkt_id, user_id_ext)?;
+        }
+
+        Ok(archived_from_workouts.len())
+    })?;
+
+    Ok(Json(res))
+}
+
+async fn restore_exercise(
+    State(state): State<Arc<AppState>>,
+    Path(exercise_id_to_restore): Path<uuid::Uuid>,
+    UserIdExtractor(user_id_ext): UserIdExtractor,
+) -> AppResult<Json<ExerciseWithCatalog>> {
+    use crate::schema::exercises::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let res = update(exercises)
+            .filter(
+                id.eq(exercise_id_to_restore)
+                    .and(owner_id.eq(user_id_ext))
+                    .and(archived_at.is_not_null()),
+            )
+            .set(archived_at.eq(None::<chrono::DateTime<chrono::Utc>>))
+            .returning(Exercise::as_returning())
+            .get_result::<Exercise>(conn)?;
+
+        if let Some(wkt_id) = res.workout_id {
+            record_workout_program_revision(conn, wkt_id, user_id_ext)?;
+        }
+
+        Ok(with_catalog(conn, res)?)
+    })?;
 
     Ok(Json(res))
 }
@@ -113,16 +320,252 @@ async fn update_exercise(
     State(state): State<Arc<AppState>>,
     UserIdExtractor(user_id_extracted): UserIdExtractor,
     Path(exercise_id): Path<uuid::Uuid>,
-    JsonExtractor(body): JsonExtractor<NewExercise>,
-) -> AppResult<Json<Exercise>> {
+    JsonExtractor(mut body): JsonExtractor<NewExercise>,
+) -> AppResult<Json<ExerciseWithCatalog>> {
     use crate::schema::exercises::dsl::*;
 
+    body.normalize().map_err(bad_request)?;
+
     let mut conn = state.db_pool.get_conn();
-    let res = update(exercises)
+
+    let (current_workout_id, current_block_id, current_catalog_id): (
+        Option<uuid::Uuid>,
+        Option<uuid::Uuid>,
+        uuid::Uuid,
+    ) = exercises
         .filter(owner_id.eq(user_id_extracted).and(id.eq(exercise_id)))
-        .set(body)
-        .returning(Exercise::as_returning())
-        .get_result::<Exercise>(&mut conn)?;
+        .select((workout_id, block_id, catalog_id))
+        .first(&mut conn)?;
+
+    // copies of shared programs keep the entries they were made with
+    if body.catalog_id != current_catalog_id {
+        find_catalog_name(&mut conn, user_id_extracted, body.catalog_id)?;
+    }
+
+    guard_block(
+        &mut conn,
+        body.block_id.unwrap_or(current_block_id),
+        body.workout_id.or(current_workout_id),
+    )?;
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let res = update(exercises)
+            .filter(owner_id.eq(user_id_extracted).and(id.eq(exercise_id)))
+            .set(body.changes())
+            .returning(Exercise::as_returning())
+            .get_result::<Exercise>(conn)?;
+
+        if let Some(wkt_id) = res.workout_id {
+            record_workout_program_revision(conn, wkt_id, user_id_extracted)?;
+        }
+
+        Ok(with_catalog(conn, res)?)
+    })?;
+
+    Ok(Json(res))
+}
+
+/// Name of the catalog entry an exercise is created or updated with.
+/// Exercises can use library entries and the user's own entries, not other trainers' ones.
+fn find_catalog_name(
+    conn: &mut DbConnection,
+    req_user_id: uuid::Uuid,
+    entry_id: uuid::Uuid,
+) -> AppResult<String> {
+    use crate::schema::exercise_catalog::dsl::*;
+
+    exercise_catalog
+        .find(entry_id)
+        .filter(owner_id.is_null().or(owner_id.eq(req_user_id)))
+        .select(name)
+        .first(conn)
+        .optional()?
+        .ok_or_else(|| bad_request("The catalog entry of the exercise does not exist."))
+}
+
+/// Exercises can only be grouped with the other exercises of their workout.
+fn guard_block(
+    conn: &mut DbConnection,
+    exercise_block_id: Option<uuid::Uuid>,
+    exercise_workout_id: Option<uuid::Uuid>,
+) -> AppResult<()> {
+    use crate::schema::workout_blocks::dsl::*;
+
+    let Some(body_block_id) = exercise_block_id else {
+        return Ok(());
+    };
+
+    let block_workout_id: Option<uuid::Uuid> = workout_blocks
+        .find(body_block_id)
+        .select(workout_id)
+        .first(conn)
+        .optional()?;
+
+    match block_workout_id {
+        Some(w_id) if exercise_workout_id == Some(w_id) => Ok(()),
+        _ => Err(bad_request(
+            "The block is not part of the exercise's workout.",
+        )),
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WorkoutExerciseOrder {
+    pub workout_id: uuid::Uuid,
+    pub exercises: Vec<uuid::Uuid>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ReorderExercises {
+    pub workouts: Vec<WorkoutExerciseOrder>,
+}
+
+/// Sets the full, ordered list of exercises for one or more workouts in a single transaction.
+///
+/// Exercises can be listed under a different workout than the one they are in to move them
+/// there. Every exercise already in a listed workout has to be listed somewhere, and the workouts
+/// exercises were moved out of are renumbered as well, so sequences always run from 0 without
+/// gaps.
+async fn reorder_exercises(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    JsonExtractor(body): JsonExtractor<ReorderExercises>,
+) -> AppResult<Json<Vec<WorkoutWithExercises>>> {
+    use crate::schema::{exercises::dsl::*, workouts::dsl as w_dsl};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let listed_workout_ids: Vec<uuid::Uuid> = body.workouts.iter().map(|w| w.workout_id).collect();
+
+    if let Some(dup) = find_duplicate(&listed_workout_ids) {
+        return Err(bad_request(format!(
+            "Workout {dup} is listed more than once."
+        )));
+    }
+
+    let ordered_ids: Vec<uuid::Uuid> = body
+        .workouts
+        .iter()
+        .flat_map(|w| w.exercises.iter().copied())
+        .collect();
+
+    if let Some(dup) = find_duplicate(&ordered_ids) {
+        return Err(bad_request(format!(
+            "Exercise {dup} is listed more than once."
+        )));
+    }
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let listed_exercises: Vec<Exercise> = exercises
+            .filter(id.eq_any(&ordered_ids).and(archived_at.is_null()))
+            .select(Exercise::as_select())
+            .for_update()
+            .load(conn)?;
+
+        if let Some(unknown) = ordered_ids
+            .iter()
+            .find(|e_id| !listed_exercises.iter().any(|e| e.id == **e_id))
+        {
+            return Err(bad_request(format!("Exercise {unknown} does not exist.")));
+        }
+
+        // the workouts being reordered and the ones exercises are moved out of
+        let mut affected_workout_ids = listed_workout_ids.clone();
+        for source_id in listed_exercises.iter().filter_map(|e| e.workout_id) {
+            if !affected_workout_ids.contains(&source_id) {
+                affected_workout_ids.push(source_id);
+            }
+        }
+
+        let affected_workouts: Vec<Workout> = w_dsl::workouts
+            .filter(w_dsl::id.eq_any(&affected_workout_ids))
+            .select(Workout::as_select())
+            .load(conn)?;
+
+        if affected_workouts.len() != affected_workout_ids.len() {
+            return Err(bad_request("One of the listed workouts does not exist."));
+        }
+
+        if affected_workouts
+            .iter()
+            .any(|w| w.owner_id != Some(req_user_id))
+        {
+            return Err(unauthorized());
+        }
+
+        // every affected workout is owned by now, so only exercises outside a workout are left
+        if listed_exercises
+            .iter()
+            .any(|e| e.workout_id.is_none() && e.owner_id != Some(req_user_id))
+        {
+            return Err(unauthorized());
+        }
+
+        let current_in_listed: Vec<uuid::Uuid> = exercises
+            .filter(
+                workout_id
+                    .eq_any(&listed_workout_ids)
+                    .and(archived_at.is_null()),
+            )
+            .select(id)
+            .load(conn)?;
+
+        if let Some(missing) = current_in_listed
+            .iter()
+            .find(|e_id| !ordered_ids.contains(e_id))
+        {
+            return Err(bad_request(format!(
+                "Exercise {missing} is missing, every exercise of the listed workouts must be listed."
+            )));
+        }
+
+        for workout_order in &body.workouts {
+            for (idx, e_id) in workout_order.exercises.iter().enumerate() {
+                // blocks belong to a single workout, exercises moved out leave theirs
+                update(exercises)
+                    .filter(
+                        id.eq(e_id)
+                            .and(workout_id.is_distinct_from(workout_order.workout_id)),
+                    )
+                    .set(block_id.eq(None::<uuid::Uuid>))
+                    .execute(conn)?;
+
+                update(exercises)
+                    .filter(id.eq(e_id))
+                    .set((
+                        workout_id.eq(workout_order.workout_id),
+                        sequence.eq(idx as i32),
+                    ))
+                    .execute(conn)?;
+            }
+        }
+
+        // close the gaps left in the workouts exercises were moved out of
+        for source_workout in affected_workouts
+            .iter()
+            .filter(|w| !listed_workout_ids.contains(&w.id))
+        {
+            let remaining: Vec<uuid::Uuid> = exercises
+                .filter(workout_id.eq(source_workout.id).and(archived_at.is_null()))
+                .order(sequence.asc())
+                .select(id)
+                .load(conn)?;
+
+            for (idx, e_id) in remaining.iter().enumerate() {
+                update(exercises)
+                    .filter(id.eq(e_id))
+                    .set(sequence.eq(idx as i32))
+                    .execute(conn)?;
+            }
+        }
+
+        // identical snapshots are skipped, so each program only gets one new revision
+        for w in &affected_workouts {
+            record_workout_program_revision(conn, w.id, req_user_id)?;
+        }
+
+        Ok(with_exercises(conn, affected_workouts)?)
+    })?;
 
     Ok(Json(res))
 }
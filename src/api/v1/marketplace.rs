@@ -0,0 +1,242 @@
+use crate::{
+    api::v1::programs::{filter_programs, ProgramListingParams},
+    db::{
+        models::{
+            notification::NewNotification,
+            program::{Program, ProgramListing, ProgramWithWorkouts},
+            program_claim::ProgramClaim,
+        },
+        programs::{deep_copy_program, load_program_with_workouts, record_program_revision},
+        DbConnection,
+    },
+    error::{bad_request, BoxedAppError},
+    pagination::*,
+    server::AppState,
+    types::AppResult,
+    util::extractors::{Path, QueryExtractor, UserIdExtractor},
+};
+use axum::{
+    extract::State,
+    routing::{get, post},
+    Json, Router,
+};
+use diesel::{insert_into, prelude::*, upsert::excluded};
+use std::{collections::HashMap, sync::Arc};
+
+pub fn marketplace_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/programs", get(list_listings))
+        .route("/programs/:slug", get(get_listing))
+        .route("/programs/:slug/claim", post(claim_listing))
+        .route("/claims", get(list_claims))
+}
+
+/// Browse the published templates, newest first.
+async fn list_listings(
+    State(state): State<Arc<AppState>>,
+    listing_params: QueryExtractor<ProgramListingParams>,
+    pagination: QueryExtractor<PaginationParams>,
+) -> AppResult<Json<PaginatedResponse<ProgramListing>>> {
+    use crate::schema::programs::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let query = filter_programs(programs.into_boxed(), &listing_params.0)
+        .filter(
+            published
+                .eq(true)
+                .and(template.eq(true))
+                .and(archived_at.is_null()),
+        )
+        .order(published_at.desc())
+        .select(Program::as_select())
+        .pages_pagination(PaginationOptions::new(pagination.0)?);
+
+    let data: Paginated<Program> = query.load(&mut conn)?;
+    let total = data.total();
+
+    let listings = load_listings(&mut conn, data.into_iter().collect())?;
+
+    Ok(Json(PaginatedResponse::new(listings, total)))
+}
+
+async fn get_listing(
+    State(state): State<Arc<AppState>>,
+    Path(listing_slug): Path<String>,
+) -> AppResult<Json<ProgramListing>> {
+    let mut conn = state.db_pool.get_conn();
+
+    let program = find_listing(&mut conn, &listing_slug)?;
+
+    let listing = load_listings(&mut conn, vec![program])?
+        .pop()
+        .expect("one listing per program");
+
+    Ok(Json(listing))
+}
+
+/// Gives the requesting user their own copy of a published template.
+///
+/// Payments happen outside of the API, the claim records which price tier the listing had when
+/// it was claimed. A listing can be claimed again once the previous copy has been deleted.
+async fn claim_listing(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(listing_slug): Path<String>,
+) -> AppResult<Json<ProgramWithWorkouts>> {
+    use crate::schema::program_claims::dsl as claim_dsl;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let listing = find_listing(&mut conn, &listing_slug)?;
+
+    let copy = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let existing_copy: Option<Option<uuid::Uuid>> = claim_dsl::program_claims
+            .filter(
+                claim_dsl::program_id
+                    .eq(listing.id)
+                    .and(claim_dsl::user_id.eq(req_user_id)),
+            )
+            .select(claim_dsl::copy_program_id)
+            .first(conn)
+            .optional()?;
+
+        if let Some(Some(_)) = existing_copy {
+            return Err(bad_request("You already claimed this program."));
+        }
+
+        let copy = deep_copy_program(conn, listing.id, req_user_id, None)?;
+
+        record_program_revision(conn, copy.id, req_user_id)?;
+
+        insert_into(claim_dsl::program_claims)
+            .values((
+                claim_dsl::program_id.eq(listing.id),
+                claim_dsl::user_id.eq(req_user_id),
+                claim_dsl::copy_program_id.eq(copy.id),
+                claim_dsl::price_tier.eq(&listing.price_tier),
+            ))
+            .on_conflict((claim_dsl::program_id, claim_dsl::user_id))
+            .do_update()
+            .set((
+                claim_dsl::copy_program_id.eq(excluded(claim_dsl::copy_program_id)),
+                claim_dsl::price_tier.eq(excluded(claim_dsl::price_tier)),
+            ))
+            .execute(conn)?;
+
+        if let Some(listing_owner_id) = listing.owner_id.filter(|o| *o != req_user_id) {
+            let d: serde_json::Value = serde_json::json!({
+                "program_id": listing.id.to_string(),
+                "name": listing.name,
+            });
+
+            let new_noti = NewNotification::new(
+                req_user_id,
+                listing_owner_id,
+                "Your program was claimed".into(),
+                format!("Someone claimed {} from the marketplace!", listing.name),
+                "program".into(),
+                "unread".into(),
+                Some(d),
+            );
+
+            new_noti.send(conn)?;
+        }
+
+        Ok(copy)
+    })?;
+
+    let res = load_program_with_workouts(copy, &mut conn)?;
+
+    Ok(Json(res))
+}
+
+async fn list_claims(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    pagination: QueryExtractor<PaginationParams>,
+) -> AppResult<Json<PaginatedResponse<ProgramClaim>>> {
+    use crate::schema::program_claims::dsl::*;
+
+    let query = program_claims
+        .filter(user_id.eq(req_user_id))
+        .order(created_at.desc())
+        .select(ProgramClaim::as_select())
+        .pages_pagination(PaginationOptions::new(pagination.0)?);
+
+    let data: Paginated<ProgramClaim> = query.load(&mut state.db_pool.get_conn())?;
+
+    Ok(Json(data.into()))
+}
+
+/// Only published templates that aren't archived are part of the marketplace, anything else is a
+/// 404.
+fn find_listing(conn: &mut DbConnection, listing_slug: &str) -> AppResult<Program> {
+    use crate::schema::programs::dsl::*;
+
+    let res = programs
+        .filter(
+            slug.eq(listing_slug)
+                .and(published.eq(true))
+                .and(template.eq(true))
+                .and(archived_at.is_null()),
+        )
+        .select(Program::as_select())
+        .first(conn)?;
+
+    Ok(res)
+}
+
+/// Adds the workout, week and claim counts to a page of programs.
+fn load_listings(
+    conn: &mut DbConnection,
+    listed_programs: Vec<Program>,
+) -> QueryResult<Vec<ProgramListing>> {
+    use crate::schema::{program_claims::dsl as claim_dsl, workouts::dsl as w_dsl};
+    use diesel::dsl::count_star;
+
+    let program_ids: Vec<uuid::Uuid> = listed_programs.iter().map(|p| p.id).collect();
+
+    let workout_counts: HashMap<uuid::Uuid, (i64, Option<i32>)> = w_dsl::workouts
+        .filter(
+            w_dsl::program_id
+                .eq_any(&program_ids)
+                .and(w_dsl::archived_at.is_null()),
+        )
+        .group_by(w_dsl::program_id)
+        .select((
+            w_dsl::program_id,
+            count_star(),
+            diesel::dsl::max(w_dsl::week),
+        ))
+        .load::<(Option<uuid::Uuid>, i64, Option<i32>)>(conn)?
+        .into_iter()
+        .filter_map(|(p_id, count, weeks)| p_id.map(|p_id| (p_id, (count, weeks))))
+        .collect();
+
+    let claim_counts: HashMap<uuid::Uuid, i64> = claim_dsl::program_claims
+        .filter(claim_dsl::program_id.eq_any(&program_ids))
+        .group_by(claim_dsl::program_id)
+        .select((claim_dsl::program_id, count_star()))
+        .load::<(Option<uuid::Uuid>, i64)>(conn)?
+        .into_iter()
+        .filter_map(|(p_id, count)| p_id.map(|p_id| (p_id, count)))
+        .collect();
+
+    let res = listed_programs
+        .into_iter()
+        .map(|program| {
+            let (workout_count, weeks) =
+                workout_counts.get(&program.id).copied().unwrap_or_default();
+
+            ProgramListing {
+                weeks: weeks.unwrap_or_default(),
+                workout_count,
+                claim_count: claim_counts.get(&program.id).copied().unwrap_or_default(),
+                program,
+            }
+        })
+        .collect();
+
+    Ok(res)
+}
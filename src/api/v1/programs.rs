@@ -1,32 +1,118 @@
 use crate::{
-    db::models::{
-        client::Client,
-        exercise::Exercise,
-        program::{NewProgram, PatchProgram, Program},
-        workout::{Workout, WorkoutWithExercises},
+    api::v1::substitutions::swap_program_exercise,
+    db::{
+        collaborators::{can_view_program, has_program_access, load_shared_programs},
+        models::{
+            client::Client,
+            collaborator::{
+                CollaboratorRoles, CollaboratorWithUser, NewCollaborator, ProgramCollaborator,
+                SharedProgram,
+            },
+            notification::NewNotification,
+            program::{
+                NewProgram, PatchProgram, PriceTiers, Program, ProgramLevels, ProgramWithWorkouts,
+                PublishProgram,
+            },
+            program_document::{DocumentFormat, ProgramDocument, WorkoutDocument},
+            program_progress::{percent, NextWorkout, ProgramProgress, ProgramProgressResponse},
+            program_revision::{ProgramDiff, ProgramRevision, ProgramRevisionSummary},
+            tag::{FacetCount, FacetParams, SetTags, Tag, TagCategories, TagMatch},
+            taxonomy::{summarize_program_volume, ProgramVolume},
+            user::{PublicUser, PUBLIC_USER_COLUMNS},
+            workout::{ScheduledWorkout, Workout, WorkoutWithExercises},
+            workout_feedback::{
+                summarize_feedback, ExerciseDiscomfort, WorkoutFeedback, WorkoutFeedbackSummary,
+            },
+            IntensityChoices,
+        },
+        programs::{
+            deep_copy_program, import_program_document, insert_workout_documents,
+            load_program_schedule, load_program_with_workouts, record_program_revision,
+            restore_program_snapshot, sync_program_progress,
+        },
+        tags::{
+            filter_tagged_programs, guard_tag_ids, load_program_tags, program_facets,
+            replace_program_tags,
+        },
+        taxonomy::load_volume_rows,
+        workouts::with_exercises,
+        DbConnection,
     },
-    error::{custom, unauthorized},
+    error::{
+        bad_request, custom, field_errors, internal_server_error, unauthorized, BoxedAppError,
+    },
+    pagination::*,
     server::AppState,
     types::AppResult,
     util::{
-        extractors::{JsonExtractor, Path, QueryHmExt, UserIdExtractor},
-        format_slug,
+        calendar::{reschedule_from, valid_training_days},
+        contains_pattern,
+        extractors::{JsonExtractor, Path, QueryExtractor, QueryHmExt, UserIdExtractor},
+        format_slug, parse_slugs,
+        progression::{generate_progression, ProgressionRules},
     },
 };
-use axum::{extract::State, routing::get, Json, Router};
-use diesel::{insert_into, prelude::*};
+use axum::{
+    extract::State,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use diesel::{insert_into, pg::Pg, prelude::*};
 use http::StatusCode;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{str::FromStr, sync::Arc};
 
 pub fn program_routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(list_programs).post(create_program))
         .route("/active", get(get_active_program))
+        .route("/import", post(import_program))
+        .route("/facets", get(get_program_facets))
+        .route("/shared", get(list_shared_programs))
         .route(
             "/:program_id",
             get(get_program).put(update_program).delete(delete_program),
         )
+        .route("/:program_id/assign", post(assign_program))
+        .route(
+            "/:program_id/collaborators",
+            get(list_program_collaborators).post(add_program_collaborator),
+        )
+        .route(
+            "/:program_id/collaborators/:user_id",
+            delete(remove_program_collaborator),
+        )
+        .route("/:program_id/export", get(export_program))
+        .route("/:program_id/feedback", get(get_program_feedback))
+        .route(
+            "/:program_id/publish",
+            post(publish_program).delete(unpublish_program),
+        )
+        .route("/:program_id/progress", get(get_program_progress))
+        .route("/:program_id/restore", post(restore_program))
+        .route("/:program_id/progression", post(generate_progression_weeks))
+        .route(
+            "/:program_id/progression/preview",
+            post(preview_progression_weeks),
+        )
+        .route("/:program_id/schedule", get(get_program_schedule))
+        .route(
+            "/:program_id/tags",
+            get(get_program_tags).put(set_program_tags),
+        )
+        .route("/:program_id/reschedule", post(reschedule_program))
+        .route("/:program_id/revisions", get(list_program_revisions))
+        .route("/:program_id/revisions/diff", get(diff_program_revisions))
+        .route(
+            "/:program_id/revisions/:revision",
+            get(get_program_revision),
+        )
+        .route(
+            "/:program_id/revisions/:revision/restore",
+            post(restore_program_revision),
+        )
+        .route("/:program_id/swap", post(swap_program_exercise))
+        .route("/:program_id/volume", get(get_program_volume))
 }
 
 async fn get_active_program(
@@ -47,26 +133,21 @@ async fn get_active_program(
         .first(&mut conn)?;
 
     let program_db_res = base
-        .filter(client_id.eq(client.id).and(active.eq(true)))
+        .filter(
+            client_id
+                .eq(client.id)
+                .and(active.eq(true))
+                .and(archived_at.is_null()),
+        )
         .select(Program::as_select())
         .first::<Program>(&mut conn)?;
 
     let workouts_belonging_to_programs = Workout::belonging_to(&program_db_res)
+        .filter(crate::schema::workouts::archived_at.is_null())
         .select(Workout::as_select())
         .load::<Workout>(&mut conn)?;
 
-    let exercises_belonging_to_workouts = Exercise::belonging_to(&workouts_belonging_to_programs)
-        .select(Exercise::as_select())
-        .load::<Exercise>(&mut conn)?;
-
-    let grouped_exercises: Vec<Vec<Exercise>> =
-        exercises_belonging_to_workouts.grouped_by(&workouts_belonging_to_programs);
-
-    let workouts_with_exercises = workouts_belonging_to_programs
-        .into_iter()
-        .zip(grouped_exercises)
-        .map(|(workout, exercises)| WorkoutWithExercises { workout, exercises })
-        .collect();
+    let workouts_with_exercises = with_exercises(&mut conn, workouts_belonging_to_programs)?;
 
     let res: ProgramWithWorkouts = ProgramWithWorkouts {
         program: program_db_res,
@@ -92,25 +173,16 @@ async fn get_program(
     };
 
     let program_db_res = base
+        .filter(archived_at.is_null())
         .select(Program::as_select())
         .first::<Program>(&mut conn)?;
 
     let workouts_belonging_to_programs = Workout::belonging_to(&program_db_res)
+        .filter(crate::schema::workouts::archived_at.is_null())
         .select(Workout::as_select())
         .load::<Workout>(&mut conn)?;
 
-    let exercises_belonging_to_workouts = Exercise::belonging_to(&workouts_belonging_to_programs)
-        .select(Exercise::as_select())
-        .load::<Exercise>(&mut conn)?;
-
-    let grouped_exercises: Vec<Vec<Exercise>> =
-        exercises_belonging_to_workouts.grouped_by(&workouts_belonging_to_programs);
-
-    let workouts_with_exercises = workouts_belonging_to_programs
-        .into_iter()
-        .zip(grouped_exercises)
-        .map(|(workout, exercises)| WorkoutWithExercises { workout, exercises })
-        .collect();
+    let workouts_with_exercises = with_exercises(&mut conn, workouts_belonging_to_programs)?;
 
     let res: ProgramWithWorkouts = ProgramWithWorkouts {
         program: program_db_res,
@@ -120,13 +192,83 @@ async fn get_program(
     Ok(Json(res))
 }
 
-// #[debug_handler]
-async fn list_programs(
-    State(state): State<Arc<AppState>>,
-    hm: QueryHmExt,
-) -> AppResult<Json<Vec<ProgramWithWorkouts>>> {
+#[derive(Deserialize, Debug, Default)]
+pub struct ProgramListingParams {
+    /// Matched against the name, description and focus areas of the program
+    pub search: Option<String>,
+    pub price_tier: Option<PriceTiers>,
+    pub level: Option<ProgramLevels>,
+    pub intensity: Option<IntensityChoices>,
+    pub published: Option<bool>,
+    /// Comma separated slugs of `focus_area` tags
+    pub focus_area: Option<String>,
+    /// Comma separated slugs of `audience` tags
+    pub audience: Option<String>,
+    #[serde(default)]
+    pub tag_match: TagMatch,
+}
+
+/// Search and listing filters shared by `list_programs` and the marketplace.
+pub fn filter_programs<'a>(
+    mut query: crate::schema::programs::BoxedQuery<'a, Pg>,
+    params: &ProgramListingParams,
+) -> crate::schema::programs::BoxedQuery<'a, Pg> {
+    use crate::schema::programs::dsl::*;
+
+    if let Some(search) = params.search.as_deref().filter(|s| !s.trim().is_empty()) {
+        let pattern = contains_pattern(search);
+
+        query = query.filter(
+            name.ilike(pattern.clone())
+                .or(description.ilike(pattern.clone()))
+                .or(focus_areas.ilike(pattern)),
+        );
+    }
+
+    if let Some(tier) = params.price_tier.clone() {
+        query = query.filter(price_tier.eq(tier));
+    }
+
+    if let Some(program_level) = params.level.clone() {
+        query = query.filter(level.eq(program_level));
+    }
+
+    if let Some(program_intensity) = params.intensity.clone() {
+        query = query.filter(intensity.eq(program_intensity));
+    }
+
+    if let Some(is_published) = params.published {
+        query = query.filter(published.eq(is_published));
+    }
+
+    if let Some(slugs) = params
+        .focus_area
+        .as_deref()
+        .map(parse_slugs)
+        .filter(|s| !s.is_empty())
+    {
+        query = filter_tagged_programs(query, TagCategories::FocusArea, slugs, params.tag_match);
+    }
+
+    if let Some(slugs) = params
+        .audience
+        .as_deref()
+        .map(parse_slugs)
+        .filter(|s| !s.is_empty())
+    {
+        query = filter_tagged_programs(query, TagCategories::Audience, slugs, params.tag_match);
+    }
+
+    query
+}
+
+/// Programs matched by the query parameters of `list_programs`, also used for its facet counts.
+fn program_listing_query(
+    hm: &QueryHmExt,
+    listing_params: &ProgramListingParams,
+) -> AppResult<crate::schema::programs::BoxedQuery<'static, Pg>> {
     use crate::schema::programs::dsl as programs_dsl;
-    let mut base_q = programs_dsl::programs.into_boxed();
+    let mut base_q = filter_programs(programs_dsl::programs.into_boxed(), listing_params);
 
     let owner_query =
         hm.0.get("owner")
@@ -145,31 +287,48 @@ async fn list_programs(
         base_q = base_q.filter(programs_dsl::active.eq(active_qp))
     };
 
+    if !hm.include_archived() {
+        base_q = base_q.filter(programs_dsl::archived_at.is_null())
+    };
+
+    Ok(base_q)
+}
+
+/// The programs, or with `facets=true` the programs and their facet counts.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ProgramListing {
+    Programs(Vec<ProgramWithWorkouts>),
+    WithFacets {
+        data: Vec<ProgramWithWorkouts>,
+        facets: Vec<FacetCount>,
+    },
+}
+
+// #[debug_handler]
+async fn list_programs(
+    State(state): State<Arc<AppState>>,
+    hm: QueryHmExt,
+    listing_params: QueryExtractor<ProgramListingParams>,
+    facet_params: QueryExtractor<FacetParams>,
+) -> AppResult<Json<ProgramListing>> {
+    let base_q = program_listing_query(&hm, &listing_params.0)?;
+
     let mut conn = state.db_pool.get_conn();
 
     let program_db_res = base_q
         .select(Program::as_select())
         .load::<Program>(&mut conn)?;
 
-    use crate::schema::{exercises::dsl as exer_dsl, workouts::dsl as workout_dsl};
+    use crate::schema::workouts::dsl as workout_dsl;
     let workouts_belonging_to_programs = Workout::belonging_to(&program_db_res)
-        .order(workout_dsl::sequence.asc())
+        .filter(workout_dsl::archived_at.is_null())
+        .order((workout_dsl::week.asc(), workout_dsl::sequence.asc()))
         .select(Workout::as_select())
         .load::<Workout>(&mut conn)?;
 
-    let exercises_belonging_to_workouts = Exercise::belonging_to(&workouts_belonging_to_programs)
-        .order(exer_dsl::sequence.asc())
-        .select(Exercise::as_select())
-        .load::<Exercise>(&mut conn)?;
-
-    let grouped_exercises: Vec<Vec<Exercise>> =
-        exercises_belonging_to_workouts.grouped_by(&workouts_belonging_to_programs);
-
-    let workouts_with_exercises: Vec<Vec<(Workout, Vec<Exercise>)>> =
-        workouts_belonging_to_programs
-            .into_iter()
-            .zip(grouped_exercises)
-            .grouped_by(&program_db_res);
+    let workouts_with_exercises: Vec<Vec<WorkoutWithExercises>> =
+        with_exercises(&mut conn, workouts_belonging_to_programs)?.grouped_by(&program_db_res);
 
     let res: Vec<ProgramWithWorkouts> = program_db_res
         .into_iter()
@@ -177,32 +336,86 @@ async fn list_programs(
         .map(ProgramWithWorkouts::from)
         .collect();
 
+    if !facet_params.0.facets {
+        return Ok(Json(ProgramListing::Programs(res)));
+    }
+
+    let facets = program_facets(&mut conn, program_listing_query(&hm, &listing_params.0)?)?;
+
+    Ok(Json(ProgramListing::WithFacets { data: res, facets }))
+}
+
+/// Number of programs carrying each tag, out of the programs `list_programs` returns for the
+/// same query parameters.
+async fn get_program_facets(
+    State(state): State<Arc<AppState>>,
+    hm: QueryHmExt,
+    listing_params: QueryExtractor<ProgramListingParams>,
+) -> AppResult<Json<Vec<FacetCount>>> {
+    let base_q = program_listing_query(&hm, &listing_params.0)?;
+
+    let res = program_facets(&mut state.db_pool.get_conn(), base_q)?;
+
     Ok(Json(res))
 }
 
-#[derive(Serialize)]
-pub struct ProgramWithWorkouts {
-    #[serde(flatten)]
-    pub program: Program,
-    pub workouts: Vec<WorkoutWithExercises>,
+async fn get_program_tags(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(program_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Vec<Tag>>> {
+    use crate::schema::programs::dsl as p_dsl;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = p_dsl::programs
+        .filter(p_dsl::id.eq(program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    if !can_view_program(&mut conn, req_user_id, &program)? {
+        return Err(unauthorized());
+    }
+
+    let res = load_program_tags(&mut conn, program_id)?;
+
+    Ok(Json(res))
 }
 
-impl From<(Program, Vec<(Workout, Vec<Exercise>)>)> for ProgramWithWorkouts {
-    fn from(value: (Program, Vec<(Workout, Vec<Exercise>)>)) -> Self {
-        let (program, workouts_with_exercises) = value;
+/// Replaces the `focus_area` and `audience` tags of the program.
+async fn set_program_tags(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(program_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<SetTags>,
+) -> AppResult<Json<Vec<Tag>>> {
+    use crate::schema::programs::dsl as p_dsl;
 
-        let workouts_with_exercises = workouts_with_exercises
-            .into_iter()
-            .map(|(workout, exercises)| WorkoutWithExercises { workout, exercises })
-            .collect();
+    let mut conn = state.db_pool.get_conn();
 
-        ProgramWithWorkouts {
-            program,
-            workouts: workouts_with_exercises,
-        }
+    let program: Program = p_dsl::programs
+        .filter(p_dsl::id.eq(program_id).and(p_dsl::archived_at.is_null()))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    if !has_program_access(&mut conn, req_user_id, &program, CollaboratorRoles::Editor)? {
+        return Err(unauthorized());
     }
+
+    guard_tag_ids(&mut conn, &body.tag_ids, true)?;
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        replace_program_tags(conn, program.id, &body.tag_ids)?;
+
+        Ok(load_program_tags(conn, program.id)?)
+    })?;
+
+    Ok(Json(res))
 }
 
+const INVALID_TRAINING_DAYS: &str =
+    "training_days must be ISO weekdays, 1 (Monday) through 7 (Sunday).";
+
 // #[debug_handler]
 async fn create_program(
     State(state): State<Arc<AppState>>,
@@ -211,6 +424,14 @@ async fn create_program(
 ) -> AppResult<Json<Program>> {
     use crate::schema::programs::dsl::*;
 
+    if body
+        .training_days
+        .as_ref()
+        .is_some_and(|days| !valid_training_days(days))
+    {
+        return Err(bad_request(INVALID_TRAINING_DAYS));
+    }
+
     let mut conn = state.db_pool.get_conn();
 
     let mut new_slug = format_slug(body.name.clone());
@@ -225,10 +446,16 @@ async fn create_program(
         new_slug = format!("{}-{}", new_slug, &rand::random::<u32>().to_string());
     }
 
-    let res = insert_into(programs)
-        .values((&body, owner_id.eq(user_id_ext), slug.eq(new_slug)))
-        .returning(Program::as_returning())
-        .get_result::<Program>(&mut state.db_pool.get_conn())?;
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let res = insert_into(programs)
+            .values((&body, owner_id.eq(user_id_ext), slug.eq(new_slug)))
+            .returning(Program::as_returning())
+            .get_result::<Program>(conn)?;
+
+        record_program_revision(conn, res.id, user_id_ext)?;
+
+        Ok(res)
+    })?;
 
     Ok(Json(res))
 }
@@ -245,6 +472,14 @@ async fn update_program(
     // println!("Updating program with id: {:?}", program_id_to_update);
     // req user needs to be owner of program
 
+    if body
+        .training_days
+        .as_ref()
+        .is_some_and(|days| !valid_training_days(days))
+    {
+        return Err(bad_request(INVALID_TRAINING_DAYS));
+    }
+
     let mut conn = state.db_pool.get_conn();
 
     let req_user_is_admin: bool = u_dsl::users
@@ -252,23 +487,38 @@ async fn update_program(
         .select(u_dsl::is_admin)
         .first(&mut conn)?;
 
-    let req_program_owner_id: Option<uuid::Uuid> = programs
+    let req_program: Program = programs
         .filter(id.eq(program_id_to_update))
-        .select(owner_id)
+        .select(Program::as_select())
         .first(&mut conn)?;
 
-    if req_program_owner_id == Some(req_user_id) || req_user_is_admin {
-        let res = diesel::update(programs)
-            .filter(id.eq(program_id_to_update))
-            .set(body)
-            .returning(Program::as_select())
-            .get_result::<Program>(&mut state.db_pool.get_conn())?;
+    if req_user_is_admin
+        || has_program_access(
+            &mut conn,
+            req_user_id,
+            &req_program,
+            CollaboratorRoles::Editor,
+        )?
+    {
+        let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+            let res = diesel::update(programs)
+                .filter(id.eq(program_id_to_update))
+                .set(body)
+                .returning(Program::as_select())
+                .get_result::<Program>(conn)?;
+
+            record_program_revision(conn, res.id, req_user_id)?;
+
+            Ok(res)
+        })?;
 
         return Ok(Json(res));
     }
     Err(unauthorized())
 }
 
+/// Archives the program, it's kept along with its workouts and their data until the retention
+/// job purges it.
 // #[debug_handler]
 async fn delete_program(
     State(state): State<Arc<AppState>>,
@@ -277,9 +527,932 @@ async fn delete_program(
 ) -> AppResult<Json<usize>> {
     use crate::schema::programs::dsl::*;
 
-    let res = diesel::delete(programs)
-        .filter(id.eq(program_id_to_update).and(owner_id.eq(user_id_ext)))
-        .execute(&mut state.db_pool.get_conn())?;
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(program_id_to_update))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    if !has_program_access(&mut conn, user_id_ext, &program, CollaboratorRoles::Editor)? {
+        return Err(unauthorized());
+    }
+
+    let res = diesel::update(programs)
+        .filter(id.eq(program.id).and(archived_at.is_null()))
+        .set(archived_at.eq(chrono::Utc::now()))
+        .execute(&mut conn)?;
+
+    Ok(Json(res))
+}
+
+async fn restore_program(
+    State(state): State<Arc<AppState>>,
+    Path(program_id_to_restore): Path<uuid::Uuid>,
+    UserIdExtractor(user_id_ext): UserIdExtractor,
+) -> AppResult<Json<Program>> {
+    use crate::schema::programs::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(program_id_to_restore))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    if !has_program_access(&mut conn, user_id_ext, &program, CollaboratorRoles::Editor)? {
+        return Err(unauthorized());
+    }
+
+    let res = diesel::update(programs)
+        .filter(id.eq(program.id).and(archived_at.is_not_null()))
+        .set(archived_at.eq(None::<chrono::DateTime<chrono::Utc>>))
+        .returning(Program::as_returning())
+        .get_result::<Program>(&mut conn)?;
+
+    Ok(Json(res))
+}
+
+/// Programs other trainers shared with the requesting user.
+async fn list_shared_programs(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+) -> AppResult<Json<Vec<SharedProgram>>> {
+    let res = load_shared_programs(&mut state.db_pool.get_conn(), req_user_id)?;
+
+    Ok(Json(res))
+}
+
+async fn list_program_collaborators(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Vec<CollaboratorWithUser<ProgramCollaborator>>>> {
+    use crate::schema::{
+        program_collaborators::dsl as pc_dsl, programs::dsl::*, users::dsl as u_dsl,
+    };
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    guard_program_owner_or_client(req_user_id, &program, &mut conn, CollaboratorRoles::Viewer)?;
+
+    let res = pc_dsl::program_collaborators
+        .filter(pc_dsl::program_id.eq(program.id))
+        .inner_join(u_dsl::users.on(pc_dsl::user_id.eq(u_dsl::id)))
+        .order(pc_dsl::created_at.asc())
+        .select((ProgramCollaborator::as_select(), PUBLIC_USER_COLUMNS))
+        .load::<(ProgramCollaborator, PublicUser)>(&mut conn)?
+        .into_iter()
+        .map(|(collaborator, user)| CollaboratorWithUser { collaborator, user })
+        .collect();
+
+    Ok(Json(res))
+}
+
+/// Shares the program with another user, granting it again changes their role. Only the owner can
+/// share a program.
+async fn add_program_collaborator(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<NewCollaborator>,
+) -> AppResult<Json<CollaboratorWithUser<ProgramCollaborator>>> {
+    use crate::schema::{
+        program_collaborators::dsl as pc_dsl, programs::dsl::*, users::dsl as u_dsl,
+    };
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    if program.owner_id != Some(req_user_id) {
+        return Err(unauthorized());
+    }
+
+    if body.user_id == req_user_id {
+        return Err(bad_request("You already own this program."));
+    }
+
+    let user: PublicUser = u_dsl::users
+        .filter(u_dsl::id.eq(body.user_id))
+        .select(PUBLIC_USER_COLUMNS)
+        .first(&mut conn)?;
+
+    let collaborator = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let collaborator = insert_into(pc_dsl::program_collaborators)
+            .values((
+                pc_dsl::program_id.eq(program.id),
+                pc_dsl::user_id.eq(body.user_id),
+                pc_dsl::granted_by.eq(req_user_id),
+                pc_dsl::role.eq(body.role),
+            ))
+            .on_conflict((pc_dsl::program_id, pc_dsl::user_id))
+            .do_update()
+            .set((
+                pc_dsl::role.eq(body.role),
+                pc_dsl::granted_by.eq(req_user_id),
+            ))
+            .returning(ProgramCollaborator::as_returning())
+            .get_result::<ProgramCollaborator>(conn)?;
+
+        let d: serde_json::Value = serde_json::json!({
+            "program_id": program.id.to_string(),
+            "role": collaborator.role,
+        });
+
+        let new_noti = NewNotification::new(
+            req_user_id,
+            body.user_id,
+            "A program was shared with you".into(),
+            format!("You can now collaborate on {}.", program.name),
+            "program".into(),
+            "unread".into(),
+            Some(d),
+        );
+
+        new_noti.send(conn)?;
+
+        Ok(collaborator)
+    })?;
+
+    Ok(Json(CollaboratorWithUser { collaborator, user }))
+}
+
+/// The owner can remove anyone, collaborators can remove themselves.
+async fn remove_program_collaborator(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path((path_program_id, path_user_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+) -> AppResult<Json<usize>> {
+    use crate::schema::{program_collaborators::dsl as pc_dsl, programs::dsl::*};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    if program.owner_id != Some(req_user_id) && path_user_id != req_user_id {
+        return Err(unauthorized());
+    }
+
+    let res = diesel::delete(pc_dsl::program_collaborators)
+        .filter(
+            pc_dsl::program_id
+                .eq(program.id)
+                .and(pc_dsl::user_id.eq(path_user_id)),
+        )
+        .execute(&mut conn)?;
+
+    Ok(Json(res))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AssignProgram {
+    pub client_id: uuid::Uuid,
+}
+
+/// Assigns a template program to one of the trainer's clients by deep copying it, along with all
+/// of its workouts and exercises.
+async fn assign_program(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(template_program_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<AssignProgram>,
+) -> AppResult<Json<ProgramWithWorkouts>> {
+    use crate::schema::{clients::dsl as c_dsl, programs::dsl::*};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let template_program: Program = programs
+        .filter(id.eq(template_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    if template_program.owner_id != Some(req_user_id) {
+        return Err(unauthorized());
+    }
+
+    if !template_program.template {
+        return Err(bad_request("Only template programs can be assigned."));
+    }
+
+    if template_program.archived_at.is_some() {
+        return Err(bad_request("Archived programs can't be assigned."));
+    }
+
+    let client: Client = c_dsl::clients
+        .filter(c_dsl::id.eq(body.client_id))
+        .select(Client::as_select())
+        .first(&mut conn)?;
+
+    // trainers can only assign programs to their own clients
+    if client.trainer_id != Some(req_user_id) {
+        return Err(unauthorized());
+    }
+
+    let assigned_program = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let new_program =
+            deep_copy_program(conn, template_program.id, req_user_id, Some(client.id))?;
+
+        record_program_revision(conn, new_program.id, req_user_id)?;
+
+        if let Some(client_user_id) = client.user_id {
+            let d: serde_json::Value = serde_json::json!({
+                "program_id": new_program.id.to_string(),
+                "name": new_program.name,
+            });
+
+            let new_noti = NewNotification::new(
+                req_user_id,
+                client_user_id,
+                "Your trainer assigned you a program".into(),
+                format!("{} is ready for you to start!", new_program.name),
+                "program".into(),
+                "unread".into(),
+                Some(d),
+            );
+
+            new_noti.send(conn)?;
+        }
+
+        Ok(new_program)
+    })?;
+
+    let res = load_program_with_workouts(assigned_program, &mut conn)?;
 
     Ok(Json(res))
 }
+
+/// `format` query param wins over the content type, defaults to JSON.
+fn document_format(hm: &QueryHmExt, headers: &http::HeaderMap) -> AppResult<DocumentFormat> {
+    match hm.0.get("format").map(|f| f.as_str()) {
+        Some("json") => return Ok(DocumentFormat::Json),
+        Some("yaml") | Some("yml") => return Ok(DocumentFormat::Yaml),
+        Some(_) => return Err(bad_request("format must be either json or yaml.")),
+        None => {}
+    }
+
+    let is_yaml = headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|val| val.to_str().ok())
+        .is_some_and(|val| val.contains("yaml"));
+
+    if is_yaml {
+        return Ok(DocumentFormat::Yaml);
+    }
+
+    Ok(DocumentFormat::Json)
+}
+
+/// Exports the program tree as a portable document, see `ProgramDocument` for the format.
+async fn export_program(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+    hm: QueryHmExt,
+    headers: http::HeaderMap,
+) -> AppResult<([(http::header::HeaderName, &'static str); 1], String)> {
+    use crate::schema::programs::dsl::*;
+
+    let format = document_format(&hm, &headers)?;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    guard_program_owner_or_client(req_user_id, &program, &mut conn, CollaboratorRoles::Viewer)?;
+
+    let tree = load_program_with_workouts(program, &mut conn)?;
+
+    let document = ProgramDocument::from(&tree)
+        .render(format)
+        .map_err(internal_server_error)?;
+
+    Ok((
+        [(http::header::CONTENT_TYPE, format.content_type())],
+        document,
+    ))
+}
+
+/// Creates a new template program owned by the requesting user from a JSON or YAML document.
+///
+/// Nothing is created unless the whole document is valid, every invalid field is reported with
+/// its path in the document.
+async fn import_program(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    hm: QueryHmExt,
+    headers: http::HeaderMap,
+    body: String,
+) -> AppResult<Json<ProgramWithWorkouts>> {
+    let format = document_format(&hm, &headers)?;
+
+    let document = ProgramDocument::parse(&body, format).map_err(|err| field_errors(vec![err]))?;
+
+    let errors = document.validate();
+    if !errors.is_empty() {
+        return Err(field_errors(errors));
+    }
+
+    let mut conn = state.db_pool.get_conn();
+
+    let imported = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let imported = import_program_document(conn, &document, req_user_id)?;
+
+        sync_program_progress(conn, imported.id)?;
+        record_program_revision(conn, imported.id, req_user_id)?;
+
+        Ok(imported)
+    })?;
+
+    let res = load_program_with_workouts(imported, &mut conn)?;
+
+    Ok(Json(res))
+}
+
+/// Loads the program with its base week and generates the rest of the block from it. Only the
+/// owner can build out a program.
+fn progression_for_program(
+    conn: &mut DbConnection,
+    req_user_id: uuid::Uuid,
+    path_program_id: uuid::Uuid,
+    rules: &ProgressionRules,
+) -> AppResult<(ProgramWithWorkouts, Vec<WorkoutDocument>)> {
+    use crate::schema::programs::dsl::*;
+
+    rules.validate().map_err(bad_request)?;
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(conn)?;
+
+    if program.owner_id != Some(req_user_id) {
+        return Err(unauthorized());
+    }
+
+    let tree = load_program_with_workouts(program, conn)?;
+
+    let base: Vec<&WorkoutWithExercises> = tree
+        .workouts
+        .iter()
+        .filter(|w| w.workout.week == rules.base_week)
+        .collect();
+
+    if base.is_empty() {
+        return Err(bad_request(format!(
+            "Week {} has no workouts to progress from.",
+            rules.base_week
+        )));
+    }
+
+    let generated = generate_progression(&base, rules);
+
+    Ok((tree, generated))
+}
+
+/// Returns the weeks the progression rules would create, nothing is saved.
+async fn preview_progression_weeks(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+    JsonExtractor(rules): JsonExtractor<ProgressionRules>,
+) -> AppResult<Json<Vec<WorkoutDocument>>> {
+    let (_, generated) = progression_for_program(
+        &mut state.db_pool.get_conn(),
+        req_user_id,
+        path_program_id,
+        &rules,
+    )?;
+
+    Ok(Json(generated))
+}
+
+/// Builds out a training block by copying the base week into the following weeks with the
+/// progression rules applied.
+///
+/// Weeks that already have workouts are rejected unless `replace_existing` is set, in which case
+/// their workouts are archived first.
+async fn generate_progression_weeks(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+    JsonExtractor(rules): JsonExtractor<ProgressionRules>,
+) -> AppResult<Json<ProgramWithWorkouts>> {
+    use crate::schema::workouts::dsl as w_dsl;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let (tree, generated) =
+        progression_for_program(&mut conn, req_user_id, path_program_id, &rules)?;
+
+    let target_weeks = rules.generated_weeks();
+
+    let taken_week = tree
+        .workouts
+        .iter()
+        .map(|w| w.workout.week)
+        .find(|week| target_weeks.contains(week));
+
+    if let (Some(week), false) = (taken_week, rules.replace_existing) {
+        return Err(bad_request(format!(
+            "Week {week} already has workouts, set replace_existing to overwrite them."
+        )));
+    }
+
+    let program = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        diesel::update(w_dsl::workouts)
+            .filter(
+                w_dsl::program_id
+                    .eq(tree.program.id)
+                    .and(w_dsl::week.between(target_weeks.start(), target_weeks.end()))
+                    .and(w_dsl::archived_at.is_null()),
+            )
+            .set(w_dsl::archived_at.eq(chrono::Utc::now()))
+            .execute(conn)?;
+
+        insert_workout_documents(conn, tree.program.id, req_user_id, &generated)?;
+
+        sync_program_progress(conn, tree.program.id)?;
+        record_program_revision(conn, tree.program.id, req_user_id)?;
+
+        Ok(tree.program)
+    })?;
+
+    let res = load_program_with_workouts(program, &mut conn)?;
+
+    Ok(Json(res))
+}
+
+/// Publishes a template program to the marketplace. Publishing an already published program
+/// updates its listing.
+async fn publish_program(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<PublishProgram>,
+) -> AppResult<Json<Program>> {
+    use crate::schema::programs::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    if program.owner_id != Some(req_user_id) {
+        return Err(unauthorized());
+    }
+
+    if !program.template {
+        return Err(bad_request("Only template programs can be published."));
+    }
+
+    if body.duration.trim().is_empty() || body.cover_image.trim().is_empty() {
+        return Err(bad_request(
+            "A duration and cover image are required to publish a program.",
+        ));
+    }
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let res = diesel::update(programs)
+            .filter(id.eq(program.id))
+            .set((
+                published.eq(true),
+                published_at.eq(program.published_at.unwrap_or_else(chrono::Utc::now)),
+                price_tier.eq(body.price_tier),
+                level.eq(body.level),
+                duration.eq(body.duration),
+                program_image.eq(body.cover_image),
+            ))
+            .returning(Program::as_returning())
+            .get_result::<Program>(conn)?;
+
+        record_program_revision(conn, res.id, req_user_id)?;
+
+        Ok(res)
+    })?;
+
+    Ok(Json(res))
+}
+
+/// Takes the program out of the marketplace, copies that were already claimed are kept.
+async fn unpublish_program(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Program>> {
+    use crate::schema::programs::dsl::*;
+
+    let res = diesel::update(programs)
+        .filter(id.eq(path_program_id).and(owner_id.eq(req_user_id)))
+        .set((
+            published.eq(false),
+            published_at.eq(None::<chrono::DateTime<chrono::Utc>>),
+        ))
+        .returning(Program::as_returning())
+        .get_result::<Program>(&mut state.db_pool.get_conn())?;
+
+    Ok(Json(res))
+}
+
+async fn get_program_schedule(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Vec<ScheduledWorkout>>> {
+    use crate::schema::programs::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    guard_program_owner_or_client(req_user_id, &program, &mut conn, CollaboratorRoles::Viewer)?;
+
+    let schedule = load_program_schedule(&mut conn, &program)?
+        .ok_or_else(|| bad_request("Program does not have a start date."))?;
+
+    Ok(Json(schedule))
+}
+
+/// Sets per muscle group over the whole program and per week, archived workouts are left out.
+async fn get_program_volume(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+) -> AppResult<Json<ProgramVolume>> {
+    use crate::schema::{programs::dsl::*, workouts::dsl as w_dsl};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    guard_program_owner_or_client(req_user_id, &program, &mut conn, CollaboratorRoles::Viewer)?;
+
+    let workout_ids: Vec<uuid::Uuid> = Workout::belonging_to(&program)
+        .filter(w_dsl::archived_at.is_null())
+        .select(w_dsl::id)
+        .load(&mut conn)?;
+
+    let rows = load_volume_rows(&mut conn, &workout_ids)?;
+
+    Ok(Json(summarize_program_volume(rows)))
+}
+
+/// Feedback averaged per workout, so trainers can spot the workouts clients keep rating too hard.
+async fn get_program_feedback(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Vec<WorkoutFeedbackSummary>>> {
+    use crate::schema::{programs::dsl::*, workouts::dsl as w_dsl};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    guard_program_owner_or_client(req_user_id, &program, &mut conn, CollaboratorRoles::Viewer)?;
+
+    let program_workouts: Vec<Workout> = Workout::belonging_to(&program)
+        .filter(w_dsl::archived_at.is_null())
+        .order((w_dsl::week.asc(), w_dsl::sequence.asc()))
+        .select(Workout::as_select())
+        .load(&mut conn)?;
+
+    let feedback: Vec<WorkoutFeedback> = WorkoutFeedback::belonging_to(&program_workouts)
+        .select(WorkoutFeedback::as_select())
+        .load(&mut conn)?;
+
+    let discomfort: Vec<ExerciseDiscomfort> = ExerciseDiscomfort::belonging_to(&feedback)
+        .select(ExerciseDiscomfort::as_select())
+        .load(&mut conn)?;
+
+    let grouped = discomfort
+        .grouped_by(&feedback)
+        .into_iter()
+        .zip(feedback)
+        .map(|(discomfort, feedback)| (feedback, discomfort))
+        .collect::<Vec<_>>()
+        .grouped_by(&program_workouts);
+
+    Ok(Json(summarize_feedback(&program_workouts, &grouped)))
+}
+
+/// Overall and per week completion of the program, the client's streaks and the next workout due.
+async fn get_program_progress(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+) -> AppResult<Json<ProgramProgressResponse>> {
+    use crate::schema::{program_progress::dsl as pp_dsl, programs::dsl::*};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    guard_program_owner_or_client(req_user_id, &program, &mut conn, CollaboratorRoles::Viewer)?;
+
+    let stored: Option<ProgramProgress> = pp_dsl::program_progress
+        .filter(pp_dsl::program_id.eq(program.id))
+        .select(ProgramProgress::as_select())
+        .first(&mut conn)
+        .optional()?;
+
+    // programs that haven't had a workout change since progress tracking was added
+    let progress = match stored {
+        Some(progress) => progress,
+        None => conn.transaction::<_, BoxedAppError, _>(|conn| {
+            Ok(sync_program_progress(conn, program.id)?)
+        })?,
+    };
+
+    let next_workout = match progress.next_workout_id {
+        Some(next_id) => {
+            let date = load_program_schedule(&mut conn, &program)?.and_then(|schedule| {
+                schedule
+                    .into_iter()
+                    .find(|scheduled| scheduled.workout.id == next_id)
+                    .map(|scheduled| scheduled.date)
+            });
+
+            let workout: Workout = Workout::belonging_to(&program)
+                .filter(crate::schema::workouts::id.eq(next_id))
+                .select(Workout::as_select())
+                .first(&mut conn)?;
+
+            Some(NextWorkout { workout, date })
+        }
+        None => None,
+    };
+
+    let res = ProgramProgressResponse {
+        program_id: program.id,
+        complete: progress.total_workouts > 0
+            && progress.completed_workouts == progress.total_workouts,
+        total_workouts: progress.total_workouts,
+        completed_workouts: progress.completed_workouts,
+        percent_complete: percent(progress.completed_workouts, progress.total_workouts),
+        weeks: progress.weeks,
+        current_streak: progress.current_streak,
+        longest_streak: progress.longest_streak,
+        last_completed_at: progress.last_completed_at,
+        next_workout,
+        updated_at: progress.updated_at,
+    };
+
+    Ok(Json(res))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RescheduleProgram {
+    /// Date the next incomplete workout should move to, defaults to today.
+    pub from: Option<chrono::NaiveDate>,
+}
+
+/// Pushes the program's incomplete workouts forward so the next one is due on `from`, keeping the
+/// spacing between the rest of them.
+async fn reschedule_program(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<RescheduleProgram>,
+) -> AppResult<Json<Vec<ScheduledWorkout>>> {
+    use crate::schema::{programs::dsl::*, workouts::dsl as w_dsl};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    guard_program_owner_or_client(req_user_id, &program, &mut conn, CollaboratorRoles::Editor)?;
+
+    let from = body.from.unwrap_or_else(|| chrono::Utc::now().date_naive());
+
+    let schedule = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let schedule = load_program_schedule(conn, &program)?
+            .ok_or_else(|| bad_request("Program does not have a start date."))?;
+
+        let (program_workouts, planned): (Vec<Workout>, Vec<chrono::NaiveDate>) = schedule
+            .into_iter()
+            .map(|scheduled| (scheduled.workout, scheduled.date))
+            .unzip();
+
+        let new_dates = reschedule_from(from, &program.training_days, &program_workouts, &planned);
+
+        for (workout, new_date) in program_workouts.iter().zip(new_dates) {
+            if let Some(new_date) = new_date {
+                diesel::update(w_dsl::workouts)
+                    .filter(w_dsl::id.eq(workout.id))
+                    .set(w_dsl::scheduled_date.eq(new_date))
+                    .execute(conn)?;
+            }
+        }
+
+        let schedule = load_program_schedule(conn, &program)?.unwrap_or_default();
+
+        Ok(schedule)
+    })?;
+
+    Ok(Json(schedule))
+}
+
+/// Only the owner of the program, the client it's assigned to and collaborators granted at least
+/// `min_role` can access it.
+pub fn guard_program_owner_or_client(
+    req_user_id: uuid::Uuid,
+    program: &Program,
+    conn: &mut DbConnection,
+    min_role: CollaboratorRoles,
+) -> AppResult<()> {
+    use crate::schema::clients::dsl as c_dsl;
+
+    if has_program_access(conn, req_user_id, program, min_role)? {
+        return Ok(());
+    }
+
+    if let Some(program_client_id) = program.client_id {
+        let client_user_id: Option<uuid::Uuid> = c_dsl::clients
+            .filter(c_dsl::id.eq(program_client_id))
+            .select(c_dsl::user_id)
+            .first(conn)?;
+
+        if client_user_id == Some(req_user_id) {
+            return Ok(());
+        }
+    }
+
+    Err(unauthorized())
+}
+
+async fn list_program_revisions(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+    pagination: QueryExtractor<PaginationParams>,
+) -> AppResult<Json<PaginatedResponse<ProgramRevisionSummary>>> {
+    use crate::schema::{program_revisions::dsl as r_dsl, programs::dsl::*};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    guard_program_owner_or_client(req_user_id, &program, &mut conn, CollaboratorRoles::Viewer)?;
+
+    let query = r_dsl::program_revisions
+        .filter(r_dsl::program_id.eq(program.id))
+        .order(r_dsl::revision.desc())
+        .select(ProgramRevisionSummary::as_select())
+        .pages_pagination(PaginationOptions::new(pagination.0)?);
+
+    let data: Paginated<ProgramRevisionSummary> = query.load(&mut conn)?;
+
+    Ok(Json(data.into()))
+}
+
+async fn get_program_revision(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path((path_program_id, path_revision)): Path<(uuid::Uuid, i32)>,
+) -> AppResult<Json<ProgramRevision>> {
+    use crate::schema::programs::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    guard_program_owner_or_client(req_user_id, &program, &mut conn, CollaboratorRoles::Viewer)?;
+
+    let res = find_program_revision(program.id, path_revision, &mut conn)?;
+
+    Ok(Json(res))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DiffParams {
+    pub from: i32,
+    pub to: i32,
+}
+
+async fn diff_program_revisions(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+    QueryExtractor(params): QueryExtractor<DiffParams>,
+) -> AppResult<Json<ProgramDiff>> {
+    use crate::schema::programs::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    guard_program_owner_or_client(req_user_id, &program, &mut conn, CollaboratorRoles::Viewer)?;
+
+    let from = find_program_revision(program.id, params.from, &mut conn)?;
+    let to = find_program_revision(program.id, params.to, &mut conn)?;
+
+    Ok(Json(ProgramDiff::between(&from, &to)))
+}
+
+/// Rolls the program back to an earlier revision. The restore itself is recorded as a new
+/// revision so it can be undone the same way.
+async fn restore_program_revision(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path((path_program_id, path_revision)): Path<(uuid::Uuid, i32)>,
+) -> AppResult<Json<ProgramWithWorkouts>> {
+    use crate::schema::{programs::dsl::*, users::dsl as u_dsl};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let req_user_is_admin: bool = u_dsl::users
+        .filter(u_dsl::id.eq(req_user_id))
+        .select(u_dsl::is_admin)
+        .first(&mut conn)?;
+
+    let program: Program = programs
+        .filter(id.eq(path_program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    if !req_user_is_admin
+        && !has_program_access(&mut conn, req_user_id, &program, CollaboratorRoles::Editor)?
+    {
+        return Err(unauthorized());
+    }
+
+    let revision = find_program_revision(program.id, path_revision, &mut conn)?;
+
+    let snapshot: ProgramWithWorkouts = serde_json::from_value(revision.snapshot)
+        .map_err(|_| bad_request("This revision can no longer be restored."))?;
+
+    let restored = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        restore_program_snapshot(conn, &program, snapshot)?;
+
+        sync_program_progress(conn, program.id)?;
+
+        record_program_revision(conn, program.id, req_user_id)?;
+
+        let restored: Program = programs
+            .filter(id.eq(program.id))
+            .select(Program::as_select())
+            .first(conn)?;
+
+        Ok(load_program_with_workouts(restored, conn)?)
+    })?;
+
+    Ok(Json(restored))
+}
+
+fn find_program_revision(
+    path_program_id: uuid::Uuid,
+    path_revision: i32,
+    conn: &mut DbConnection,
+) -> QueryResult<ProgramRevision> {
+    use crate::schema::program_revisions::dsl::*;
+
+    program_revisions
+        .filter(
+            program_id
+                .eq(path_program_id)
+                .and(revision.eq(path_revision)),
+        )
+        .select(ProgramRevision::as_select())
+        .first(conn)
+}
@@ -0,0 +1,222 @@
+use crate::{
+    db::{
+        collaborators::client_role,
+        models::{
+            client::Client,
+            exercise_catalog::CatalogExercise,
+            personal_record::{
+                LeaderboardEntry, LeaderboardParams, PersonalRecord, RecordKinds,
+                RecordListingParams, RecordWithCatalog,
+            },
+            user::{PublicUser, PUBLIC_USER_COLUMNS},
+        },
+        DbConnection,
+    },
+    error::{bad_request, unauthorized},
+    pagination::*,
+    server::AppState,
+    types::AppResult,
+    util::extractors::{QueryExtractor, UserIdExtractor},
+};
+use axum::{extract::State, routing::get, Json, Router};
+use diesel::prelude::*;
+use std::sync::Arc;
+
+/// Personal records are derived from logged sets, see `update_records`.
+pub fn record_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_records))
+        .route("/history", get(list_record_history))
+        .route("/leaderboard", get(get_leaderboard))
+}
+
+/// Current records of a user, one per catalog entry, kind and rep count.
+async fn list_records(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    params: QueryExtractor<RecordListingParams>,
+) -> AppResult<Json<Vec<RecordWithCatalog>>> {
+    use crate::schema::{exercise_catalog as ec, personal_records::dsl::*};
+
+    let params = params.0;
+    let mut conn = state.db_pool.get_conn();
+
+    let record_user_id = params.user.unwrap_or(req_user_id);
+    guard_record_viewer(&mut conn, req_user_id, record_user_id)?;
+
+    let mut query = personal_records
+        .inner_join(ec::table)
+        .filter(user_id.eq(record_user_id))
+        .distinct_on((catalog_id, kind, reps))
+        .into_boxed();
+
+    if let Some(catalog_filter) = params.catalog {
+        query = query.filter(catalog_id.eq(catalog_filter));
+    }
+
+    if let Some(kind_filter) = params.kind {
+        query = query.filter(kind.eq(kind_filter));
+    }
+
+    let mut res: Vec<(PersonalRecord, CatalogExercise)> = query
+        .order_by((catalog_id, kind, reps, value.desc(), achieved_at.asc()))
+        .select((PersonalRecord::as_select(), CatalogExercise::as_select()))
+        .load(&mut conn)?;
+
+    res.sort_by(|(a, a_catalog), (b, b_catalog)| {
+        a_catalog
+            .name
+            .cmp(&b_catalog.name)
+            .then_with(|| a.catalog_id.cmp(&b.catalog_id))
+            .then_with(|| a.kind.cmp(&b.kind))
+            .then_with(|| a.reps.cmp(&b.reps))
+    });
+
+    Ok(Json(
+        res.into_iter()
+            .map(|(record, catalog)| RecordWithCatalog { record, catalog })
+            .collect(),
+    ))
+}
+
+/// Every record a user set, including the ones they beat since, newest first.
+async fn list_record_history(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    params: QueryExtractor<RecordListingParams>,
+    pagination: QueryExtractor<PaginationParams>,
+) -> AppResult<Json<PaginatedResponse<RecordWithCatalog>>> {
+    use crate::schema::{exercise_catalog as ec, personal_records::dsl::*};
+
+    let params = params.0;
+    let mut conn = state.db_pool.get_conn();
+
+    let record_user_id = params.user.unwrap_or(req_user_id);
+    guard_record_viewer(&mut conn, req_user_id, record_user_id)?;
+
+    let mut query = personal_records
+        .inner_join(ec::table)
+        .filter(user_id.eq(record_user_id))
+        .into_boxed();
+
+    if let Some(catalog_filter) = params.catalog {
+        query = query.filter(catalog_id.eq(catalog_filter));
+    }
+
+    if let Some(kind_filter) = params.kind {
+        query = query.filter(kind.eq(kind_filter));
+    }
+
+    let data: Paginated<RecordWithCatalog> = query
+        .order_by((achieved_at.desc(), id.asc()))
+        .select(RecordWithCatalog::as_select())
+        .pages_pagination(PaginationOptions::new(pagination.0)?)
+        .load(&mut conn)?;
+
+    let total = data.total();
+
+    Ok(Json(PaginatedResponse::new(
+        data.into_iter().collect(),
+        total,
+    )))
+}
+
+/// Best record of the requesting user and of the clients they coach, or that were shared with
+/// them, for one catalog entry and kind. Users with the same value share a rank.
+async fn get_leaderboard(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    params: QueryExtractor<LeaderboardParams>,
+) -> AppResult<Json<Vec<LeaderboardEntry>>> {
+    use crate::schema::{
+        client_collaborators as cc, clients as c, personal_records::dsl::*, users as u,
+    };
+
+    let params = params.0;
+
+    if params.kind == RecordKinds::RepMax && params.reps < 1 {
+        return Err(bad_request("reps must be at least 1 for rep maxes."));
+    }
+
+    let board_reps = match params.kind {
+        RecordKinds::RepMax => params.reps,
+        _ => 0,
+    };
+
+    let mut conn = state.db_pool.get_conn();
+
+    let shared_client_ids = cc::table
+        .filter(cc::user_id.eq(req_user_id))
+        .select(cc::client_id);
+
+    let mut participants: Vec<uuid::Uuid> = c::table
+        .filter(
+            c::trainer_id
+                .eq(req_user_id)
+                .or(c::id.eq_any(shared_client_ids)),
+        )
+        .filter(c::user_id.is_not_null())
+        .select(c::user_id.assume_not_null())
+        .load(&mut conn)?;
+
+    participants.push(req_user_id);
+
+    let mut best: Vec<(PersonalRecord, PublicUser)> = personal_records
+        .inner_join(u::table)
+        .filter(user_id.eq_any(participants))
+        .filter(catalog_id.eq(params.catalog))
+        .filter(kind.eq(params.kind))
+        .filter(reps.eq(board_reps))
+        .distinct_on(user_id)
+        .order_by((user_id, value.desc(), achieved_at.asc()))
+        .select((PersonalRecord::as_select(), PUBLIC_USER_COLUMNS))
+        .load(&mut conn)?;
+
+    // whoever got there first goes first on ties
+    best.sort_by(|(a, _), (b, _)| {
+        b.value
+            .total_cmp(&a.value)
+            .then_with(|| a.achieved_at.cmp(&b.achieved_at))
+    });
+
+    let mut res: Vec<LeaderboardEntry> = vec![];
+
+    for (idx, (record, user)) in best.into_iter().enumerate() {
+        let rank = match res.last() {
+            Some(prev) if prev.record.value == record.value => prev.rank,
+            _ => idx as i64 + 1,
+        };
+
+        res.push(LeaderboardEntry { rank, user, record });
+    }
+
+    Ok(Json(res))
+}
+
+/// Users see their own records, trainers and their collaborators the records of their clients.
+fn guard_record_viewer(
+    conn: &mut DbConnection,
+    req_user_id: uuid::Uuid,
+    record_user_id: uuid::Uuid,
+) -> AppResult<()> {
+    use crate::schema::clients::dsl::*;
+
+    if req_user_id == record_user_id {
+        return Ok(());
+    }
+
+    let user_clients: Vec<Client> = clients
+        .filter(user_id.eq(record_user_id))
+        .select(Client::as_select())
+        .load(conn)?;
+
+    for client in user_clients {
+        if client.trainer_id == Some(req_user_id)
+            || client_role(conn, req_user_id, client.id)?.is_some()
+        {
+            return Ok(());
+        }
+    }
+
+    Err(unauthorized())
+}
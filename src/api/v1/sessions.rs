@@ -0,0 +1,407 @@
+use crate::{
+    api::v1::workouts::guard_workout_owner_or_program_client,
+    db::{
+        models::{
+            collaborator::CollaboratorRoles,
+            exercise::{Exercise, ExerciseWithCatalog},
+            exercise_catalog::CatalogExercise,
+            notification::NewNotification,
+            personal_record::PersonalRecord,
+            workout::Workout,
+            workout_session::{
+                validate_set_values, ExerciseComparison, FinishSession, NewSetLog, PatchSetLog,
+                SessionComparison, SetLog, SetLogWithRecords, WorkoutSession,
+            },
+        },
+        programs::{record_program_revision, sync_program_progress},
+        records::{drop_set_records, update_records},
+        DbConnection,
+    },
+    error::{bad_request, unauthorized, BoxedAppError},
+    server::AppState,
+    types::AppResult,
+    util::extractors::{JsonExtractor, Path, UserIdExtractor},
+};
+use axum::{
+    extract::State,
+    routing::{get, patch, post},
+    Json, Router,
+};
+use diesel::{insert_into, pg::upsert::excluded, prelude::*, update};
+use std::sync::Arc;
+
+pub fn session_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/:session_id", get(get_session))
+        .route("/:session_id/finish", post(finish_session))
+        .route("/:session_id/sets", post(log_set))
+        .route(
+            "/:session_id/sets/:set_id",
+            patch(update_set).delete(delete_set),
+        )
+}
+
+/// The session with every exercise of the workout as prescribed next to the logged sets.
+/// Readable by whoever logged it and by anyone who can view the workout.
+async fn get_session(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(session_id): Path<uuid::Uuid>,
+) -> AppResult<Json<SessionComparison>> {
+    let mut conn = state.db_pool.get_conn();
+
+    let session = load_session(&mut conn, session_id)?;
+
+    if session.user_id != req_user_id {
+        guard_workout_owner_or_program_client(
+            req_user_id,
+            session.workout_id,
+            state.db_pool.get_conn(),
+            CollaboratorRoles::Viewer,
+        )
+        .await?;
+    }
+
+    Ok(Json(load_session_comparison(&mut conn, session)?))
+}
+
+/// Logs a set and updates the personal records it beats, the user is congratulated on new ones.
+async fn log_set(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_session_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<NewSetLog>,
+) -> AppResult<Json<SetLogWithRecords>> {
+    use crate::schema::{exercises::dsl as e_dsl, set_logs::dsl::*};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let session = guard_open_session(&mut conn, req_user_id, path_session_id)?;
+
+    validate_set_values(
+        Some(body.reps),
+        body.rpe,
+        body.load,
+        body.load_unit.as_deref(),
+        body.duration_seconds,
+    )
+    .map_err(bad_request)?;
+
+    if body.set_number.is_some_and(|n| n < 1) {
+        return Err(bad_request("set_number must be at least 1."));
+    }
+
+    let exercise: Exercise = e_dsl::exercises
+        .filter(
+            e_dsl::id
+                .eq(body.exercise_id)
+                .and(e_dsl::workout_id.eq(session.workout_id))
+                .and(e_dsl::archived_at.is_null()),
+        )
+        .select(Exercise::as_select())
+        .first(&mut conn)
+        .optional()?
+        .ok_or_else(|| bad_request("The exercise isn't part of this workout."))?;
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let number = match body.set_number {
+            Some(n) => n,
+            None => {
+                let last: Option<i32> = set_logs
+                    .filter(session_id.eq(session.id).and(exercise_id.eq(exercise.id)))
+                    .select(diesel::dsl::max(set_number))
+                    .first(conn)?;
+
+                last.unwrap_or(0) + 1
+            }
+        };
+
+        let res = insert_into(set_logs)
+            .values((
+                session_id.eq(session.id),
+                exercise_id.eq(exercise.id),
+                set_number.eq(number),
+                reps.eq(body.reps),
+                load.eq(body.load),
+                load_unit.eq(body.load_unit.clone().unwrap_or(exercise.load_unit.clone())),
+                rpe.eq(body.rpe),
+                notes.eq(body.notes.clone()),
+                duration_seconds.eq(body.duration_seconds),
+            ))
+            .on_conflict((session_id, exercise_id, set_number))
+            .do_update()
+            .set((
+                reps.eq(excluded(reps)),
+                load.eq(excluded(load)),
+                load_unit.eq(excluded(load_unit)),
+                rpe.eq(excluded(rpe)),
+                notes.eq(excluded(notes)),
+                duration_seconds.eq(excluded(duration_seconds)),
+            ))
+            .returning(SetLog::as_returning())
+            .get_result(conn)?;
+
+        let records = update_records(conn, session.user_id, exercise.catalog_id, &res)?;
+        send_record_notification(conn, session.user_id, exercise.catalog_id, &records)?;
+
+        Ok(SetLogWithRecords { set: res, records })
+    })?;
+
+    Ok(Json(res))
+}
+
+async fn update_set(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path((path_session_id, set_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+    JsonExtractor(body): JsonExtractor<PatchSetLog>,
+) -> AppResult<Json<SetLogWithRecords>> {
+    use crate::schema::{exercises::dsl as e_dsl, set_logs::dsl::*};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let session = guard_open_session(&mut conn, req_user_id, path_session_id)?;
+
+    if body.is_empty() {
+        return Err(bad_request("Nothing to update."));
+    }
+
+    validate_set_values(
+        body.reps,
+        body.rpe,
+        body.load,
+        body.load_unit.as_deref(),
+        body.duration_seconds,
+    )
+    .map_err(bad_request)?;
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let res: SetLog = update(set_logs)
+            .filter(id.eq(set_id).and(session_id.eq(session.id)))
+            .set(body)
+            .returning(SetLog::as_returning())
+            .get_result(conn)?;
+
+        let set_catalog_id: uuid::Uuid = e_dsl::exercises
+            .filter(e_dsl::id.eq(res.exercise_id))
+            .select(e_dsl::catalog_id)
+            .first(conn)?;
+
+        let records = update_records(conn, session.user_id, set_catalog_id, &res)?;
+        send_record_notification(conn, session.user_id, set_catalog_id, &records)?;
+
+        Ok(SetLogWithRecords { set: res, records })
+    })?;
+
+    Ok(Json(res))
+}
+
+async fn delete_set(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path((path_session_id, set_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+) -> AppResult<Json<serde_json::Value>> {
+    use crate::schema::{exercises::dsl as e_dsl, set_logs::dsl::*};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let session = guard_open_session(&mut conn, req_user_id, path_session_id)?;
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let set_catalog_id: Option<uuid::Uuid> = set_logs
+            .inner_join(e_dsl::exercises)
+            .filter(id.eq(set_id).and(session_id.eq(session.id)))
+            .select(e_dsl::catalog_id)
+            .first(conn)
+            .optional()?;
+
+        if let Some(set_catalog_id) = set_catalog_id {
+            drop_set_records(conn, session.user_id, set_catalog_id, set_id)?;
+        }
+
+        let res: usize =
+            diesel::delete(set_logs.filter(id.eq(set_id).and(session_id.eq(session.id))))
+                .execute(conn)?;
+
+        Ok(res)
+    })?;
+
+    Ok(Json(serde_json::json!({"deleted": res})))
+}
+
+/// Closes the session and marks its workout complete, which moves the program progress along.
+async fn finish_session(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_session_id): Path<uuid::Uuid>,
+    body: Option<JsonExtractor<FinishSession>>,
+) -> AppResult<Json<SessionComparison>> {
+    use crate::schema::{workout_sessions::dsl as s_dsl, workouts::dsl as w_dsl};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let session = guard_open_session(&mut conn, req_user_id, path_session_id)?;
+    let body = body.map(|b| b.0).unwrap_or_default();
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let session: WorkoutSession = update(s_dsl::workout_sessions)
+            .filter(s_dsl::id.eq(session.id))
+            .set((
+                s_dsl::finished_at.eq(chrono::Utc::now()),
+                s_dsl::notes.eq(body.notes.unwrap_or(session.notes)),
+            ))
+            .returning(WorkoutSession::as_returning())
+            .get_result(conn)?;
+
+        let previously_completed: Option<chrono::DateTime<chrono::Utc>> = w_dsl::workouts
+            .filter(w_dsl::id.eq(session.workout_id))
+            .select(w_dsl::completed_at)
+            .first(conn)?;
+
+        // keep the time of the first completion if the workout was done before
+        let workout: Workout = update(w_dsl::workouts)
+            .filter(w_dsl::id.eq(session.workout_id))
+            .set((
+                w_dsl::complete.eq(true),
+                w_dsl::completed_at.eq(previously_completed.or(session.finished_at)),
+            ))
+            .returning(Workout::as_returning())
+            .get_result(conn)?;
+
+        if let Some(prog_id) = workout.program_id {
+            sync_program_progress(conn, prog_id)?;
+            record_program_revision(conn, prog_id, req_user_id)?;
+        }
+
+        load_session_comparison(conn, session)
+    })?;
+
+    Ok(Json(res))
+}
+
+fn load_session(conn: &mut DbConnection, session_id: uuid::Uuid) -> QueryResult<WorkoutSession> {
+    use crate::schema::workout_sessions::dsl::*;
+
+    workout_sessions
+        .filter(id.eq(session_id))
+        .select(WorkoutSession::as_select())
+        .first(conn)
+}
+
+/// Congratulates the user on the records a set beat, one notification per set.
+fn send_record_notification(
+    conn: &mut DbConnection,
+    user_id: uuid::Uuid,
+    catalog_id: uuid::Uuid,
+    records: &[PersonalRecord],
+) -> AppResult<()> {
+    use crate::schema::exercise_catalog::dsl as ec_dsl;
+
+    // the rep max is the most telling, it comes first
+    let Some(headline) = records.first() else {
+        return Ok(());
+    };
+
+    let exercise_name: String = ec_dsl::exercise_catalog
+        .filter(ec_dsl::id.eq(catalog_id))
+        .select(ec_dsl::name)
+        .first(conn)?;
+
+    let content = match records.len() {
+        1 => format!("New {} on {exercise_name}.", headline.describe()),
+        n => format!(
+            "{n} new records on {exercise_name}, including {} {}.",
+            headline.article(),
+            headline.describe()
+        ),
+    };
+
+    let d: serde_json::Value = serde_json::json!({
+        "catalog_id": catalog_id.to_string(),
+        "set_log_id": headline.set_log_id.to_string(),
+    });
+
+    NewNotification::new(
+        user_id,
+        user_id,
+        "New personal record!".into(),
+        content,
+        "record".into(),
+        "unread".into(),
+        Some(d),
+    )
+    .send(conn)?;
+
+    Ok(())
+}
+
+/// Only the user who started the session can log to it, and only until it's finished.
+fn guard_open_session(
+    conn: &mut DbConnection,
+    req_user_id: uuid::Uuid,
+    session_id: uuid::Uuid,
+) -> AppResult<WorkoutSession> {
+    let session = load_session(conn, session_id)?;
+
+    if session.user_id != req_user_id {
+        return Err(unauthorized());
+    }
+
+    if session.finished_at.is_some() {
+        return Err(bad_request("The session is already finished."));
+    }
+
+    Ok(session)
+}
+
+fn load_session_comparison(
+    conn: &mut DbConnection,
+    session: WorkoutSession,
+) -> AppResult<SessionComparison> {
+    use crate::schema::{exercises::dsl as e_dsl, set_logs::dsl as l_dsl, workouts::dsl as w_dsl};
+
+    let workout: Workout = w_dsl::workouts
+        .filter(w_dsl::id.eq(session.workout_id))
+        .select(Workout::as_select())
+        .first(conn)?;
+
+    let sets: Vec<SetLog> = SetLog::belonging_to(&session)
+        .order((l_dsl::exercise_id.asc(), l_dsl::set_number.asc()))
+        .select(SetLog::as_select())
+        .load(conn)?;
+
+    let logged_exercise_ids: Vec<uuid::Uuid> = sets.iter().map(|s| s.exercise_id).collect();
+
+    // exercises archived after they were logged still show up next to their sets
+    let (prescribed, catalog): (Vec<Exercise>, Vec<CatalogExercise>) =
+        Exercise::belonging_to(&workout)
+            .inner_join(crate::schema::exercise_catalog::table)
+            .filter(
+                e_dsl::archived_at
+                    .is_null()
+                    .or(e_dsl::id.eq_any(logged_exercise_ids)),
+            )
+            .order(e_dsl::sequence.asc())
+            .select((Exercise::as_select(), CatalogExercise::as_select()))
+            .load::<(Exercise, CatalogExercise)>(conn)?
+            .into_iter()
+            .unzip();
+
+    let grouped_sets = sets.grouped_by(&prescribed);
+
+    let exercises = prescribed
+        .into_iter()
+        .zip(catalog)
+        .zip(grouped_sets)
+        .map(|((exercise, catalog), actual)| ExerciseComparison {
+            prescribed: ExerciseWithCatalog { exercise, catalog },
+            actual,
+        })
+        .collect();
+
+    Ok(SessionComparison {
+        session,
+        workout,
+        exercises,
+    })
+}
@@ -0,0 +1,330 @@
+use crate::{
+    api::v1::workouts::guard_workout_owner_or_program_client,
+    db::{
+        collaborators::{client_role, has_program_access},
+        models::{
+            client::Client,
+            collaborator::CollaboratorRoles,
+            exercise::ExerciseWithCatalog,
+            exercise_catalog::CatalogExercise,
+            notification::NewNotification,
+            program::{Program, ProgramWithWorkouts},
+            workout::Workout,
+        },
+        programs::{load_program_with_workouts, record_program_revision},
+        taxonomy::load_muscle_links,
+        DbConnection,
+    },
+    error::{bad_request, unauthorized, BoxedAppError},
+    server::AppState,
+    types::AppResult,
+    util::{
+        extractors::{JsonExtractor, Path, QueryExtractor, UserIdExtractor},
+        parse_slugs,
+        substitution::{
+            score_substitute, ExerciseProfile, Substitute, SubstituteParams, SwapExercise,
+            DEFAULT_SUBSTITUTES, MAX_SUBSTITUTES,
+        },
+    },
+};
+use axum::{extract::State, Json};
+use diesel::{dsl::not, prelude::*, update};
+use std::sync::Arc;
+
+/// Catalog entries that could replace the exercise, best first. Candidates share at least a
+/// muscle or the movement pattern with the exercise and are scored with `score_substitute`.
+///
+/// Entries needing equipment the client doesn't have are left out, as are the ones training any
+/// of `avoid_muscles`. Clients without any equipment recorded aren't filtered on equipment.
+pub async fn list_exercise_substitutes(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(exercise_id): Path<uuid::Uuid>,
+    params: QueryExtractor<SubstituteParams>,
+) -> AppResult<Json<Vec<Substitute>>> {
+    use crate::schema::{
+        catalog_equipment as ce, catalog_muscles as cm, client_equipment as cle, clients as c,
+        exercise_catalog as ec, exercises as e, muscles as m, programs as p, workouts as w,
+    };
+
+    let params = params.0;
+    let limit = params.limit.unwrap_or(DEFAULT_SUBSTITUTES);
+
+    if !(1..=MAX_SUBSTITUTES).contains(&limit) {
+        return Err(bad_request(format!(
+            "limit must be between 1 and {MAX_SUBSTITUTES}."
+        )));
+    }
+
+    let exercise: ExerciseWithCatalog = e::table
+        .inner_join(ec::table)
+        .filter(e::id.eq(exercise_id))
+        .select(ExerciseWithCatalog::as_select())
+        .first(&mut state.db_pool.get_conn())?;
+
+    match exercise.exercise.workout_id {
+        Some(exercise_workout_id) => {
+            guard_workout_owner_or_program_client(
+                req_user_id,
+                exercise_workout_id,
+                state.db_pool.get_conn(),
+                CollaboratorRoles::Viewer,
+            )
+            .await?
+        }
+        None if exercise.exercise.owner_id == Some(req_user_id) => {}
+        None => return Err(unauthorized()),
+    }
+
+    let mut conn = state.db_pool.get_conn();
+
+    let client_id = match params.client {
+        Some(client_id) => {
+            guard_client_viewer(&mut conn, req_user_id, client_id)?;
+            Some(client_id)
+        }
+        None => match exercise.exercise.workout_id {
+            Some(exercise_workout_id) => w::table
+                .inner_join(p::table)
+                .filter(w::id.eq(exercise_workout_id))
+                .select(p::client_id)
+                .first::<Option<uuid::Uuid>>(&mut conn)
+                .optional()?
+                .flatten(),
+            None => None,
+        },
+    };
+
+    let source_id = exercise.catalog.id;
+    let source = ExerciseProfile {
+        muscles: load_muscle_links(&mut conn, &[source_id])?
+            .into_iter()
+            .map(|(_, muscle_id, role)| (muscle_id, role))
+            .collect(),
+        movement_pattern: exercise.catalog.movement_pattern,
+        intensity: Some(exercise.exercise.intensity),
+    };
+
+    let source_muscle_ids: Vec<uuid::Uuid> = source.muscles.iter().map(|(id, _)| *id).collect();
+    let related = cm::table
+        .filter(cm::muscle_id.eq_any(source_muscle_ids))
+        .select(cm::catalog_id);
+
+    let mut query = ec::table
+        .filter(ec::id.ne(source_id))
+        .filter(ec::owner_id.is_null().or(ec::owner_id.eq(req_user_id)))
+        .into_boxed();
+
+    query = match source.movement_pattern {
+        Some(pattern) => query.filter(ec::id.eq_any(related).or(ec::movement_pattern.eq(pattern))),
+        None => query.filter(ec::id.eq_any(related)),
+    };
+
+    if let Some(avoid) = params.avoid_muscles.as_deref() {
+        let avoided = cm::table
+            .inner_join(m::table)
+            .filter(m::slug.eq_any(parse_slugs(avoid)))
+            .select(cm::catalog_id);
+
+        query = query.filter(not(ec::id.eq_any(avoided)));
+    }
+
+    if let Some(client_id) = client_id {
+        let equipment_recorded: bool = c::table
+            .find(client_id)
+            .select(c::equipment_recorded)
+            .first(&mut conn)?;
+
+        // clients whose equipment was never set aren't limited to bodyweight exercises
+        if equipment_recorded {
+            let owned: Vec<uuid::Uuid> = cle::table
+                .filter(cle::client_id.eq(client_id))
+                .select(cle::equipment_id)
+                .load(&mut conn)?;
+
+            let needs_other = ce::table
+                .filter(ce::equipment_id.ne_all(owned))
+                .select(ce::catalog_id);
+
+            query = query.filter(not(ec::id.eq_any(needs_other)));
+        }
+    }
+
+    let candidates: Vec<CatalogExercise> = query
+        .order_by((ec::name.asc(), ec::id.asc()))
+        .select(CatalogExercise::as_select())
+        .load(&mut conn)?;
+
+    let links = load_muscle_links(
+        &mut conn,
+        &candidates.iter().map(|c| c.id).collect::<Vec<_>>(),
+    )?;
+
+    let mut res: Vec<Substitute> = candidates
+        .into_iter()
+        .map(|catalog| {
+            let candidate = ExerciseProfile {
+                muscles: links
+                    .iter()
+                    .filter(|(c_id, ..)| *c_id == catalog.id)
+                    .map(|(_, muscle_id, role)| (*muscle_id, *role))
+                    .collect(),
+                movement_pattern: catalog.movement_pattern,
+                intensity: catalog.intensity.clone(),
+            };
+
+            let (score, muscle_score, same_movement, intensity_score) =
+                score_substitute(&source, &candidate);
+
+            Substitute {
+                catalog,
+                score,
+                muscle_score,
+                same_movement,
+                intensity_score,
+            }
+        })
+        .collect();
+
+    // candidates are loaded by name, the stable sort keeps ties in that order
+    res.sort_by(|a, b| b.score.total_cmp(&a.score));
+    res.truncate(limit as usize);
+
+    Ok(Json(res))
+}
+
+/// Replaces a catalog entry with another one in every exercise of the program, keeping their
+/// prescriptions. The client of the program has to have the equipment of the new entry, when their
+/// equipment is recorded, and is notified of the swap.
+pub async fn swap_program_exercise(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_program_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<SwapExercise>,
+) -> AppResult<Json<ProgramWithWorkouts>> {
+    use crate::schema::{
+        catalog_equipment as ce, client_equipment as cle, clients as c, exercise_catalog as ec,
+        exercises as e, programs as p, workouts as w,
+    };
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = p::table
+        .filter(p::id.eq(path_program_id).and(p::archived_at.is_null()))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    if !has_program_access(&mut conn, req_user_id, &program, CollaboratorRoles::Editor)? {
+        return Err(unauthorized());
+    }
+
+    if body.from_catalog_id == body.to_catalog_id {
+        return Err(bad_request("The exercise can't be swapped with itself."));
+    }
+
+    let new_entry: CatalogExercise = ec::table
+        .filter(ec::id.eq(body.to_catalog_id))
+        .filter(ec::owner_id.is_null().or(ec::owner_id.eq(req_user_id)))
+        .select(CatalogExercise::as_select())
+        .first(&mut conn)
+        .optional()?
+        .ok_or_else(|| bad_request("The catalog entry to swap in does not exist."))?;
+
+    let client: Option<Client> = match program.client_id {
+        Some(program_client_id) => Some(
+            c::table
+                .filter(c::id.eq(program_client_id))
+                .select(Client::as_select())
+                .first(&mut conn)?,
+        ),
+        None => None,
+    };
+
+    if let Some(client) = client.as_ref().filter(|client| client.equipment_recorded) {
+        let owned: Vec<uuid::Uuid> = cle::table
+            .filter(cle::client_id.eq(client.id))
+            .select(cle::equipment_id)
+            .load(&mut conn)?;
+
+        let missing: i64 = ce::table
+            .filter(ce::catalog_id.eq(new_entry.id))
+            .filter(not(ce::equipment_id.eq_any(owned)))
+            .count()
+            .get_result(&mut conn)?;
+
+        if missing > 0 {
+            return Err(bad_request(
+                "The client doesn't have the equipment the exercise needs.",
+            ));
+        }
+    }
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let program_workouts = Workout::belonging_to(&program)
+            .filter(w::archived_at.is_null())
+            .select(w::id);
+
+        let swapped = update(
+            e::table
+                .filter(e::workout_id.eq_any(program_workouts.nullable()))
+                .filter(e::catalog_id.eq(body.from_catalog_id))
+                .filter(e::archived_at.is_null()),
+        )
+        .set(e::catalog_id.eq(new_entry.id))
+        .execute(conn)?;
+
+        if swapped == 0 {
+            return Err(bad_request(
+                "None of the program's exercises use the catalog entry.",
+            ));
+        }
+
+        record_program_revision(conn, program.id, req_user_id)?;
+
+        if let Some(client_user_id) = client.as_ref().and_then(|c| c.user_id) {
+            let d: serde_json::Value = serde_json::json!({
+                "program_id": program.id.to_string(),
+                "catalog_id": new_entry.id.to_string(),
+            });
+
+            let new_noti = NewNotification::new(
+                req_user_id,
+                client_user_id,
+                "Your program was updated".into(),
+                format!("{} now has {} in it.", program.name, new_entry.name),
+                "program".into(),
+                "unread".into(),
+                Some(d),
+            );
+
+            new_noti.send(conn)?;
+        }
+
+        Ok(load_program_with_workouts(program, conn)?)
+    })?;
+
+    Ok(Json(res))
+}
+
+/// The client, their trainer and the trainers it was shared with.
+fn guard_client_viewer(
+    conn: &mut DbConnection,
+    req_user_id: uuid::Uuid,
+    client_id: uuid::Uuid,
+) -> AppResult<()> {
+    use crate::schema::clients::dsl::*;
+
+    let client: Client = clients
+        .filter(id.eq(client_id))
+        .select(Client::as_select())
+        .first(conn)?;
+
+    if client.user_id == Some(req_user_id)
+        || client.trainer_id == Some(req_user_id)
+        || client_role(conn, req_user_id, client.id)?.is_some()
+    {
+        return Ok(());
+    }
+
+    Err(unauthorized())
+}
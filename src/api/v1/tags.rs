@@ -0,0 +1,163 @@
+use crate::{
+    db::{
+        models::tag::{NewTag, PatchTag, Tag, TagCategories},
+        users::guard_admin,
+    },
+    error::{bad_request, BoxedAppError},
+    server::AppState,
+    types::AppResult,
+    util::{
+        extractors::{JsonExtractor, Path, QueryExtractor, UserIdExtractor},
+        name_slug,
+    },
+};
+use axum::{extract::State, routing::get, Json, Router};
+use diesel::{
+    insert_into,
+    prelude::*,
+    result::{DatabaseErrorKind, Error as DieselError},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// The tag vocabulary is curated by admins, everyone else can only list it and tag their own
+/// programs and workouts with it.
+pub fn tag_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_tags).post(create_tag))
+        .route(
+            "/:tag_id",
+            get(get_tag).patch(update_tag).delete(delete_tag),
+        )
+}
+
+#[derive(Deserialize, Debug)]
+struct TagListingParams {
+    category: Option<TagCategories>,
+}
+
+async fn list_tags(
+    State(state): State<Arc<AppState>>,
+    params: QueryExtractor<TagListingParams>,
+) -> AppResult<Json<Vec<Tag>>> {
+    use crate::schema::tags::dsl::*;
+
+    let mut base = tags.into_boxed();
+
+    if let Some(tag_category) = params.0.category {
+        base = base.filter(category.eq(tag_category));
+    }
+
+    let res = base
+        .order_by((category.asc(), name.asc()))
+        .select(Tag::as_select())
+        .load(&mut state.db_pool.get_conn())?;
+
+    Ok(Json(res))
+}
+
+async fn get_tag(
+    State(state): State<Arc<AppState>>,
+    Path(tag_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Tag>> {
+    use crate::schema::tags::dsl::*;
+
+    let res = tags
+        .filter(id.eq(tag_id))
+        .select(Tag::as_select())
+        .first(&mut state.db_pool.get_conn())?;
+
+    Ok(Json(res))
+}
+
+async fn create_tag(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    JsonExtractor(body): JsonExtractor<NewTag>,
+) -> AppResult<Json<Tag>> {
+    use crate::schema::tags::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_admin(req_user_id, &mut conn)?;
+
+    let (tag_name, tag_slug) = validate_tag_name(&body.name)?;
+
+    let res = insert_into(tags)
+        .values((
+            category.eq(body.category),
+            name.eq(tag_name),
+            slug.eq(tag_slug),
+        ))
+        .returning(Tag::as_returning())
+        .get_result(&mut conn)
+        .map_err(duplicate_tag_error)?;
+
+    Ok(Json(res))
+}
+
+/// Renaming a tag also changes its slug, links to programs and workouts are kept.
+async fn update_tag(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(tag_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<PatchTag>,
+) -> AppResult<Json<Tag>> {
+    use crate::schema::tags::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_admin(req_user_id, &mut conn)?;
+
+    let (tag_name, tag_slug) = validate_tag_name(&body.name)?;
+
+    let res = diesel::update(tags.filter(id.eq(tag_id)))
+        .set((name.eq(tag_name), slug.eq(tag_slug)))
+        .returning(Tag::as_returning())
+        .get_result(&mut conn)
+        .map_err(duplicate_tag_error)?;
+
+    Ok(Json(res))
+}
+
+/// Deleting a tag removes it from every program and workout it was linked to.
+async fn delete_tag(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(tag_id): Path<uuid::Uuid>,
+) -> AppResult<Json<serde_json::Value>> {
+    use crate::schema::tags::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_admin(req_user_id, &mut conn)?;
+
+    let res: usize = diesel::delete(tags.filter(id.eq(tag_id))).execute(&mut conn)?;
+
+    Ok(Json(serde_json::json!({"deleted": res})))
+}
+
+fn validate_tag_name(raw: &str) -> AppResult<(String, String)> {
+    let tag_name = raw.trim().to_string();
+    let tag_slug = name_slug(&tag_name);
+
+    if tag_slug.is_empty() {
+        return Err(bad_request("Tag names need at least one letter or number."));
+    }
+
+    if tag_name.chars().count() > 50 {
+        return Err(bad_request("Tag names can be at most 50 characters long."));
+    }
+
+    Ok((tag_name, tag_slug))
+}
+
+/// Tags are unique per (category, slug), surface that as a 400 instead of a 500.
+fn duplicate_tag_error(err: DieselError) -> BoxedAppError {
+    match err {
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            bad_request("A tag with this name already exists in the category.")
+        }
+        _ => err.into(),
+    }
+}
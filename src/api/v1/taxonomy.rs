@@ -0,0 +1,243 @@
+use crate::{
+    db::{
+        models::taxonomy::{Equipment, Muscle, TaxonomyName},
+        users::guard_admin,
+    },
+    error::{bad_request, BoxedAppError},
+    server::AppState,
+    types::AppResult,
+    util::{
+        extractors::{JsonExtractor, Path, UserIdExtractor},
+        name_slug,
+    },
+};
+use axum::{extract::State, routing::get, Json, Router};
+use diesel::{
+    insert_into,
+    prelude::*,
+    result::{DatabaseErrorKind, Error as DieselError},
+};
+use std::sync::Arc;
+
+/// Muscles are curated by admins, catalog entries link to them as primary or secondary muscles.
+pub fn muscle_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_muscles).post(create_muscle))
+        .route(
+            "/:muscle_id",
+            get(get_muscle).patch(update_muscle).delete(delete_muscle),
+        )
+}
+
+/// Equipment is curated by admins, catalog entries link to the equipment they need.
+pub fn equipment_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_equipment).post(create_equipment))
+        .route(
+            "/:equipment_id",
+            get(get_equipment)
+                .patch(update_equipment)
+                .delete(delete_equipment),
+        )
+}
+
+async fn list_muscles(State(state): State<Arc<AppState>>) -> AppResult<Json<Vec<Muscle>>> {
+    use crate::schema::muscles::dsl::*;
+
+    let res = muscles
+        .order_by(name.asc())
+        .select(Muscle::as_select())
+        .load(&mut state.db_pool.get_conn())?;
+
+    Ok(Json(res))
+}
+
+async fn get_muscle(
+    State(state): State<Arc<AppState>>,
+    Path(muscle_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Muscle>> {
+    use crate::schema::muscles::dsl::*;
+
+    let res = muscles
+        .filter(id.eq(muscle_id))
+        .select(Muscle::as_select())
+        .first(&mut state.db_pool.get_conn())?;
+
+    Ok(Json(res))
+}
+
+async fn create_muscle(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    JsonExtractor(body): JsonExtractor<TaxonomyName>,
+) -> AppResult<Json<Muscle>> {
+    use crate::schema::muscles::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_admin(req_user_id, &mut conn)?;
+
+    let (new_name, new_slug) = validate_name(&body.name)?;
+
+    let res = insert_into(muscles)
+        .values((name.eq(new_name), slug.eq(new_slug)))
+        .returning(Muscle::as_returning())
+        .get_result(&mut conn)
+        .map_err(duplicate_name_error)?;
+
+    Ok(Json(res))
+}
+
+/// Renaming a muscle also changes its slug, links to catalog entries are kept.
+async fn update_muscle(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(muscle_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<TaxonomyName>,
+) -> AppResult<Json<Muscle>> {
+    use crate::schema::muscles::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_admin(req_user_id, &mut conn)?;
+
+    let (new_name, new_slug) = validate_name(&body.name)?;
+
+    let res = diesel::update(muscles.filter(id.eq(muscle_id)))
+        .set((name.eq(new_name), slug.eq(new_slug)))
+        .returning(Muscle::as_returning())
+        .get_result(&mut conn)
+        .map_err(duplicate_name_error)?;
+
+    Ok(Json(res))
+}
+
+/// Deleting a muscle unlinks it from every catalog entry.
+async fn delete_muscle(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(muscle_id): Path<uuid::Uuid>,
+) -> AppResult<Json<serde_json::Value>> {
+    use crate::schema::muscles::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_admin(req_user_id, &mut conn)?;
+
+    let res: usize = diesel::delete(muscles.filter(id.eq(muscle_id))).execute(&mut conn)?;
+
+    Ok(Json(serde_json::json!({"deleted": res})))
+}
+
+async fn list_equipment(State(state): State<Arc<AppState>>) -> AppResult<Json<Vec<Equipment>>> {
+    use crate::schema::equipment::dsl::*;
+
+    let res = equipment
+        .order_by(name.asc())
+        .select(Equipment::as_select())
+        .load(&mut state.db_pool.get_conn())?;
+
+    Ok(Json(res))
+}
+
+async fn get_equipment(
+    State(state): State<Arc<AppState>>,
+    Path(equipment_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Equipment>> {
+    use crate::schema::equipment::dsl::*;
+
+    let res = equipment
+        .filter(id.eq(equipment_id))
+        .select(Equipment::as_select())
+        .first(&mut state.db_pool.get_conn())?;
+
+    Ok(Json(res))
+}
+
+async fn create_equipment(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    JsonExtractor(body): JsonExtractor<TaxonomyName>,
+) -> AppResult<Json<Equipment>> {
+    use crate::schema::equipment::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_admin(req_user_id, &mut conn)?;
+
+    let (new_name, new_slug) = validate_name(&body.name)?;
+
+    let res = insert_into(equipment)
+        .values((name.eq(new_name), slug.eq(new_slug)))
+        .returning(Equipment::as_returning())
+        .get_result(&mut conn)
+        .map_err(duplicate_name_error)?;
+
+    Ok(Json(res))
+}
+
+/// Renaming equipment also changes its slug, links to catalog entries are kept.
+async fn update_equipment(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(equipment_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<TaxonomyName>,
+) -> AppResult<Json<Equipment>> {
+    use crate::schema::equipment::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_admin(req_user_id, &mut conn)?;
+
+    let (new_name, new_slug) = validate_name(&body.name)?;
+
+    let res = diesel::update(equipment.filter(id.eq(equipment_id)))
+        .set((name.eq(new_name), slug.eq(new_slug)))
+        .returning(Equipment::as_returning())
+        .get_result(&mut conn)
+        .map_err(duplicate_name_error)?;
+
+    Ok(Json(res))
+}
+
+/// Deleting equipment unlinks it from every catalog entry.
+async fn delete_equipment(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(equipment_id): Path<uuid::Uuid>,
+) -> AppResult<Json<serde_json::Value>> {
+    use crate::schema::equipment::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_admin(req_user_id, &mut conn)?;
+
+    let res: usize = diesel::delete(equipment.filter(id.eq(equipment_id))).execute(&mut conn)?;
+
+    Ok(Json(serde_json::json!({"deleted": res})))
+}
+
+fn validate_name(raw: &str) -> AppResult<(String, String)> {
+    let new_name = raw.trim().to_string();
+    let new_slug = name_slug(&new_name);
+
+    if new_slug.is_empty() {
+        return Err(bad_request("Names need at least one letter or number."));
+    }
+
+    if new_name.chars().count() > 50 {
+        return Err(bad_request("Names can be at most 50 characters long."));
+    }
+
+    Ok((new_name, new_slug))
+}
+
+/// Slugs are unique per table, surface that as a 400 instead of a 500.
+fn duplicate_name_error(err: DieselError) -> BoxedAppError {
+    match err {
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            bad_request("An entry with this name already exists.")
+        }
+        _ => err.into(),
+    }
+}
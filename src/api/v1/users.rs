@@ -1,12 +1,13 @@
 use crate::{
     db::{
+        catalog::release_catalog_entries,
         models::{
             self,
             user::{NewUser, PublicUser, User},
         },
         users::guard_admin,
     },
-    error::{api_error, custom, json_msg},
+    error::{api_error, custom, json_msg, BoxedAppError},
     server::AppState,
     types::{self, AppResult, DBResult, JsonObject},
     util::extractors::{JsonExtractor, Path, QueryHmExt, UserIdExtractor},
@@ -51,6 +52,14 @@ pub async fn update_me(
 ) -> types::DBResult<models::user::User> {
     use crate::schema::users::dsl::*;
 
+    if body
+        .timezone
+        .as_deref()
+        .is_some_and(|tz| tz.parse::<chrono_tz::Tz>().is_err())
+    {
+        return Err((StatusCode::BAD_REQUEST, json_msg("Unknown timezone.")));
+    }
+
     let res = update(users)
         .filter(id.eq(user_id))
         .set(body)
@@ -152,8 +161,18 @@ pub async fn admin_delete_user_by_username(
     let mut conn = state.db_pool.get_conn();
     let _ = guard_admin(user_id_admin, &mut conn);
 
-    let res: usize =
-        diesel::delete(users.filter(user_name.eq(user_name_path))).execute(&mut conn)?;
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let deleted_ids: Vec<uuid::Uuid> = users
+            .filter(user_name.eq(&user_name_path))
+            .select(id)
+            .load(conn)?;
+
+        for deleted_id in deleted_ids {
+            release_catalog_entries(conn, deleted_id)?;
+        }
+
+        Ok(diesel::delete(users.filter(user_name.eq(user_name_path))).execute(conn)?)
+    })?;
 
     Ok(Json(serde_json::json!({"deleted": res})))
 }
@@ -1,29 +1,69 @@
 use crate::{
+    api::v1::{
+        blocks::{
+            create_workout_block, delete_workout_block, list_workout_blocks, update_workout_block,
+        },
+        comments::{create_workout_comment, list_workout_comments},
+    },
     db::{
+        collaborators::{has_program_access, visible_program_ids},
         models::{
+            collaborator::CollaboratorRoles,
             exercise::Exercise,
-            program::Program,
+            notification::NewNotification,
+            program::{Program, ProgramWithWorkouts},
+            tag::{FacetCount, FacetParams, SetTags, Tag, TagCategories, TagMatch},
+            taxonomy::{summarize_volume, MuscleVolume},
+            user::{PublicUser, PUBLIC_USER_COLUMNS},
             workout::{NewWorkout, PatchWorkout, Workout, WorkoutWithExercises},
             workout_data::{NewWorkoutData, WorkoutData},
+            workout_feedback::{
+                DiscomfortKinds, ExerciseDiscomfort, FeedbackWithDiscomfort, NewWorkoutFeedback,
+                WorkoutFeedback,
+            },
+            workout_session::{SessionWithSets, SetLog, WorkoutSession},
+            IntensityChoices,
         },
+        programs::{load_program_with_workouts, record_program_revision, sync_program_progress},
+        tags::{
+            filter_tagged_workouts, guard_tag_ids, load_workout_tags, replace_workout_tags,
+            workout_facets,
+        },
+        taxonomy::load_volume_rows,
+        workouts::with_exercises,
         DbConnection,
     },
-    error::{custom, not_found, unauthorized},
+    error::{bad_request, not_found, unauthorized, BoxedAppError},
+    pagination::*,
     server::AppState,
     types::AppResult,
     util::{
-        extractors::{JsonExtractor, Path, QueryHmExt, UserIdExtractor},
-        format_slug,
+        calendar::MAX_WORKOUT_WEEK,
+        contains_pattern,
+        extractors::{JsonExtractor, Path, QueryExtractor, UserIdExtractor},
+        find_duplicate, format_slug, parse_slugs,
     },
 };
-use axum::{extract::State, routing::get, Json, Router};
-use diesel::{dsl::exists, insert_into, prelude::*, select, update};
-use http::StatusCode;
+use axum::{
+    extract::State,
+    routing::{get, post, put},
+    Json, Router,
+};
+use diesel::{
+    dsl::{exists, not},
+    insert_into,
+    pg::Pg,
+    prelude::*,
+    select, update,
+};
+use serde::{Deserialize, Serialize};
 use std::{str::FromStr, sync::Arc};
 
 pub fn workout_routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(list_workouts).post(create_workout))
+        .route("/facets", get(get_workout_facets))
+        .route("/order", put(reorder_workouts))
         .route(
             "/:path_workout_id/data",
             get(get_workout_data).post(create_workout_data),
@@ -34,17 +74,52 @@ pub fn workout_routes() -> Router<Arc<AppState>> {
                 .patch(update_workout)
                 .delete(delete_workout),
         )
+        .route(
+            "/:workout_id/blocks",
+            get(list_workout_blocks).post(create_workout_block),
+        )
+        .route(
+            "/:workout_id/blocks/:block_id",
+            put(update_workout_block).delete(delete_workout_block),
+        )
+        .route(
+            "/:workout_id/comments",
+            get(list_workout_comments).post(create_workout_comment),
+        )
+        .route(
+            "/:workout_id/feedback",
+            get(list_workout_feedback).put(submit_workout_feedback),
+        )
+        .route("/:workout_id/restore", post(restore_workout))
+        .route(
+            "/:workout_id/sessions",
+            get(list_workout_sessions).post(start_workout_session),
+        )
+        .route(
+            "/:workout_id/tags",
+            get(get_workout_tags).put(set_workout_tags),
+        )
+        .route("/:workout_id/volume", get(get_workout_volume))
 }
 
+/// Readable by the users who can read its program, see `visible_program_ids`. Workouts outside of
+/// a program are only readable by their owner.
 async fn get_workout(
     State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
     Path(workout_path): Path<String>,
 ) -> AppResult<Json<WorkoutWithExercises>> {
-    use crate::schema::workouts::dsl::*;
+    use crate::schema::{programs::dsl as p, workouts::dsl::*};
 
     let mut conn = state.db_pool.get_conn();
 
-    let mut base = workouts.into_boxed();
+    let mut base = workouts
+        .filter(
+            owner_id
+                .eq(req_user_id)
+                .or(program_id.eq_any(visible_program_ids(req_user_id).nullable())),
+        )
+        .into_boxed();
 
     base = match uuid::Uuid::from_str(&workout_path) {
         Ok(id_filter) => base.filter(id.eq(id_filter)),
@@ -52,92 +127,608 @@ async fn get_workout(
     };
 
     let db_workout = base
+        .filter(archived_at.is_null())
+        // workouts of an archived program are hidden along with it
+        .filter(
+            program_id.is_null().or(not(program_id.eq_any(
+                p::programs
+                    .filter(p::archived_at.is_not_null())
+                    .select(p::id.nullable()),
+            ))),
+        )
         .select(Workout::as_select())
         .first::<Workout>(&mut conn)?;
 
-    let exercises_belonging_to_workouts = Exercise::belonging_to(&db_workout)
-        .select(Exercise::as_select())
-        .load::<Exercise>(&mut conn)?;
-
-    let res = WorkoutWithExercises {
-        workout: db_workout,
-        exercises: exercises_belonging_to_workouts,
-    };
+    let res = with_exercises(&mut conn, vec![db_workout])?.remove(0);
 
     Ok(Json(res))
 }
 
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum WorkoutSort {
+    /// Program order, by week then sequence
+    #[default]
+    Schedule,
+    Newest,
+    Oldest,
+    Name,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct WorkoutListingParams {
+    owner: Option<uuid::Uuid>,
+    program: Option<uuid::Uuid>,
+    template: Option<bool>,
+    week: Option<i32>,
+    intensity: Option<IntensityChoices>,
+    complete: Option<bool>,
+    /// Only workouts created on or after this day (UTC)
+    created_from: Option<chrono::NaiveDate>,
+    /// Only workouts created on or before this day (UTC)
+    created_to: Option<chrono::NaiveDate>,
+    /// Matched against the name of the workout
+    search: Option<String>,
+    #[serde(default)]
+    include_archived: bool,
+    /// Comma separated slugs of `workout_type` tags
+    workout_type: Option<String>,
+    /// Comma separated slugs of `equipment` tags
+    equipment: Option<String>,
+    #[serde(default)]
+    tag_match: TagMatch,
+    #[serde(default)]
+    sort: WorkoutSort,
+}
+
+/// Workouts the user can read matched by the query parameters of `list_workouts`, also used for
+/// its facet counts. See `get_workout` for who can read a workout.
+fn workout_listing_query(
+    req_user_id: uuid::Uuid,
+    params: &WorkoutListingParams,
+) -> AppResult<crate::schema::workouts::BoxedQuery<'static, Pg>> {
+    use crate::schema::{programs::dsl as p, workouts::dsl::*};
+    let mut base_q = workouts
+        .filter(
+            owner_id
+                .eq(req_user_id)
+                .or(program_id.eq_any(visible_program_ids(req_user_id).nullable())),
+        )
+        .into_boxed();
+
+    if let (Some(from), Some(to)) = (params.created_from, params.created_to) {
+        if from > to {
+            return Err(bad_request("created_from must not be after created_to."));
+        }
+    }
+
+    if let Some(only_templates) = params.template {
+        base_q = base_q.filter(template.eq(only_templates));
+    }
+
+    if let Some(owner) = params.owner {
+        base_q = base_q.filter(owner_id.eq(owner));
+    }
+
+    if let Some(program) = params.program {
+        base_q = base_q.filter(program_id.eq(program));
+    }
+
+    if let Some(program_week) = params.week {
+        base_q = base_q.filter(week.eq(program_week));
+    }
+
+    if let Some(workout_intensity) = params.intensity.clone() {
+        base_q = base_q.filter(intensity.eq(workout_intensity));
+    }
+
+    if let Some(is_complete) = params.complete {
+        base_q = base_q.filter(complete.eq(is_complete));
+    }
+
+    if let Some(from) = params.created_from {
+        base_q = base_q.filter(created_at.ge(from.and_time(chrono::NaiveTime::MIN).and_utc()));
+    }
+
+    if let Some(to) = params.created_to.and_then(|to| to.succ_opt()) {
+        base_q = base_q.filter(created_at.lt(to.and_time(chrono::NaiveTime::MIN).and_utc()));
+    }
+
+    if let Some(search) = params.search.as_deref().filter(|s| !s.trim().is_empty()) {
+        base_q = base_q.filter(name.ilike(contains_pattern(search)));
+    }
+
+    if !params.include_archived {
+        base_q = base_q.filter(archived_at.is_null()).filter(
+            program_id.is_null().or(not(program_id.eq_any(
+                p::programs
+                    .filter(p::archived_at.is_not_null())
+                    .select(p::id.nullable()),
+            ))),
+        );
+    }
+
+    if let Some(slugs) = params
+        .workout_type
+        .as_deref()
+        .map(parse_slugs)
+        .filter(|s| !s.is_empty())
+    {
+        base_q =
+            filter_tagged_workouts(base_q, TagCategories::WorkoutType, slugs, params.tag_match);
+    }
+
+    if let Some(slugs) = params
+        .equipment
+        .as_deref()
+        .map(parse_slugs)
+        .filter(|s| !s.is_empty())
+    {
+        base_q = filter_tagged_workouts(base_q, TagCategories::Equipment, slugs, params.tag_match);
+    }
+
+    Ok(base_q)
+}
+
+/// A page of workouts, with `facets=true` also the facet counts of every matched workout.
+#[derive(Serialize)]
+struct WorkoutListing {
+    #[serde(flatten)]
+    page: PaginatedResponse<WorkoutWithExercises>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    facets: Option<Vec<FacetCount>>,
+}
+
 async fn list_workouts(
     State(state): State<Arc<AppState>>,
-    hm: QueryHmExt,
-) -> AppResult<Json<Vec<WorkoutWithExercises>>> {
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    listing_params: QueryExtractor<WorkoutListingParams>,
+    facet_params: QueryExtractor<FacetParams>,
+    pagination: QueryExtractor<PaginationParams>,
+) -> AppResult<Json<WorkoutListing>> {
     use crate::schema::workouts::dsl::*;
-    let mut base_q = workouts.into_boxed();
-
-    let program_query =
-        hm.0.get("program")
-            .map(|val| val.as_str())
-            .map(uuid::Uuid::from_str);
+    let base_q = workout_listing_query(req_user_id, &listing_params.0)?;
+
+    // the id keeps the order stable across pages when the sort columns tie
+    let base_q = match listing_params.0.sort {
+        WorkoutSort::Schedule => base_q.order((week.asc(), sequence.asc(), id.asc())),
+        WorkoutSort::Newest => base_q.order((created_at.desc(), id.asc())),
+        WorkoutSort::Oldest => base_q.order((created_at.asc(), id.asc())),
+        WorkoutSort::Name => base_q.order((name.asc(), id.asc())),
+    };
 
-    let owner =
-        hm.0.get("owner")
-            .map(|val| val.as_str())
-            .map(uuid::Uuid::from_str);
+    let mut conn = state.db_pool.get_conn();
+    let data: Paginated<Workout> = base_q
+        .select(Workout::as_select())
+        .pages_pagination(PaginationOptions::new(pagination.0)?)
+        .load(&mut conn)?;
 
-    let only_templates = hm.0.get("template").map(|val| val.as_str());
+    let total = data.total();
+    let workouts_res: Vec<Workout> = data.into_iter().collect();
 
-    if let Some(only_templates) = only_templates {
-        match only_templates {
-            "true" => {
-                base_q = base_q.filter(template.eq(true));
-            }
+    let workout_with_exercises = with_exercises(&mut conn, workouts_res)?;
 
-            "false" => {
-                base_q = base_q.filter(template.eq(false));
-            }
-            _ => {}
-        }
+    let facets = match facet_params.0.facets {
+        true => Some(workout_facets(
+            &mut conn,
+            workout_listing_query(req_user_id, &listing_params.0)?,
+        )?),
+        false => None,
     };
 
-    base_q = match owner {
-        Some(Ok(val)) => base_q.filter(owner_id.eq(val)),
-        Some(Err(_e)) => {
-            return Err(custom(StatusCode::BAD_REQUEST, "Invalid Owner ID."));
-        }
-        None => base_q,
+    Ok(Json(WorkoutListing {
+        page: PaginatedResponse::new(workout_with_exercises, total),
+        facets,
+    }))
+}
+
+/// Number of workouts carrying each tag, out of the workouts `list_workouts` returns for the
+/// same query parameters.
+async fn get_workout_facets(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    listing_params: QueryExtractor<WorkoutListingParams>,
+) -> AppResult<Json<Vec<FacetCount>>> {
+    let base_q = workout_listing_query(req_user_id, &listing_params.0)?;
+
+    let res = workout_facets(&mut state.db_pool.get_conn(), base_q)?;
+
+    Ok(Json(res))
+}
+
+async fn get_workout_tags(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(workout_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Vec<Tag>>> {
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        workout_id,
+        state.db_pool.get_conn(),
+        CollaboratorRoles::Viewer,
+    )
+    .await?;
+
+    let res = load_workout_tags(&mut state.db_pool.get_conn(), workout_id)?;
+
+    Ok(Json(res))
+}
+
+/// Sets per muscle group, see `MuscleVolume`.
+async fn get_workout_volume(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(workout_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Vec<MuscleVolume>>> {
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        workout_id,
+        state.db_pool.get_conn(),
+        CollaboratorRoles::Viewer,
+    )
+    .await?;
+
+    let rows = load_volume_rows(&mut state.db_pool.get_conn(), &[workout_id])?;
+
+    let res = summarize_volume(
+        &rows
+            .into_iter()
+            .map(|(_, muscle, role, sets)| (muscle, role, sets))
+            .collect::<Vec<_>>(),
+    );
+
+    Ok(Json(res))
+}
+
+/// Replaces the `workout_type` and `equipment` tags of the workout.
+async fn set_workout_tags(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(workout_id): Path<uuid::Uuid>,
+    JsonExtractor(body): JsonExtractor<SetTags>,
+) -> AppResult<Json<Vec<Tag>>> {
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        workout_id,
+        state.db_pool.get_conn(),
+        CollaboratorRoles::Editor,
+    )
+    .await?;
+
+    let mut conn = state.db_pool.get_conn();
+
+    guard_tag_ids(&mut conn, &body.tag_ids, false)?;
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        replace_workout_tags(conn, workout_id, &body.tag_ids)?;
+
+        Ok(load_workout_tags(conn, workout_id)?)
+    })?;
+
+    Ok(Json(res))
+}
+
+/// Starts logging the workout. If the user already has a session of it in progress that one is
+/// returned instead, so a client reopening the workout picks up where they left off.
+async fn start_workout_session(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_workout_id): Path<uuid::Uuid>,
+) -> AppResult<Json<SessionWithSets>> {
+    use crate::schema::{workout_sessions::dsl::*, workouts::dsl as w_dsl};
+
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        path_workout_id,
+        state.db_pool.get_conn(),
+        CollaboratorRoles::Editor,
+    )
+    .await?;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let workout_is_archived: bool = w_dsl::workouts
+        .filter(w_dsl::id.eq(path_workout_id))
+        .select(w_dsl::archived_at.is_not_null())
+        .first(&mut conn)?;
+
+    if workout_is_archived {
+        return Err(bad_request("Archived workouts can't be started."));
+    }
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let open_session = workout_sessions
+            .filter(
+                workout_id
+                    .eq(path_workout_id)
+                    .and(user_id.eq(req_user_id))
+                    .and(finished_at.is_null()),
+            )
+            .select(WorkoutSession::as_select());
+
+        let existing: Option<WorkoutSession> = open_session.first(conn).optional()?;
+
+        // a concurrent start can insert the open session first, it's returned instead
+        let session = match existing {
+            Some(session) => session,
+            None => match insert_into(workout_sessions)
+                .values((workout_id.eq(path_workout_id), user_id.eq(req_user_id)))
+                .on_conflict_do_nothing()
+                .returning(WorkoutSession::as_returning())
+                .get_result(conn)
+                .optional()?
+            {
+                Some(session) => session,
+                None => open_session.first(conn)?,
+            },
+        };
+
+        let sets = SetLog::belonging_to(&session)
+            .order(crate::schema::set_logs::set_number.asc())
+            .select(SetLog::as_select())
+            .load(conn)?;
+
+        Ok(SessionWithSets { session, sets })
+    })?;
+
+    Ok(Json(res))
+}
+
+/// Every session logged for the workout, newest first.
+async fn list_workout_sessions(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_workout_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Vec<SessionWithSets>>> {
+    use crate::schema::{set_logs::dsl as l_dsl, workout_sessions::dsl::*};
+
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        path_workout_id,
+        state.db_pool.get_conn(),
+        CollaboratorRoles::Viewer,
+    )
+    .await?;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let sessions: Vec<WorkoutSession> = workout_sessions
+        .filter(workout_id.eq(path_workout_id))
+        .order(started_at.desc())
+        .select(WorkoutSession::as_select())
+        .load(&mut conn)?;
+
+    let sets: Vec<SetLog> = SetLog::belonging_to(&sessions)
+        .order((l_dsl::exercise_id.asc(), l_dsl::set_number.asc()))
+        .select(SetLog::as_select())
+        .load(&mut conn)?;
+
+    let res = sets
+        .grouped_by(&sessions)
+        .into_iter()
+        .zip(sessions)
+        .map(|(sets, session)| SessionWithSets { session, sets })
+        .collect();
+
+    Ok(Json(res))
+}
+
+/// Records how the workout went for the requesting user. Submitting again replaces the earlier
+/// feedback and its discomfort flags. The trainer is notified either way.
+async fn submit_workout_feedback(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_workout_id): Path<uuid::Uuid>,
+    JsonExtractor(new_feedback): JsonExtractor<NewWorkoutFeedback>,
+) -> AppResult<Json<FeedbackWithDiscomfort>> {
+    use crate::schema::{exercise_discomfort::dsl as d_dsl, workout_feedback::dsl::*};
+
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        path_workout_id,
+        state.db_pool.get_conn(),
+        CollaboratorRoles::Editor,
+    )
+    .await?;
+
+    new_feedback.validate().map_err(bad_request)?;
+
+    let flagged_ids: Vec<uuid::Uuid> = new_feedback
+        .discomfort
+        .iter()
+        .map(|d| d.exercise_id)
+        .collect();
+
+    if let Some(dup) = find_duplicate(&flagged_ids) {
+        return Err(bad_request(format!(
+            "Exercise {dup} is flagged more than once."
+        )));
+    }
+
+    let mut conn = state.db_pool.get_conn();
+
+    let workout: Workout = crate::schema::workouts::table
+        .find(path_workout_id)
+        .filter(crate::schema::workouts::archived_at.is_null())
+        .select(Workout::as_select())
+        .first(&mut conn)?;
+
+    if !workout.complete {
+        return Err(bad_request(
+            "Feedback can only be given on completed workouts.",
+        ));
+    }
+
+    let workout_exercise_ids: Vec<uuid::Uuid> = Exercise::belonging_to(&workout)
+        .filter(crate::schema::exercises::archived_at.is_null())
+        .select(crate::schema::exercises::id)
+        .load(&mut conn)?;
+
+    if let Some(unknown) = new_feedback
+        .discomfort
+        .iter()
+        .find(|d| !workout_exercise_ids.contains(&d.exercise_id))
+    {
+        return Err(bad_request(format!(
+            "Exercise {} isn't part of this workout.",
+            unknown.exercise_id
+        )));
+    }
+
+    let (feedback, discomfort) = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let feedback = insert_into(workout_feedback)
+            .values((
+                workout_id.eq(path_workout_id),
+                user_id.eq(req_user_id),
+                session_rpe.eq(new_feedback.session_rpe),
+                enjoyment.eq(new_feedback.enjoyment),
+                notes.eq(&new_feedback.notes),
+            ))
+            .on_conflict((workout_id, user_id))
+            .do_update()
+            .set((
+                updated_at.eq(chrono::Utc::now()),
+                session_rpe.eq(new_feedback.session_rpe),
+                enjoyment.eq(new_feedback.enjoyment),
+                notes.eq(&new_feedback.notes),
+            ))
+            .returning(WorkoutFeedback::as_returning())
+            .get_result(conn)?;
+
+        diesel::delete(ExerciseDiscomfort::belonging_to(&feedback)).execute(conn)?;
+
+        let rows: Vec<_> = new_feedback
+            .discomfort
+            .iter()
+            .map(|d| {
+                (
+                    d_dsl::feedback_id.eq(feedback.id),
+                    d_dsl::exercise_id.eq(d.exercise_id),
+                    d_dsl::kind.eq(d.kind),
+                    d_dsl::note.eq(&d.note),
+                )
+            })
+            .collect();
+
+        let discomfort = insert_into(d_dsl::exercise_discomfort)
+            .values(rows)
+            .returning(ExerciseDiscomfort::as_returning())
+            .get_results(conn)?;
+
+        Ok((feedback, discomfort))
+    })?;
+
+    let program_owner: Option<uuid::Uuid> = match workout.program_id {
+        Some(p_id) => crate::schema::programs::table
+            .find(p_id)
+            .select(crate::schema::programs::owner_id)
+            .first(&mut conn)?,
+        None => None,
     };
 
-    base_q = match program_query {
-        Some(Ok(val)) => base_q.filter(program_id.eq(val)),
-        Some(Err(_e)) => {
-            return Err(custom(StatusCode::BAD_REQUEST, "Invalid Program ID."));
+    if let Some(trainer_id) = program_owner.or(workout.owner_id) {
+        if trainer_id != req_user_id {
+            feedback_notification(&workout, &feedback, &discomfort, trainer_id).send(&mut conn)?;
         }
-        None => base_q,
-    };
+    }
+
+    let user = crate::schema::users::table
+        .find(req_user_id)
+        .select(PUBLIC_USER_COLUMNS)
+        .first::<PublicUser>(&mut conn)?;
+
+    Ok(Json(FeedbackWithDiscomfort {
+        feedback,
+        discomfort,
+        user,
+    }))
+}
+
+fn feedback_notification(
+    workout: &Workout,
+    feedback: &WorkoutFeedback,
+    discomfort: &[ExerciseDiscomfort],
+    trainer_id: uuid::Uuid,
+) -> NewNotification {
+    let pain_flags = discomfort
+        .iter()
+        .filter(|d| d.kind == DiscomfortKinds::Pain)
+        .count();
+
+    let mut content = format!(
+        "Session RPE {}/10, enjoyment {}/5",
+        feedback.session_rpe, feedback.enjoyment
+    );
+    if !discomfort.is_empty() {
+        content += &format!(
+            ", {} exercise(s) flagged ({pain_flags} with pain)",
+            discomfort.len()
+        );
+    }
+
+    let d = serde_json::json!({
+        "workout_id": workout.id,
+        "feedback_id": feedback.id,
+    });
+
+    NewNotification::new(
+        feedback.user_id,
+        trainer_id,
+        format!("Feedback on {}", workout.name)
+            .chars()
+            .take(255)
+            .collect(),
+        content,
+        "workout".into(),
+        "unread".into(),
+        Some(d),
+    )
+}
+
+/// Everyone's feedback on the workout, most recent first.
+async fn list_workout_feedback(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    Path(path_workout_id): Path<uuid::Uuid>,
+) -> AppResult<Json<Vec<FeedbackWithDiscomfort>>> {
+    use crate::schema::{users::dsl as u_dsl, workout_feedback::dsl::*};
+
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        path_workout_id,
+        state.db_pool.get_conn(),
+        CollaboratorRoles::Viewer,
+    )
+    .await?;
 
     let mut conn = state.db_pool.get_conn();
-    let workouts_res = base_q
-        .order(sequence.asc())
-        .select(Workout::as_select())
-        .load::<Workout>(&mut conn)?;
 
-    use crate::schema::exercises::dsl as exer_dsl;
-    let exercises_res: Vec<Exercise> = Exercise::belonging_to(&workouts_res)
-        .order(exer_dsl::sequence.asc())
-        .select(Exercise::as_select())
+    let (feedback, users): (Vec<WorkoutFeedback>, Vec<PublicUser>) = workout_feedback
+        .inner_join(u_dsl::users)
+        .filter(workout_id.eq(path_workout_id))
+        .order(updated_at.desc())
+        .select((WorkoutFeedback::as_select(), PUBLIC_USER_COLUMNS))
+        .load::<(WorkoutFeedback, PublicUser)>(&mut conn)?
+        .into_iter()
+        .unzip();
+
+    let discomfort = ExerciseDiscomfort::belonging_to(&feedback)
+        .select(ExerciseDiscomfort::as_select())
         .load(&mut conn)?;
 
-    let workout_with_exercises = exercises_res
-        .grouped_by(&workouts_res)
+    let res = discomfort
+        .grouped_by(&feedback)
         .into_iter()
-        .zip(workouts_res)
-        .map(|(exer, wk)| WorkoutWithExercises {
-            workout: wk,
-            exercises: exer,
+        .zip(feedback)
+        .zip(users)
+        .map(|((discomfort, feedback), user)| FeedbackWithDiscomfort {
+            feedback,
+            discomfort,
+            user,
         })
-        .collect::<Vec<WorkoutWithExercises>>();
+        .collect();
 
-    Ok(Json(workout_with_exercises))
+    Ok(Json(res))
 }
 
 async fn create_workout(
@@ -147,6 +738,8 @@ async fn create_workout(
 ) -> AppResult<Json<WorkoutWithExercises>> {
     use crate::schema::workouts::dsl::*;
 
+    guard_week(body.week)?;
+
     let mut conn = state.db_pool.get_conn();
 
     let mut new_slug = format_slug(body.name.clone());
@@ -161,84 +754,97 @@ async fn create_workout(
         new_slug = format!("{}-{}", new_slug, &rand::random::<u32>().to_string());
     }
 
-    let res = insert_into(workouts)
-        .values((&body, owner_id.eq(user_id_extracted), slug.eq(new_slug)))
-        .returning(Workout::as_returning())
-        .get_result::<Workout>(&mut conn)?;
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let res = insert_into(workouts)
+            .values((&body, owner_id.eq(user_id_extracted), slug.eq(new_slug)))
+            .returning(Workout::as_returning())
+            .get_result::<Workout>(conn)?;
+
+        if let Some(prog_id) = res.program_id {
+            sync_program_progress(conn, prog_id)?;
+            record_program_revision(conn, prog_id, user_id_extracted)?;
+        }
 
-    let exercises_belonging_to_workouts = Exercise::belonging_to(&res)
-        .select(Exercise::as_select())
-        .load::<Exercise>(&mut conn)?;
+        Ok(res)
+    })?;
 
-    let wrk_exer = WorkoutWithExercises {
-        workout: res,
-        exercises: exercises_belonging_to_workouts,
-    };
+    let wrk_exer = with_exercises(&mut conn, vec![res])?.remove(0);
 
     Ok(Json(wrk_exer))
 }
 
+/// Weeks past the cap can't be placed on the calendar, see `plan_workout_dates`.
+fn guard_week(workout_week: i32) -> AppResult<()> {
+    if !(1..=MAX_WORKOUT_WEEK).contains(&workout_week) {
+        return Err(bad_request(format!(
+            "week must be between 1 and {MAX_WORKOUT_WEEK}."
+        )));
+    }
+
+    Ok(())
+}
+
 async fn update_workout(
     State(state): State<Arc<AppState>>,
     UserIdExtractor(req_user_id): UserIdExtractor,
     Path(workout_id): Path<uuid::Uuid>,
     JsonExtractor(body): JsonExtractor<PatchWorkout>,
 ) -> AppResult<Json<WorkoutWithExercises>> {
-    use crate::schema::{programs::dsl as pro_dsl, workouts::dsl::*};
+    use crate::schema::workouts::dsl::*;
+
+    if let Some(body_week) = body.week {
+        guard_week(body_week)?;
+    }
 
     let conn = state.db_pool.get_conn();
 
-    // only the owner of the workout or a client that is assigned a program in which this
-    // workout is connected to can update this workout.
+    // only the owner of the workout, a client that is assigned a program in which this
+    // workout is connected to or an editor of that program can update this workout.
 
-    guard_workout_owner_or_program_client(req_user_id, workout_id, conn).await?;
+    guard_workout_owner_or_program_client(req_user_id, workout_id, conn, CollaboratorRoles::Editor)
+        .await?;
 
     let mut conn = state.db_pool.get_conn();
     let workout_filter = id.eq(workout_id);
+    let completion_change = body.complete;
+
+    let wrk_exer = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let mut res = update(workouts)
+            .filter(workout_filter)
+            .set(body)
+            .returning(Workout::as_returning())
+            .get_result::<Workout>(conn)?;
+
+        // keep the time of the first completion, un-completing the workout clears it
+        let completed_time = match completion_change {
+            Some(true) => res.completed_at.or_else(|| Some(chrono::Utc::now())),
+            Some(false) => None,
+            None => res.completed_at,
+        };
+
+        if completed_time != res.completed_at {
+            res = update(workouts)
+                .filter(workout_filter)
+                .set(completed_at.eq(completed_time))
+                .returning(Workout::as_returning())
+                .get_result::<Workout>(conn)?;
+        }
 
-    let res = update(workouts)
-        .filter(workout_filter)
-        .set(body)
-        .returning(Workout::as_returning())
-        .get_result::<Workout>(&mut conn)?;
-
-    let exercises_belonging_to_workouts = Exercise::belonging_to(&res)
-        .select(Exercise::as_select())
-        .load::<Exercise>(&mut conn)?;
-
-    let wrk_exer = WorkoutWithExercises {
-        workout: res,
-        exercises: exercises_belonging_to_workouts,
-    };
+        let wrk_exer = with_exercises(conn, vec![res])?.remove(0);
 
-    let wkt_program_id: Option<uuid::Uuid> = workouts
-        .filter(id.eq(workout_id))
-        .select(program_id.nullable())
-        .first(&mut conn)?;
+        if let Some(prog_id) = wrk_exer.workout.program_id {
+            sync_program_progress(conn, prog_id)?;
+            record_program_revision(conn, prog_id, req_user_id)?;
+        };
 
-    // if all of the programs workouts are not complete, set program as complete!
-    if let Some(prog_id) = wkt_program_id {
-        let prog_workouts: Result<Vec<bool>, diesel::result::Error> = workouts
-            .filter(program_id.eq(prog_id))
-            .select(complete)
-            .load::<bool>(&mut conn);
-
-        if let Ok(wkts) = prog_workouts {
-            let completed = wkts.clone().into_iter().filter(|w| *w).count();
-
-            if completed == wkts.len() {
-                // all of the workouts have been completed! update the program
-                let _res = update(pro_dsl::programs)
-                    .filter(pro_dsl::id.eq(prog_id))
-                    .set(pro_dsl::complete.eq(true))
-                    .execute(&mut conn);
-            }
-        }
-    };
+        Ok(wrk_exer)
+    })?;
 
     Ok(Json(wrk_exer))
 }
 
+/// Archives the workout, its exercises and logged data are kept until the retention job purges
+/// it.
 async fn delete_workout(
     State(state): State<Arc<AppState>>,
     Path(workout_id_to_delete): Path<uuid::Uuid>,
@@ -246,9 +852,153 @@ async fn delete_workout(
 ) -> AppResult<Json<usize>> {
     use crate::schema::workouts::dsl::*;
 
-    let res = diesel::delete(workouts)
-        .filter(id.eq(workout_id_to_delete).and(owner_id.eq(user_id_ext)))
-        .execute(&mut state.db_pool.get_conn())?;
+    let mut conn = state.db_pool.get_conn();
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let archived_from_programs: Vec<Option<uuid::Uuid>> = update(workouts)
+            .filter(
+                id.eq(workout_id_to_delete)
+                    .and(owner_id.eq(user_id_ext))
+                    .and(archived_at.is_null()),
+            )
+            .set(archived_at.eq(chrono::Utc::now()))
+            .returning(program_id)
+            .get_results(conn)?;
+
+        for prog_id in archived_from_programs.iter().flatten() {
+            sync_program_progress(conn, *prog_id)?;
+            record_program_revision(conn, *prog_id, user_id_ext)?;
+        }
+
+        Ok(archived_from_programs.len())
+    })?;
+
+    Ok(Json(res))
+}
+
+async fn restore_workout(
+    State(state): State<Arc<AppState>>,
+    Path(workout_id_to_restore): Path<uuid::Uuid>,
+    UserIdExtractor(user_id_ext): UserIdExtractor,
+) -> AppResult<Json<Workout>> {
+    use crate::schema::workouts::dsl::*;
+
+    let mut conn = state.db_pool.get_conn();
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        let res = update(workouts)
+            .filter(
+                id.eq(workout_id_to_restore)
+                    .and(owner_id.eq(user_id_ext))
+                    .and(archived_at.is_not_null()),
+            )
+            .set(archived_at.eq(None::<chrono::DateTime<chrono::Utc>>))
+            .returning(Workout::as_returning())
+            .get_result::<Workout>(conn)?;
+
+        if let Some(prog_id) = res.program_id {
+            sync_program_progress(conn, prog_id)?;
+            record_program_revision(conn, prog_id, user_id_ext)?;
+        }
+
+        Ok(res)
+    })?;
+
+    Ok(Json(res))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WeekOrder {
+    pub week: i32,
+    pub workouts: Vec<uuid::Uuid>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ReorderWorkouts {
+    pub program_id: uuid::Uuid,
+    pub weeks: Vec<WeekOrder>,
+}
+
+/// Replaces the order of all of a program's workouts at once. The body must list every workout of
+/// the program exactly once, grouped by the week it should end up in. Sequences are renumbered
+/// from 0 within each week so there are no gaps or duplicates left behind.
+async fn reorder_workouts(
+    State(state): State<Arc<AppState>>,
+    UserIdExtractor(req_user_id): UserIdExtractor,
+    JsonExtractor(body): JsonExtractor<ReorderWorkouts>,
+) -> AppResult<Json<ProgramWithWorkouts>> {
+    use crate::schema::{programs::dsl as pro_dsl, workouts::dsl::*};
+
+    let mut conn = state.db_pool.get_conn();
+
+    let program: Program = pro_dsl::programs
+        .filter(pro_dsl::id.eq(body.program_id))
+        .select(Program::as_select())
+        .first(&mut conn)?;
+
+    if program.owner_id != Some(req_user_id) {
+        return Err(unauthorized());
+    }
+
+    for week_order in &body.weeks {
+        guard_week(week_order.week)?;
+    }
+
+    let week_numbers: Vec<i32> = body.weeks.iter().map(|w| w.week).collect();
+    if (1..week_numbers.len()).any(|i| week_numbers[..i].contains(&week_numbers[i])) {
+        return Err(bad_request("Each week can only be listed once."));
+    }
+
+    let ordered_ids: Vec<uuid::Uuid> = body
+        .weeks
+        .iter()
+        .flat_map(|w| w.workouts.iter().copied())
+        .collect();
+
+    if let Some(dup) = find_duplicate(&ordered_ids) {
+        return Err(bad_request(format!(
+            "Workout {dup} is listed more than once."
+        )));
+    }
+
+    let program_workout_ids: Vec<uuid::Uuid> = workouts
+        .filter(program_id.eq(program.id).and(archived_at.is_null()))
+        .select(id)
+        .load(&mut conn)?;
+
+    if let Some(unknown) = ordered_ids
+        .iter()
+        .find(|w_id| !program_workout_ids.contains(w_id))
+    {
+        return Err(bad_request(format!(
+            "Workout {unknown} is not part of this program."
+        )));
+    }
+
+    if let Some(missing) = program_workout_ids
+        .iter()
+        .find(|w_id| !ordered_ids.contains(w_id))
+    {
+        return Err(bad_request(format!(
+            "Workout {missing} is missing, every workout of the program must be listed."
+        )));
+    }
+
+    let res = conn.transaction::<_, BoxedAppError, _>(|conn| {
+        for week_order in &body.weeks {
+            for (idx, w_id) in week_order.workouts.iter().enumerate() {
+                update(workouts)
+                    .filter(id.eq(w_id))
+                    .set((week.eq(week_order.week), sequence.eq(idx as i32)))
+                    .execute(conn)?;
+            }
+        }
+
+        sync_program_progress(conn, program.id)?;
+        record_program_revision(conn, program.id, req_user_id)?;
+
+        Ok(load_program_with_workouts(program, conn)?)
+    })?;
 
     Ok(Json(res))
 }
@@ -262,7 +1012,13 @@ async fn get_workout_data(
 
     let conn = state.db_pool.get_conn();
 
-    guard_workout_owner_or_program_client(req_user_id, path_workout_id, conn).await?;
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        path_workout_id,
+        conn,
+        CollaboratorRoles::Viewer,
+    )
+    .await?;
 
     let mut conn = state.db_pool.get_conn();
 
@@ -278,6 +1034,7 @@ async fn get_workout_data(
     Ok(Json(res))
 }
 
+/// Measurements are stored in the units of their columns, see `NewWorkoutData::normalize`.
 async fn create_workout_data(
     State(state): State<Arc<AppState>>,
     UserIdExtractor(req_user_id): UserIdExtractor,
@@ -288,7 +1045,15 @@ async fn create_workout_data(
 
     let conn = state.db_pool.get_conn();
 
-    guard_workout_owner_or_program_client(req_user_id, path_workout_id, conn).await?;
+    guard_workout_owner_or_program_client(
+        req_user_id,
+        path_workout_id,
+        conn,
+        CollaboratorRoles::Editor,
+    )
+    .await?;
+
+    let new_data = body.normalize().map_err(bad_request)?;
 
     let mut conn = state.db_pool.get_conn();
 
@@ -302,17 +1067,18 @@ async fn create_workout_data(
     }
 
     let res = insert_into(workout_data)
-        .values((&body, workout_id.eq(path_workout_id)))
+        .values((&new_data, workout_id.eq(path_workout_id)))
         .returning(WorkoutData::as_returning())
         .get_result(&mut conn)?;
 
     Ok(Json(res))
 }
 
-async fn guard_workout_owner_or_program_client(
+pub async fn guard_workout_owner_or_program_client(
     req_user_id: uuid::Uuid,
     workout_id: uuid::Uuid,
     mut conn: DbConnection,
+    min_role: CollaboratorRoles,
 ) -> AppResult<()> {
     use crate::schema::{clients::dsl as cli_dsl, programs::dsl as pro_dsl, workouts::dsl::*};
 
@@ -334,10 +1100,18 @@ async fn guard_workout_owner_or_program_client(
     let req_user_is_workout_owner = workout_from_path.owner_id == Some(req_user_id);
     let req_user_is_client_from_workout_program = req_user_client.is_ok_and(|req_client_id| {
         program_for_workout
+            .as_ref()
             .is_ok_and(|prog| prog.client_id.is_some_and(|cli_id| cli_id == req_client_id))
     });
 
-    let res = req_user_is_client_from_workout_program || req_user_is_workout_owner;
+    let req_user_is_program_collaborator = match &program_for_workout {
+        Ok(prog) => has_program_access(&mut conn, req_user_id, prog, min_role)?,
+        Err(_) => false,
+    };
+
+    let res = req_user_is_client_from_workout_program
+        || req_user_is_workout_owner
+        || req_user_is_program_collaborator;
 
     if !res {
         return Err(unauthorized());
@@ -352,6 +352,7 @@ impl From<serde_json::Value> for NewUser {
             gender,
             bio,
             beta_access,
+            timezone: None,
         }
     }
 }
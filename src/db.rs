@@ -1,5 +1,13 @@
+pub mod catalog;
+pub mod collaborators;
 pub mod models;
+pub mod programs;
+pub mod records;
+pub mod reminders;
+pub mod tags;
+pub mod taxonomy;
 pub mod users;
+pub mod workouts;
 use anyhow::{Context, Ok, Result};
 use diesel::{
     r2d2::{ConnectionManager, Pool, PooledConnection},
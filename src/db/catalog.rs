@@ -0,0 +1,184 @@
+use super::{
+    collaborators::visible_workout_ids,
+    models::{
+        exercise::{Exercise, ExerciseWithCatalog},
+        exercise_catalog::{catalog_slug, CatalogExercise},
+        program_document::ExerciseDocument,
+    },
+    taxonomy::link_catalog_from_text,
+    DbConnection,
+};
+use crate::schema::exercise_catalog;
+use diesel::{insert_into, pg::Pg, prelude::*, sql_types};
+
+/// Loads the catalog entry of a single exercise.
+pub fn with_catalog(
+    conn: &mut DbConnection,
+    exercise: Exercise,
+) -> QueryResult<ExerciseWithCatalog> {
+    let catalog = exercise_catalog::table
+        .find(exercise.catalog_id)
+        .select(CatalogExercise::as_select())
+        .first(conn)?;
+
+    Ok(ExerciseWithCatalog { exercise, catalog })
+}
+
+/// Entries the user can read: the library, their own entries and the ones used by the exercises
+/// they can read. Meant as a subquery.
+pub fn visible_catalog_ids<'a>(
+    user_id: uuid::Uuid,
+) -> exercise_catalog::BoxedQuery<'a, Pg, sql_types::Uuid> {
+    use crate::schema::{exercise_catalog::dsl::*, exercises::dsl as e_dsl};
+
+    let used_entries = e_dsl::exercises
+        .filter(
+            e_dsl::owner_id
+                .eq(user_id)
+                .or(e_dsl::workout_id.eq_any(visible_workout_ids(user_id))),
+        )
+        .select(e_dsl::catalog_id);
+
+    exercise_catalog
+        .filter(
+            owner_id
+                .is_null()
+                .or(owner_id.eq(user_id))
+                .or(id.eq_any(used_entries)),
+        )
+        .select(id)
+        .into_boxed()
+}
+
+/// Entry of the library or of `entry_owner_id` matching the slug or the name, the trainer's own
+/// entry wins over the library one. Other trainers' entries are never matched.
+pub fn find_catalog_entry(
+    conn: &mut DbConnection,
+    entry_owner_id: Option<uuid::Uuid>,
+    entry_slug: &str,
+    entry_name: &str,
+) -> QueryResult<Option<uuid::Uuid>> {
+    use crate::schema::exercise_catalog::dsl::*;
+
+    exercise_catalog
+        .filter(owner_id.is_null().or(owner_id.eq(entry_owner_id)))
+        .filter(slug.eq(entry_slug).or(name.eq(entry_name)))
+        .order((slug.eq(entry_slug).desc(), owner_id.is_not_null().desc()))
+        .select(id)
+        .first(conn)
+        .optional()
+}
+
+/// Catalog entry an imported or generated exercise refers to. Documents carry the id of the entry
+/// when they have one, otherwise their exercises are matched on the slug or the name of the entry,
+/// see `find_catalog_entry`. Ids of other trainers' entries are matched the same way.
+///
+/// Exercises the catalog doesn't know yet are added to it, owned by `new_owner_id`, and linked to
+/// the muscles and equipment their document names.
+pub fn resolve_catalog_entry(
+    conn: &mut DbConnection,
+    new_owner_id: uuid::Uuid,
+    exercise: &ExerciseDocument,
+) -> QueryResult<uuid::Uuid> {
+    use crate::schema::exercise_catalog::dsl::*;
+
+    if let Some(known_id) = exercise.catalog_id {
+        let usable: Option<uuid::Uuid> = exercise_catalog
+            .filter(id.eq(known_id))
+            .filter(owner_id.is_null().or(owner_id.eq(new_owner_id)))
+            .select(id)
+            .first(conn)
+            .optional()?;
+
+        if let Some(usable) = usable {
+            return Ok(usable);
+        }
+    }
+
+    let entry_name = exercise.name.trim();
+    let entry_slug = catalog_slug(entry_name);
+
+    if let Some(existing) = find_catalog_entry(conn, Some(new_owner_id), &entry_slug, entry_name)? {
+        return Ok(existing);
+    }
+
+    let new_id = insert_into(exercise_catalog)
+        .values((
+            owner_id.eq(new_owner_id),
+            name.eq(entry_name),
+            slug.eq(entry_slug),
+            description.eq(&exercise.description),
+            equipment.eq(&exercise.equipment),
+            muscle_groups.eq(&exercise.muscle_groups),
+            image.eq(&exercise.image),
+            video.eq(&exercise.video),
+            instructions.eq(&exercise.instructions),
+        ))
+        .returning(id)
+        .get_result(conn)?;
+
+    link_catalog_from_text(conn, new_id, &exercise.muscle_groups, &exercise.equipment)?;
+
+    Ok(new_id)
+}
+
+/// Entry a restored exercise refers to. Entries are deleted once nothing references them anymore,
+/// a deleted one is matched like an import for the owner of the program, or recreated with its
+/// original id when the catalog doesn't have it anymore.
+pub fn restore_catalog_entry(
+    conn: &mut DbConnection,
+    program_owner_id: Option<uuid::Uuid>,
+    entry: CatalogExercise,
+) -> QueryResult<uuid::Uuid> {
+    use crate::schema::exercise_catalog::dsl::*;
+
+    let exists: i64 = exercise_catalog
+        .filter(id.eq(entry.id))
+        .count()
+        .get_result(conn)?;
+
+    if exists > 0 {
+        return Ok(entry.id);
+    }
+
+    if let Some(existing) = find_catalog_entry(conn, program_owner_id, &entry.slug, &entry.name)? {
+        return Ok(existing);
+    }
+
+    let entry = CatalogExercise {
+        owner_id: entry.owner_id.and(program_owner_id),
+        ..entry
+    };
+
+    insert_into(exercise_catalog).values(&entry).execute(conn)?;
+
+    link_catalog_from_text(conn, entry.id, &entry.muscle_groups, &entry.equipment)?;
+
+    Ok(entry.id)
+}
+
+/// Entries of a deleted user join the library (`owner_id` is set to null), the ones whose slug the
+/// library already has get a suffix so they don't collide with it. Run before deleting the user.
+pub fn release_catalog_entries(conn: &mut DbConnection, user_id: uuid::Uuid) -> QueryResult<()> {
+    use crate::schema::exercise_catalog::dsl::*;
+
+    let library_slugs = exercise_catalog
+        .filter(owner_id.is_null())
+        .select(slug)
+        .into_boxed();
+
+    let colliding: Vec<(uuid::Uuid, String)> = exercise_catalog
+        .filter(owner_id.eq(user_id).and(slug.eq_any(library_slugs)))
+        .select((id, slug))
+        .load(conn)?;
+
+    for (entry_id, entry_slug) in colliding {
+        let suffix = entry_id.simple().to_string();
+
+        diesel::update(exercise_catalog.find(entry_id))
+            .set(slug.eq(format!("{entry_slug}-{}", &suffix[..8])))
+            .execute(conn)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use super::{
+    models::{
+        collaborator::{CollaboratorRoles, SharedProgram},
+        program::Program,
+    },
+    DbConnection,
+};
+use crate::schema::{programs, workouts};
+use diesel::{pg::Pg, prelude::*, sql_types};
+
+/// Strongest role the user was granted on the program, either on the program itself or on the
+/// client it's assigned to. Owners aren't collaborators, so this is `None` for them.
+pub fn program_role(
+    conn: &mut DbConnection,
+    user_id: uuid::Uuid,
+    program: &Program,
+) -> QueryResult<Option<CollaboratorRoles>> {
+    use crate::schema::program_collaborators::dsl as pc_dsl;
+
+    let program_grant: Option<CollaboratorRoles> = pc_dsl::program_collaborators
+        .filter(
+            pc_dsl::program_id
+                .eq(program.id)
+                .and(pc_dsl::user_id.eq(user_id)),
+        )
+        .select(pc_dsl::role)
+        .first(conn)
+        .optional()?;
+
+    let client_grant = match program.client_id {
+        Some(program_client_id) => client_role(conn, user_id, program_client_id)?,
+        None => None,
+    };
+
+    Ok(program_grant.max(client_grant))
+}
+
+/// Role the user was granted on a client relationship, `None` for the client's own trainer.
+pub fn client_role(
+    conn: &mut DbConnection,
+    user_id: uuid::Uuid,
+    client_id: uuid::Uuid,
+) -> QueryResult<Option<CollaboratorRoles>> {
+    use crate::schema::client_collaborators::dsl as cc_dsl;
+
+    cc_dsl::client_collaborators
+        .filter(
+            cc_dsl::client_id
+                .eq(client_id)
+                .and(cc_dsl::user_id.eq(user_id)),
+        )
+        .select(cc_dsl::role)
+        .first(conn)
+        .optional()
+}
+
+/// Whether the user owns the program or was granted at least `min_role` on it.
+pub fn has_program_access(
+    conn: &mut DbConnection,
+    user_id: uuid::Uuid,
+    program: &Program,
+    min_role: CollaboratorRoles,
+) -> QueryResult<bool> {
+    if program.owner_id == Some(user_id) {
+        return Ok(true);
+    }
+
+    Ok(program_role(conn, user_id, program)?.is_some_and(|role| role >= min_role))
+}
+
+/// Whether the user can read the program: they own it, it's assigned to them as a client, it was
+/// shared with them or it's a published template of the marketplace.
+pub fn can_view_program(
+    conn: &mut DbConnection,
+    user_id: uuid::Uuid,
+    program: &Program,
+) -> QueryResult<bool> {
+    use crate::schema::clients::dsl as c_dsl;
+
+    if program.template && program.published && program.archived_at.is_none() {
+        return Ok(true);
+    }
+
+    if has_program_access(conn, user_id, program, CollaboratorRoles::Viewer)? {
+        return Ok(true);
+    }
+
+    let Some(program_client_id) = program.client_id else {
+        return Ok(false);
+    };
+
+    let client_user_id: Option<uuid::Uuid> = c_dsl::clients
+        .filter(c_dsl::id.eq(program_client_id))
+        .select(c_dsl::user_id)
+        .first(conn)?;
+
+    Ok(client_user_id == Some(user_id))
+}
+
+/// Programs shared with the user, directly or through a client, with the strongest role they
+/// were given on each.
+pub fn load_shared_programs(
+    conn: &mut DbConnection,
+    user_id: uuid::Uuid,
+) -> QueryResult<Vec<SharedProgram>> {
+    use crate::schema::{
+        client_collaborators::dsl as cc_dsl, program_collaborators::dsl as pc_dsl,
+        programs::dsl as p_dsl,
+    };
+
+    let mut shared: Vec<SharedProgram> = vec![];
+
+    let direct: Vec<(Program, CollaboratorRoles)> = p_dsl::programs
+        .inner_join(pc_dsl::program_collaborators)
+        .filter(
+            pc_dsl::user_id
+                .eq(user_id)
+                .and(p_dsl::archived_at.is_null()),
+        )
+        .select((Program::as_select(), pc_dsl::role))
+        .load(conn)?;
+
+    let through_clients: Vec<(Program, CollaboratorRoles)> = p_dsl::programs
+        .inner_join(
+            cc_dsl::client_collaborators.on(p_dsl::client_id.eq(cc_dsl::client_id.nullable())),
+        )
+        .filter(
+            cc_dsl::user_id
+                .eq(user_id)
+                .and(p_dsl::archived_at.is_null()),
+        )
+        .select((Program::as_select(), cc_dsl::role))
+        .load(conn)?;
+
+    for (program, role) in direct.into_iter().chain(through_clients) {
+        match shared.iter_mut().find(|s| s.program.id == program.id) {
+            Some(existing) => existing.role = existing.role.max(role),
+            None => shared.push(SharedProgram { program, role }),
+        }
+    }
+
+    shared.sort_by_key(|s| std::cmp::Reverse(s.program.created_at));
+
+    Ok(shared)
+}
+
+/// Programs the user can read: their own, the ones assigned to them as a client, the ones shared
+/// with them and the published templates of the marketplace. Meant as a subquery, see
+/// `can_view_program` for a single program.
+pub fn visible_program_ids<'a>(
+    user_id: uuid::Uuid,
+) -> programs::BoxedQuery<'a, Pg, sql_types::Uuid> {
+    use crate::schema::{
+        client_collaborators::dsl as cc_dsl, clients::dsl as c_dsl,
+        program_collaborators::dsl as pc_dsl, programs::dsl as p_dsl,
+    };
+
+    let own_clients = c_dsl::clients
+        .filter(c_dsl::user_id.eq(user_id))
+        .select(c_dsl::id.nullable());
+
+    let shared_programs = pc_dsl::program_collaborators
+        .filter(pc_dsl::user_id.eq(user_id))
+        .select(pc_dsl::program_id);
+
+    let shared_clients = cc_dsl::client_collaborators
+        .filter(cc_dsl::user_id.eq(user_id))
+        .select(cc_dsl::client_id.nullable());
+
+    p_dsl::programs
+        .filter(
+            p_dsl::owner_id
+                .eq(user_id)
+                .or(p_dsl::client_id.eq_any(own_clients))
+                .or(p_dsl::id.eq_any(shared_programs))
+                .or(p_dsl::client_id.eq_any(shared_clients))
+                .or(p_dsl::published
+                    .eq(true)
+                    .and(p_dsl::template.eq(true))
+                    .and(p_dsl::archived_at.is_null())),
+        )
+        .select(p_dsl::id)
+        .into_boxed()
+}
+
+/// Workouts the user can read: their own and the ones of the programs they can read, see
+/// `visible_program_ids`. Meant as a subquery.
+pub fn visible_workout_ids<'a>(
+    user_id: uuid::Uuid,
+) -> workouts::BoxedQuery<'a, Pg, sql_types::Nullable<sql_types::Uuid>> {
+    use crate::schema::workouts::dsl as w_dsl;
+
+    w_dsl::workouts
+        .filter(
+            w_dsl::owner_id
+                .eq(user_id)
+                .or(w_dsl::program_id.eq_any(visible_program_ids(user_id).nullable())),
+        )
+        .select(w_dsl::id.nullable())
+        .into_boxed()
+}
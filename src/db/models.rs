@@ -3,13 +3,27 @@ pub mod betacode;
 pub mod certification;
 pub mod client;
 pub mod client_form;
+pub mod collaborator;
+pub mod comment;
 pub mod exercise;
+pub mod exercise_catalog;
 pub mod feedback;
 pub mod notification;
+pub mod personal_record;
 pub mod program;
+pub mod program_claim;
+pub mod program_document;
+pub mod program_progress;
+pub mod program_revision;
+pub mod tag;
+pub mod taxonomy;
 pub mod user;
 pub mod workout;
+pub mod workout_alert;
+pub mod workout_block;
 pub mod workout_data;
+pub mod workout_feedback;
+pub mod workout_session;
 
 #[derive(diesel_derive_enum::DbEnum, Debug, Serialize, Clone, Deserialize)]
 #[ExistingTypePath = "crate::schema::sql_types::IntensityChoices"]
@@ -29,3 +29,18 @@ pub struct NewCertification {
     pub user_id: Option<uuid::Uuid>,
     pub expiration: Option<chrono::NaiveDate>,
 }
+
+#[derive(AsChangeset, Deserialize, Debug)]
+#[diesel(table_name = crate::schema::certifications)]
+pub struct PatchCertification {
+    pub name: Option<String>,
+    /// `null` clears the expiration
+    #[serde(default, deserialize_with = "crate::util::double_option")]
+    pub expiration: Option<Option<chrono::NaiveDate>>,
+}
+
+impl PatchCertification {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.expiration.is_none()
+    }
+}
@@ -38,6 +38,10 @@ pub struct Client {
     pub status: String,
     pub invite: InviteStates,
     pub accepted_invite_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(skip_serializing)]
+    pub calendar_token: Option<String>,
+    /// Whether the client's equipment was set, an empty list then means bodyweight only
+    pub equipment_recorded: bool,
 }
 
 #[derive(Serialize)]
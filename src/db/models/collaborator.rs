@@ -0,0 +1,96 @@
+use crate::db::models::{client::Client, program::Program, user::PublicUser};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Ordered by how much access the role gives, so the strongest of several grants is the `max`.
+#[derive(
+    diesel_derive_enum::DbEnum,
+    Debug,
+    Serialize,
+    Clone,
+    Copy,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[ExistingTypePath = "crate::schema::sql_types::CollaboratorRoles"]
+pub enum CollaboratorRoles {
+    Viewer,
+    Editor,
+}
+
+// program_collaborators (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     program_id -> Uuid,
+//     user_id -> Uuid,
+//     granted_by -> Nullable<Uuid>,
+//     role -> CollaboratorRoles,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+#[diesel(table_name = crate::schema::program_collaborators, check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(Program))]
+pub struct ProgramCollaborator {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    // Relationships
+    pub program_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub granted_by: Option<uuid::Uuid>,
+
+    // Fields
+    pub role: CollaboratorRoles,
+}
+
+// client_collaborators (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     client_id -> Uuid,
+//     user_id -> Uuid,
+//     granted_by -> Nullable<Uuid>,
+//     role -> CollaboratorRoles,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+#[diesel(table_name = crate::schema::client_collaborators, check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(Client))]
+pub struct ClientCollaborator {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    // Relationships
+    pub client_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub granted_by: Option<uuid::Uuid>,
+
+    // Fields
+    pub role: CollaboratorRoles,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NewCollaborator {
+    pub user_id: uuid::Uuid,
+    pub role: CollaboratorRoles,
+}
+
+#[derive(Serialize)]
+pub struct CollaboratorWithUser<T> {
+    #[serde(flatten)]
+    pub collaborator: T,
+    pub user: PublicUser,
+}
+
+/// A program someone else owns that the user was given access to, directly or through one of
+/// the owner's clients.
+#[derive(Serialize)]
+pub struct SharedProgram {
+    #[serde(flatten)]
+    pub program: Program,
+    pub role: CollaboratorRoles,
+}
@@ -0,0 +1,109 @@
+use super::{user::PublicUser, workout::Workout};
+use diesel::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+// comments (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     edited_at -> Nullable<Timestamptz>,
+//     deleted_at -> Nullable<Timestamptz>,
+//     workout_id -> Uuid,
+//     exercise_id -> Nullable<Uuid>,
+//     parent_id -> Nullable<Uuid>,
+//     author_id -> Uuid,
+//     body -> Text,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+#[diesel(table_name = crate::schema::comments, check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(Workout))]
+#[diesel(belongs_to(Comment, foreign_key = parent_id))]
+pub struct Comment {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub edited_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Deleted comments keep their place in the thread with an empty body
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    // Relationships
+    pub workout_id: uuid::Uuid,
+    pub exercise_id: Option<uuid::Uuid>,
+    pub parent_id: Option<uuid::Uuid>,
+    pub author_id: uuid::Uuid,
+
+    // Fields
+    pub body: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NewComment {
+    pub body: String,
+    /// Starts the thread on one of the workout's exercises instead of the workout itself
+    pub exercise_id: Option<uuid::Uuid>,
+    /// Comment being replied to, replies always end up in the thread of the first comment
+    pub parent_id: Option<uuid::Uuid>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PatchComment {
+    pub body: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct CommentListingParams {
+    /// Only the threads on this exercise
+    pub exercise: Option<uuid::Uuid>,
+}
+
+#[derive(Serialize)]
+pub struct CommentWithAuthor {
+    #[serde(flatten)]
+    pub comment: Comment,
+    pub author: PublicUser,
+}
+
+#[derive(Serialize)]
+pub struct CommentThread {
+    #[serde(flatten)]
+    pub comment: CommentWithAuthor,
+    pub replies: Vec<CommentWithAuthor>,
+}
+
+pub const MAX_COMMENT_LENGTH: usize = 5000;
+
+/// Usernames mentioned as `@user_name` in a comment, without duplicates. Emails aren't mentions.
+pub fn parse_mentions(body: &str) -> Vec<String> {
+    let re = Regex::new(r"(?:^|[^\w@.])@([A-Za-z0-9_-]+)").unwrap();
+
+    let mut mentions: Vec<String> = vec![];
+
+    for caps in re.captures_iter(body) {
+        let user_name = caps[1].to_lowercase();
+
+        if !mentions.contains(&user_name) {
+            mentions.push(user_name);
+        }
+    }
+
+    mentions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mentions() {
+        assert_eq!(
+            parse_mentions("@Coach-Jake keep your chest up, cc @sam and @coach-jake. sam@x.com"),
+            vec!["coach-jake".to_string(), "sam".to_string()]
+        );
+        assert_eq!(
+            parse_mentions("nice set @jane_doe!"),
+            vec!["jane_doe".to_string()]
+        );
+        assert!(parse_mentions("no mentions here").is_empty());
+    }
+}
@@ -1,12 +1,29 @@
-use super::IntensityChoices;
-use crate::db::models::{user::User, workout::Workout};
-use diesel::prelude::*;
+use super::{exercise_catalog::CatalogExercise, IntensityChoices};
+use crate::{
+    db::models::{user::User, workout::Workout},
+    util::prescription::Prescription,
+};
+use diesel::{associations::BelongsTo, prelude::*};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+/// An exercise as prescribed in a workout, what the exercise is comes from its catalog entry.
+#[derive(
+    Debug,
+    Clone,
+    Queryable,
+    Selectable,
+    Insertable,
+    AsChangeset,
+    Serialize,
+    Deserialize,
+    Identifiable,
+    Associations,
+)]
 #[diesel(table_name = crate::schema::exercises, check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
 #[diesel(belongs_to(Workout))]
 #[diesel(belongs_to(User, foreign_key=owner_id))]
+#[diesel(belongs_to(CatalogExercise, foreign_key=catalog_id))]
 pub struct Exercise {
     pub id: uuid::Uuid,
     pub created_at: chrono::DateTime<chrono::Utc>,
@@ -14,41 +31,248 @@ pub struct Exercise {
     // Relationships
     pub workout_id: Option<uuid::Uuid>,
     pub owner_id: Option<uuid::Uuid>,
+    pub catalog_id: uuid::Uuid,
+    #[serde(default)]
+    pub block_id: Option<uuid::Uuid>,
 
     // Fields
-    pub name: String,
-    pub description: String,
     // Use duration for time-based exercises, reps for rep-based exercises
     pub duration: String,
     pub reps: String,
     pub sets: i32,
     pub rest_period: String,
     pub intensity: IntensityChoices,
-    pub equipment: String,
-    pub muscle_groups: String,
-    pub image: String,
-    pub video: String,
-    pub instructions: String,
     pub sequence: i32,
     pub slug: String,
+    #[serde(default)]
+    pub load: Option<f64>,
+    #[serde(default)]
+    pub load_unit: String,
+    #[serde(default)]
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    // Prescription, parsed from reps, duration and rest_period
+    pub reps_min: Option<i32>,
+    pub reps_max: Option<i32>,
+    #[serde(default)]
+    pub amrap: bool,
+    pub duration_seconds: Option<i32>,
+    pub tut_seconds: Option<i32>,
+    #[serde(default)]
+    pub tempo: String,
+    pub distance_meters: Option<f64>,
+    pub rest_seconds: Option<i32>,
+    pub percent_1rm: Option<f64>,
+}
+
+impl Exercise {
+    pub fn prescription(&self) -> Prescription {
+        Prescription {
+            reps_min: self.reps_min,
+            reps_max: self.reps_max,
+            amrap: self.amrap,
+            duration_seconds: self.duration_seconds,
+            tut_seconds: self.tut_seconds,
+            tempo: self.tempo.clone(),
+            distance_meters: self.distance_meters,
+            rest_seconds: self.rest_seconds,
+            percent_1rm: self.percent_1rm,
+        }
+    }
+
+    /// Parses the prescription out of the display strings again, used for rows restored from
+    /// snapshots taken before the prescription existed.
+    pub fn reparse_prescription(&mut self) {
+        let (_, p) = Prescription::parse(&self.reps, &self.duration, &self.rest_period);
+
+        self.reps_min = p.reps_min;
+        self.reps_max = p.reps_max;
+        self.amrap = p.amrap;
+        self.duration_seconds = p.duration_seconds;
+        self.tut_seconds = p.tut_seconds;
+        self.tempo = p.tempo;
+        self.distance_meters = p.distance_meters;
+        self.rest_seconds = p.rest_seconds;
+        self.percent_1rm = p.percent_1rm;
+    }
+}
+
+/// An exercise along with its catalog entry, selected from `exercises` joined with
+/// `exercise_catalog`.
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ExerciseWithCatalog {
+    #[diesel(embed)]
+    #[serde(flatten)]
+    pub exercise: Exercise,
+    #[diesel(embed)]
+    pub catalog: CatalogExercise,
+}
+
+impl BelongsTo<Workout> for ExerciseWithCatalog {
+    type ForeignKey = uuid::Uuid;
+    type ForeignKeyColumn = crate::schema::exercises::workout_id;
+
+    fn foreign_key(&self) -> Option<&uuid::Uuid> {
+        self.exercise.workout_id.as_ref()
+    }
+
+    fn foreign_key_column() -> Self::ForeignKeyColumn {
+        crate::schema::exercises::workout_id
+    }
 }
 
-#[derive(Insertable, Deserialize, Debug, AsChangeset)]
+/// Exercises can be written with the legacy strings, the structured prescription or both. The
+/// structured fields win and the strings they cover are rendered from them, see `normalize`.
+#[derive(Insertable, Deserialize, Debug)]
 #[diesel(table_name = crate::schema::exercises)]
 pub struct NewExercise {
     pub workout_id: Option<uuid::Uuid>,
-    pub name: String,
-    pub description: String,
+    pub catalog_id: uuid::Uuid,
+    /// Has to be a block of the same workout. Kept by an update when it's left out, `null` clears
+    /// it.
+    #[serde(default, deserialize_with = "crate::util::double_option")]
+    pub block_id: Option<Option<uuid::Uuid>>,
     pub duration: String,
     pub reps: String,
     pub sets: i32,
     pub rest_period: String,
     pub intensity: IntensityChoices,
-    pub equipment: String,
-    pub muscle_groups: String,
-    pub image: String,
-    pub video: String,
-    pub instructions: String,
     pub sequence: i32,
     // pub slug: String,
+    /// Kept by an update when it's left out, `null` clears it
+    #[serde(default, deserialize_with = "crate::util::double_option")]
+    pub load: Option<Option<f64>>,
+    #[serde(default)]
+    pub load_unit: String,
+
+    #[serde(default)]
+    pub reps_min: Option<i32>,
+    #[serde(default)]
+    pub reps_max: Option<i32>,
+    #[serde(default)]
+    pub amrap: bool,
+    #[serde(default)]
+    pub duration_seconds: Option<i32>,
+    #[serde(default)]
+    pub tut_seconds: Option<i32>,
+    #[serde(default)]
+    pub tempo: String,
+    #[serde(default)]
+    pub distance_meters: Option<f64>,
+    #[serde(default)]
+    pub rest_seconds: Option<i32>,
+    #[serde(default)]
+    pub percent_1rm: Option<f64>,
+}
+
+impl NewExercise {
+    /// Renders the strings covered by the structured fields that were sent, then parses all of
+    /// them so the stored prescription always matches the display form. Sets written as `3x8`
+    /// are only used when `sets` wasn't given.
+    pub fn normalize(&mut self) -> Result<(), String> {
+        let explicit = Prescription {
+            reps_min: self.reps_min,
+            reps_max: self.reps_max,
+            amrap: self.amrap,
+            duration_seconds: self.duration_seconds,
+            tut_seconds: self.tut_seconds,
+            tempo: self.tempo.trim().to_lowercase(),
+            distance_meters: self.distance_meters,
+            rest_seconds: self.rest_seconds,
+            percent_1rm: self.percent_1rm,
+        };
+
+        explicit.validate()?;
+
+        let (reps, duration, rest_period) = explicit.display();
+
+        if explicit.has_reps() {
+            self.reps = reps;
+        }
+
+        if explicit.has_duration() {
+            self.duration = duration;
+        }
+
+        if explicit.rest_seconds.is_some() {
+            self.rest_period = rest_period;
+        }
+
+        self.reps = self.reps.trim().to_string();
+        self.duration = self.duration.trim().to_string();
+        self.rest_period = self.rest_period.trim().to_string();
+
+        let (sets, p) = Prescription::parse(&self.reps, &self.duration, &self.rest_period);
+
+        if self.sets < 1 {
+            self.sets = sets.unwrap_or(self.sets);
+        }
+
+        self.reps_min = p.reps_min;
+        self.reps_max = p.reps_max;
+        self.amrap = p.amrap;
+        self.duration_seconds = p.duration_seconds;
+        self.tut_seconds = p.tut_seconds;
+        self.tempo = p.tempo;
+        self.distance_meters = p.distance_meters;
+        self.rest_seconds = p.rest_seconds;
+        self.percent_1rm = p.percent_1rm;
+
+        Ok(())
+    }
+
+    /// Changeset of an update, see `ExerciseChanges`.
+    pub fn changes(&self) -> ExerciseChanges {
+        ExerciseChanges {
+            workout_id: self.workout_id,
+            catalog_id: self.catalog_id,
+            block_id: self.block_id,
+            duration: self.duration.clone(),
+            reps: self.reps.clone(),
+            sets: self.sets,
+            rest_period: self.rest_period.clone(),
+            intensity: self.intensity.clone(),
+            sequence: self.sequence,
+            load: self.load,
+            load_unit: self.load_unit.clone(),
+            reps_min: Some(self.reps_min),
+            reps_max: Some(self.reps_max),
+            amrap: self.amrap,
+            duration_seconds: Some(self.duration_seconds),
+            tut_seconds: Some(self.tut_seconds),
+            tempo: self.tempo.clone(),
+            distance_meters: Some(self.distance_meters),
+            rest_seconds: Some(self.rest_seconds),
+            percent_1rm: Some(self.percent_1rm),
+        }
+    }
+}
+
+/// Written by an update. The workout, block and load are kept when they're left out, `null` clears
+/// the block and the load. The prescription is always written since `normalize` derives it from
+/// the strings.
+#[derive(AsChangeset, Debug)]
+#[diesel(table_name = crate::schema::exercises)]
+pub struct ExerciseChanges {
+    pub workout_id: Option<uuid::Uuid>,
+    pub catalog_id: uuid::Uuid,
+    pub block_id: Option<Option<uuid::Uuid>>,
+    pub duration: String,
+    pub reps: String,
+    pub sets: i32,
+    pub rest_period: String,
+    pub intensity: IntensityChoices,
+    pub sequence: i32,
+    pub load: Option<Option<f64>>,
+    pub load_unit: String,
+    pub reps_min: Option<Option<i32>>,
+    pub reps_max: Option<Option<i32>>,
+    pub amrap: bool,
+    pub duration_seconds: Option<Option<i32>>,
+    pub tut_seconds: Option<Option<i32>>,
+    pub tempo: String,
+    pub distance_meters: Option<Option<f64>>,
+    pub rest_seconds: Option<Option<i32>>,
+    pub percent_1rm: Option<Option<f64>>,
 }
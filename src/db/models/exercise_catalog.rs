@@ -0,0 +1,185 @@
+use super::IntensityChoices;
+use crate::util::name_slug;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// What kind of movement the exercise is, substitutes with the same pattern rank higher.
+#[derive(diesel_derive_enum::DbEnum, Debug, Serialize, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::MovementPatterns"]
+pub enum MovementPatterns {
+    Squat,
+    Hinge,
+    Lunge,
+    HorizontalPush,
+    VerticalPush,
+    HorizontalPull,
+    VerticalPull,
+    Carry,
+    Core,
+    Conditioning,
+}
+
+// exercise_catalog (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     owner_id -> Nullable<Uuid>,
+//     #[max_length = 50]
+//     name -> Varchar,
+//     #[max_length = 60]
+//     slug -> Varchar,
+//     #[max_length = 255]
+//     description -> Varchar,
+//     #[max_length = 255]
+//     equipment -> Varchar,
+//     #[max_length = 255]
+//     muscle_groups -> Varchar,
+//     #[max_length = 255]
+//     image -> Varchar,
+//     #[max_length = 255]
+//     video -> Varchar,
+//     #[max_length = 500]
+//     instructions -> Varchar,
+//     movement_pattern -> Nullable<MovementPatterns>,
+//     intensity -> Nullable<IntensityChoices>,
+// }
+
+/// Canonical description of an exercise, the exercises of a workout reference one and only carry
+/// the prescription. Entries without an owner belong to the library curated by admins.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable, Serialize, Deserialize, Identifiable)]
+#[diesel(table_name = crate::schema::exercise_catalog, check_for_backend(diesel::pg::Pg))]
+pub struct CatalogExercise {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    // Relationships
+    pub owner_id: Option<uuid::Uuid>,
+
+    // Fields
+    pub name: String,
+    pub slug: String,
+    pub description: String,
+    pub equipment: String,
+    pub muscle_groups: String,
+    pub image: String,
+    pub video: String,
+    pub instructions: String,
+    pub movement_pattern: Option<MovementPatterns>,
+    /// How demanding the exercise usually is
+    pub intensity: Option<IntensityChoices>,
+}
+
+#[derive(Insertable, Deserialize, Debug)]
+#[diesel(table_name = crate::schema::exercise_catalog)]
+pub struct NewCatalogExercise {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub equipment: String,
+    #[serde(default)]
+    pub muscle_groups: String,
+    #[serde(default)]
+    pub image: String,
+    #[serde(default)]
+    pub video: String,
+    #[serde(default)]
+    pub instructions: String,
+    #[serde(default)]
+    pub movement_pattern: Option<MovementPatterns>,
+    #[serde(default)]
+    pub intensity: Option<IntensityChoices>,
+}
+
+/// The slug stays the same when an entry is renamed.
+#[derive(AsChangeset, Deserialize, Debug)]
+#[diesel(table_name = crate::schema::exercise_catalog)]
+pub struct PatchCatalogExercise {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub equipment: Option<String>,
+    pub muscle_groups: Option<String>,
+    pub image: Option<String>,
+    pub video: Option<String>,
+    pub instructions: Option<String>,
+    pub movement_pattern: Option<MovementPatterns>,
+    pub intensity: Option<IntensityChoices>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct CatalogListingParams {
+    /// Matched against the name, equipment and muscle groups of the entry
+    pub search: Option<String>,
+    pub owner: Option<uuid::Uuid>,
+    /// Only entries curated by admins
+    pub library: Option<bool>,
+}
+
+fn check_lengths(fields: &[(&str, Option<&str>, usize)]) -> Result<(), String> {
+    match fields
+        .iter()
+        .find(|(_, val, max)| val.is_some_and(|v| v.chars().count() > *max))
+    {
+        Some((field, _, max)) => Err(format!("{field} can be at most {max} characters long.")),
+        None => Ok(()),
+    }
+}
+
+impl NewCatalogExercise {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name can't be empty.".into());
+        }
+
+        check_lengths(&[
+            ("name", Some(self.name.trim()), 50),
+            ("description", Some(&self.description), 255),
+            ("equipment", Some(&self.equipment), 255),
+            ("muscle_groups", Some(&self.muscle_groups), 255),
+            ("image", Some(&self.image), 255),
+            ("video", Some(&self.video), 255),
+            ("instructions", Some(&self.instructions), 500),
+        ])
+    }
+}
+
+impl PatchCatalogExercise {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+            return Err("name can't be empty.".into());
+        }
+
+        check_lengths(&[
+            ("name", self.name.as_deref().map(str::trim), 50),
+            ("description", self.description.as_deref(), 255),
+            ("equipment", self.equipment.as_deref(), 255),
+            ("muscle_groups", self.muscle_groups.as_deref(), 255),
+            ("image", self.image.as_deref(), 255),
+            ("video", self.video.as_deref(), 255),
+            ("instructions", self.instructions.as_deref(), 500),
+        ])
+    }
+}
+
+/// Slug of a catalog entry, see `name_slug`. Names without any letter or digit still need one.
+pub fn catalog_slug(name: &str) -> String {
+    let slug = name_slug(name);
+
+    if slug.is_empty() {
+        "exercise".into()
+    } else {
+        slug
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_slug() {
+        assert_eq!(catalog_slug(" Back  Squat "), "back-squat");
+        assert_eq!(catalog_slug("Push-up (wide)"), "push-up-wide");
+        assert_eq!(catalog_slug("!!"), "exercise");
+    }
+}
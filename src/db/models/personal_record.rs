@@ -0,0 +1,253 @@
+use super::{exercise_catalog::CatalogExercise, user::PublicUser};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// What a personal record measures. Loads are compared in kg, durations in seconds.
+#[derive(
+    diesel_derive_enum::DbEnum,
+    Debug,
+    Serialize,
+    Clone,
+    Copy,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[ExistingTypePath = "crate::schema::sql_types::RecordKinds"]
+pub enum RecordKinds {
+    /// Heaviest load lifted for a rep count
+    RepMax,
+    /// One rep max estimated with the Epley formula
+    #[db_rename = "epley_1rm"]
+    Epley1rm,
+    /// One rep max estimated with the Brzycki formula
+    #[db_rename = "brzycki_1rm"]
+    Brzycki1rm,
+    /// Heaviest reps × load of a single set
+    Volume,
+    /// Longest set
+    Duration,
+}
+
+// personal_records (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     user_id -> Uuid,
+//     catalog_id -> Uuid,
+//     set_log_id -> Uuid,
+//     kind -> RecordKinds,
+//     reps -> Int4,
+//     value -> Float8,
+//     previous_value -> Nullable<Float8>,
+//     achieved_at -> Timestamptz,
+// }
+
+/// A record is never updated, beating it adds a new row so the history is kept.
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable)]
+#[diesel(table_name = crate::schema::personal_records, check_for_backend(diesel::pg::Pg))]
+pub struct PersonalRecord {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    // Relationships
+    pub user_id: uuid::Uuid,
+    pub catalog_id: uuid::Uuid,
+    pub set_log_id: uuid::Uuid,
+
+    // Fields
+    pub kind: RecordKinds,
+    /// Rep count of a rep max, 0 for the other kinds
+    pub reps: i32,
+    pub value: f64,
+    /// The record this one beat
+    pub previous_value: Option<f64>,
+    pub achieved_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A record along with its catalog entry, selected from `personal_records` joined with
+/// `exercise_catalog`.
+#[derive(Debug, Queryable, Selectable, Serialize)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RecordWithCatalog {
+    #[diesel(embed)]
+    #[serde(flatten)]
+    pub record: PersonalRecord,
+    #[diesel(embed)]
+    pub catalog: CatalogExercise,
+}
+
+#[derive(Serialize, Debug)]
+pub struct LeaderboardEntry {
+    pub rank: i64,
+    pub user: PublicUser,
+    pub record: PersonalRecord,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct RecordListingParams {
+    /// Whose records, defaults to the requesting user
+    pub user: Option<uuid::Uuid>,
+    pub catalog: Option<uuid::Uuid>,
+    pub kind: Option<RecordKinds>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LeaderboardParams {
+    pub catalog: uuid::Uuid,
+    pub kind: RecordKinds,
+    /// Only used by rep maxes
+    #[serde(default)]
+    pub reps: i32,
+}
+
+impl PersonalRecord {
+    /// Short human readable description, e.g. for notifications.
+    pub fn describe(&self) -> String {
+        match self.kind {
+            RecordKinds::RepMax => format!("{} rep max of {:.1} kg", self.reps, self.value),
+            RecordKinds::Epley1rm | RecordKinds::Brzycki1rm => {
+                format!("estimated 1RM of {:.1} kg", self.value)
+            }
+            RecordKinds::Volume => format!("set volume of {:.1} kg", self.value),
+            RecordKinds::Duration => format!("set lasting {} s", self.value),
+        }
+    }
+
+    /// Indefinite article going with `describe`, "an estimated 1RM" or "an 8 rep max".
+    pub fn article(&self) -> &'static str {
+        let vowel_sound = match self.kind {
+            RecordKinds::RepMax => {
+                self.reps.to_string().starts_with('8') || matches!(self.reps, 11 | 18)
+            }
+            RecordKinds::Epley1rm | RecordKinds::Brzycki1rm => true,
+            RecordKinds::Volume | RecordKinds::Duration => false,
+        };
+
+        if vowel_sound {
+            "an"
+        } else {
+            "a"
+        }
+    }
+}
+
+/// Sets with more reps than this don't estimate a one rep max, both formulas drift too far.
+pub const MAX_ESTIMATE_REPS: i32 = 12;
+
+const KG_PER_LB: f64 = 0.45359237;
+
+/// Load in kg, `None` for units that aren't a weight, e.g. a percentage or a band color.
+pub fn load_in_kg(load: f64, unit: &str) -> Option<f64> {
+    match unit.trim().to_lowercase().as_str() {
+        "" | "kg" | "kgs" => Some(load),
+        "lb" | "lbs" => Some(load * KG_PER_LB),
+        _ => None,
+    }
+}
+
+pub fn epley(load: f64, reps: i32) -> f64 {
+    if reps == 1 {
+        load
+    } else {
+        load * (1.0 + f64::from(reps) / 30.0)
+    }
+}
+
+pub fn brzycki(load: f64, reps: i32) -> f64 {
+    load * 36.0 / f64::from(37 - reps)
+}
+
+/// Every record a logged set could set, as kind, rep count and value.
+pub fn record_candidates(
+    reps: i32,
+    load: Option<f64>,
+    load_unit: &str,
+    duration_seconds: Option<i32>,
+) -> Vec<(RecordKinds, i32, f64)> {
+    let mut res = vec![];
+
+    let load_kg = load
+        .and_then(|l| load_in_kg(l, load_unit))
+        .filter(|l| *l > 0.0);
+
+    if let Some(load_kg) = load_kg.filter(|_| reps >= 1) {
+        res.push((RecordKinds::RepMax, reps, load_kg));
+
+        if reps <= MAX_ESTIMATE_REPS {
+            res.push((RecordKinds::Epley1rm, 0, epley(load_kg, reps)));
+            res.push((RecordKinds::Brzycki1rm, 0, brzycki(load_kg, reps)));
+        }
+
+        res.push((RecordKinds::Volume, 0, f64::from(reps) * load_kg));
+    }
+
+    if let Some(seconds) = duration_seconds.filter(|s| *s > 0) {
+        res.push((RecordKinds::Duration, 0, f64::from(seconds)));
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_candidates() {
+        let res = record_candidates(5, Some(100.0), "kg", None);
+        assert_eq!(res.len(), 4);
+        assert_eq!(res[0], (RecordKinds::RepMax, 5, 100.0));
+        assert!((res[1].2 - 116.666_666).abs() < 1e-3);
+        assert!((res[2].2 - 112.5).abs() < 1e-9);
+        assert_eq!(res[3], (RecordKinds::Volume, 0, 500.0));
+
+        // both estimates are the load itself for a single
+        let res = record_candidates(1, Some(225.0), "LBS", None);
+        assert!((res[0].2 - 102.058).abs() < 1e-3);
+        assert_eq!(res[1].2, res[0].2);
+        assert_eq!(res[2].2, res[0].2);
+
+        let res = record_candidates(15, Some(20.0), "", Some(45));
+        let kinds: Vec<RecordKinds> = res.iter().map(|(k, ..)| *k).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                RecordKinds::RepMax,
+                RecordKinds::Volume,
+                RecordKinds::Duration
+            ]
+        );
+
+        assert!(record_candidates(10, Some(60.0), "%", None).is_empty());
+        assert!(record_candidates(0, Some(60.0), "kg", None).is_empty());
+        assert_eq!(
+            record_candidates(0, None, "kg", Some(60)),
+            vec![(RecordKinds::Duration, 0, 60.0)]
+        );
+    }
+
+    #[test]
+    fn test_article() {
+        let record = |kind, reps| PersonalRecord {
+            id: uuid::Uuid::nil(),
+            created_at: chrono::Utc::now(),
+            user_id: uuid::Uuid::nil(),
+            catalog_id: uuid::Uuid::nil(),
+            set_log_id: uuid::Uuid::nil(),
+            kind,
+            reps,
+            value: 100.0,
+            previous_value: None,
+            achieved_at: chrono::Utc::now(),
+        };
+
+        assert_eq!(record(RecordKinds::Epley1rm, 0).article(), "an");
+        assert_eq!(record(RecordKinds::RepMax, 8).article(), "an");
+        assert_eq!(record(RecordKinds::RepMax, 11).article(), "an");
+        assert_eq!(record(RecordKinds::RepMax, 5).article(), "a");
+        assert_eq!(record(RecordKinds::Volume, 0).article(), "a");
+    }
+}
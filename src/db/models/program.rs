@@ -1,3 +1,4 @@
+use super::workout::WorkoutWithExercises;
 use crate::db::models::user::User;
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -24,10 +25,51 @@ use serde::{Deserialize, Serialize};
 //     template -> Bool,
 //     client_id -> Nullable<Uuid>,
 //     active -> Bool,
+//     complete -> Bool,
+//     start_date -> Nullable<Date>,
+//     training_days -> Array<Nullable<Int4>>,
+//     published -> Bool,
+//     published_at -> Nullable<Timestamptz>,
+//     price_tier -> PriceTiers,
+//     level -> ProgramLevels,
 // }
 
-#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+#[derive(
+    diesel_derive_enum::DbEnum, Debug, Serialize, Clone, Deserialize, PartialEq, Eq, Default,
+)]
+#[ExistingTypePath = "crate::schema::sql_types::PriceTiers"]
+pub enum PriceTiers {
+    #[default]
+    Free,
+    Basic,
+    Premium,
+}
+
+#[derive(
+    diesel_derive_enum::DbEnum, Debug, Serialize, Clone, Deserialize, PartialEq, Eq, Default,
+)]
+#[ExistingTypePath = "crate::schema::sql_types::ProgramLevels"]
+pub enum ProgramLevels {
+    #[default]
+    Beginner,
+    Intermediate,
+    Advanced,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Queryable,
+    Selectable,
+    Insertable,
+    AsChangeset,
+    Serialize,
+    Deserialize,
+    Identifiable,
+    Associations,
+)]
 #[diesel(table_name = crate::schema::programs, check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
 #[diesel(belongs_to(User, foreign_key=owner_id))]
 pub struct Program {
     pub id: uuid::Uuid,
@@ -49,6 +91,48 @@ pub struct Program {
     pub client_id: Option<uuid::Uuid>,
     pub active: bool,
     pub complete: bool,
+    pub start_date: Option<chrono::NaiveDate>,
+    // ISO weekdays, 1 = Monday ... 7 = Sunday
+    pub training_days: Vec<Option<i32>>,
+
+    // Marketplace listing, defaulted so revisions from before the marketplace still load
+    #[serde(default)]
+    pub published: bool,
+    #[serde(default)]
+    pub published_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub price_tier: PriceTiers,
+    #[serde(default)]
+    pub level: ProgramLevels,
+
+    // Set when the program is deleted, it's purged for good after the retention period
+    #[serde(default)]
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ProgramWithWorkouts {
+    #[serde(flatten)]
+    pub program: Program,
+    pub workouts: Vec<WorkoutWithExercises>,
+}
+
+/// A published template as it shows up in the marketplace.
+#[derive(Serialize)]
+pub struct ProgramListing {
+    #[serde(flatten)]
+    pub program: Program,
+    pub weeks: i32,
+    pub workout_count: i64,
+    pub claim_count: i64,
+}
+
+impl From<(Program, Vec<WorkoutWithExercises>)> for ProgramWithWorkouts {
+    fn from(value: (Program, Vec<WorkoutWithExercises>)) -> Self {
+        let (program, workouts) = value;
+
+        ProgramWithWorkouts { program, workouts }
+    }
 }
 
 #[derive(Insertable, Deserialize, Debug, AsChangeset)]
@@ -66,6 +150,8 @@ pub struct PatchProgram {
     pub client_id: Option<uuid::Uuid>,
     pub active: Option<bool>,
     pub complete: Option<bool>,
+    pub start_date: Option<chrono::NaiveDate>,
+    pub training_days: Option<Vec<Option<i32>>>,
 }
 
 #[derive(Insertable, Deserialize, Debug, AsChangeset)]
@@ -82,4 +168,14 @@ pub struct NewProgram {
     pub template: bool,
     pub client_id: Option<uuid::Uuid>,
     pub active: bool,
+    pub start_date: Option<chrono::NaiveDate>,
+    pub training_days: Option<Vec<Option<i32>>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PublishProgram {
+    pub price_tier: PriceTiers,
+    pub level: ProgramLevels,
+    pub duration: String,
+    pub cover_image: String,
 }
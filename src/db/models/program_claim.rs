@@ -0,0 +1,33 @@
+use crate::db::models::{
+    program::{PriceTiers, Program},
+    user::User,
+};
+use diesel::prelude::*;
+use serde::Serialize;
+
+// program_claims (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     program_id -> Nullable<Uuid>,
+//     user_id -> Uuid,
+//     copy_program_id -> Nullable<Uuid>,
+//     price_tier -> PriceTiers,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+#[diesel(table_name = crate::schema::program_claims, check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(Program))]
+#[diesel(belongs_to(User))]
+pub struct ProgramClaim {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    // Relationships
+    pub program_id: Option<uuid::Uuid>,
+    pub user_id: uuid::Uuid,
+    pub copy_program_id: Option<uuid::Uuid>,
+
+    // Fields
+    pub price_tier: PriceTiers,
+}
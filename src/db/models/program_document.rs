@@ -0,0 +1,520 @@
+//! Portable format used to export and import a program along with its workouts and exercises.
+//!
+//! Documents can be written as JSON or YAML and leave out everything that is tied to a single
+//! account or database (ids, slugs, owners, clients and completion state), so they can be moved
+//! between accounts, kept as backups or written by hand:
+//!
+//! ```yaml
+//! version: 2
+//! name: Strength Block
+//! intensity: Medium
+//! training_days: [1, 3, 5]
+//! workouts:
+//!   - name: Lower Body
+//!     week: 1
+//!     blocks:
+//!       - block_type: Superset
+//!         rounds: 3
+//!     exercises:
+//!       - name: Back Squat
+//!         sets: 3
+//!         reps: 8-12
+//!         rest_period: 90 sec
+//!         block: 0
+//! ```
+//!
+//! Only `version` and the `name` of the program, its workouts and exercises are required. Lists
+//! keep their order, a missing `sequence` is filled in from the position in the list. Exercises
+//! refer to the blocks of their workout by position.
+
+use super::{
+    exercise::ExerciseWithCatalog,
+    program::{ProgramLevels, ProgramWithWorkouts},
+    workout::WorkoutWithExercises,
+    workout_block::{BlockTypes, NewWorkoutBlock, WorkoutBlock},
+    IntensityChoices,
+};
+use crate::{
+    error::FieldError,
+    util::calendar::{valid_training_days, MAX_WORKOUT_WEEK},
+};
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever the format changes in a way older documents can't be read as. Version 2 added
+/// workout blocks.
+pub const PROGRAM_DOCUMENT_VERSION: i32 = 2;
+
+/// Oldest version still imported, version 1 documents are version 2 documents without blocks.
+pub const OLDEST_PROGRAM_DOCUMENT_VERSION: i32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProgramDocument {
+    pub version: i32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub duration: String,
+    #[serde(default)]
+    pub focus_areas: String,
+    #[serde(default)]
+    pub target_audience: String,
+    #[serde(default)]
+    pub program_image: String,
+    #[serde(default = "default_intensity")]
+    pub intensity: IntensityChoices,
+    #[serde(default)]
+    pub level: ProgramLevels,
+    // ISO weekdays, 1 = Monday ... 7 = Sunday
+    #[serde(default)]
+    pub training_days: Vec<i32>,
+    #[serde(default)]
+    pub workouts: Vec<WorkoutDocument>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkoutDocument {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub duration: String,
+    #[serde(default = "default_week")]
+    pub week: i32,
+    pub sequence: Option<i32>,
+    #[serde(default = "default_intensity")]
+    pub intensity: IntensityChoices,
+    #[serde(default)]
+    pub workout_type: String,
+    #[serde(default)]
+    pub equipment_needed: String,
+    #[serde(default)]
+    pub image: String,
+    #[serde(default)]
+    pub video: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocks: Vec<BlockDocument>,
+    #[serde(default)]
+    pub exercises: Vec<ExerciseDocument>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockDocument {
+    #[serde(default)]
+    pub block_type: BlockTypes,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(default = "default_rounds")]
+    pub rounds: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_seconds: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rest_seconds: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub round_rest_seconds: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExerciseDocument {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub duration: String,
+    #[serde(default)]
+    pub reps: String,
+    #[serde(default)]
+    pub sets: i32,
+    #[serde(default)]
+    pub rest_period: String,
+    #[serde(default = "default_intensity")]
+    pub intensity: IntensityChoices,
+    #[serde(default)]
+    pub equipment: String,
+    #[serde(default)]
+    pub muscle_groups: String,
+    #[serde(default)]
+    pub image: String,
+    #[serde(default)]
+    pub video: String,
+    #[serde(default)]
+    pub instructions: String,
+    pub sequence: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load: Option<f64>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub load_unit: String,
+    /// Position of the exercise's block in the blocks of its workout
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block: Option<usize>,
+    /// Set for exercises generated from existing ones, documents match exercises by name
+    #[serde(skip)]
+    pub catalog_id: Option<uuid::Uuid>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DocumentFormat {
+    Json,
+    Yaml,
+}
+
+impl DocumentFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            DocumentFormat::Json => "application/json",
+            DocumentFormat::Yaml => "application/yaml",
+        }
+    }
+}
+
+fn default_intensity() -> IntensityChoices {
+    IntensityChoices::Low
+}
+
+fn default_week() -> i32 {
+    1
+}
+
+fn default_rounds() -> i32 {
+    1
+}
+
+impl From<&ProgramWithWorkouts> for ProgramDocument {
+    fn from(value: &ProgramWithWorkouts) -> Self {
+        let program = &value.program;
+
+        ProgramDocument {
+            version: PROGRAM_DOCUMENT_VERSION,
+            name: program.name.clone(),
+            description: program.description.clone(),
+            duration: program.duration.clone(),
+            focus_areas: program.focus_areas.clone(),
+            target_audience: program.target_audience.clone(),
+            program_image: program.program_image.clone(),
+            intensity: program.intensity.clone(),
+            level: program.level.clone(),
+            training_days: program.training_days.iter().flatten().copied().collect(),
+            workouts: value.workouts.iter().map(WorkoutDocument::from).collect(),
+        }
+    }
+}
+
+impl From<&WorkoutWithExercises> for WorkoutDocument {
+    fn from(value: &WorkoutWithExercises) -> Self {
+        let workout = &value.workout;
+
+        WorkoutDocument {
+            name: workout.name.clone(),
+            description: workout.description.clone(),
+            duration: workout.duration.clone(),
+            week: workout.week,
+            sequence: Some(workout.sequence),
+            intensity: workout.intensity.clone(),
+            workout_type: workout.workout_type.clone(),
+            equipment_needed: workout.equipment_needed.clone(),
+            image: workout.image.clone(),
+            video: workout.video.clone(),
+            blocks: value.blocks.iter().map(BlockDocument::from).collect(),
+            exercises: value
+                .exercises
+                .iter()
+                .map(|e| ExerciseDocument {
+                    block: block_position(&value.blocks, e.exercise.block_id),
+                    ..ExerciseDocument::from(e)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&WorkoutBlock> for BlockDocument {
+    fn from(block: &WorkoutBlock) -> Self {
+        BlockDocument {
+            block_type: block.block_type,
+            name: block.name.clone(),
+            rounds: block.rounds,
+            work_seconds: block.work_seconds,
+            rest_seconds: block.rest_seconds,
+            round_rest_seconds: block.round_rest_seconds,
+        }
+    }
+}
+
+impl BlockDocument {
+    pub fn to_new_block(&self, sequence: i32) -> NewWorkoutBlock {
+        NewWorkoutBlock {
+            block_type: self.block_type,
+            name: self.name.clone(),
+            sequence,
+            rounds: self.rounds,
+            work_seconds: self.work_seconds,
+            rest_seconds: self.rest_seconds,
+            round_rest_seconds: self.round_rest_seconds,
+        }
+    }
+}
+
+/// Position of the block in `blocks`, as exercise documents refer to it.
+pub fn block_position(blocks: &[WorkoutBlock], block_id: Option<uuid::Uuid>) -> Option<usize> {
+    block_id.and_then(|b_id| blocks.iter().position(|b| b.id == b_id))
+}
+
+impl From<&ExerciseWithCatalog> for ExerciseDocument {
+    fn from(value: &ExerciseWithCatalog) -> Self {
+        let ExerciseWithCatalog { exercise, catalog } = value;
+
+        ExerciseDocument {
+            name: catalog.name.clone(),
+            description: catalog.description.clone(),
+            duration: exercise.duration.clone(),
+            reps: exercise.reps.clone(),
+            sets: exercise.sets,
+            rest_period: exercise.rest_period.clone(),
+            intensity: exercise.intensity.clone(),
+            equipment: catalog.equipment.clone(),
+            muscle_groups: catalog.muscle_groups.clone(),
+            image: catalog.image.clone(),
+            video: catalog.video.clone(),
+            instructions: catalog.instructions.clone(),
+            sequence: Some(exercise.sequence),
+            load: exercise.load,
+            load_unit: exercise.load_unit.clone(),
+            block: None,
+            catalog_id: None,
+        }
+    }
+}
+
+/// Collects errors for text fields that are empty (when required) or longer than their column.
+struct Limits<'a> {
+    errors: &'a mut Vec<FieldError>,
+    prefix: String,
+}
+
+impl Limits<'_> {
+    fn required(&mut self, field: &str, val: &str, max: usize) {
+        if val.trim().is_empty() {
+            self.errors
+                .push(FieldError::new(self.path(field), "must not be empty"));
+        }
+        self.max(field, val, max);
+    }
+
+    fn max(&mut self, field: &str, val: &str, max: usize) {
+        if val.chars().count() > max {
+            self.errors.push(FieldError::new(
+                self.path(field),
+                format!("must be at most {max} characters"),
+            ));
+        }
+    }
+
+    fn check(&mut self, field: &str, valid: bool, detail: &str) {
+        if !valid {
+            self.errors.push(FieldError::new(self.path(field), detail));
+        }
+    }
+
+    fn path(&self, field: &str) -> String {
+        if self.prefix.is_empty() {
+            return field.to_string();
+        }
+        format!("{}.{field}", self.prefix)
+    }
+}
+
+impl ProgramDocument {
+    /// Checks the document against the limits of the columns it ends up in. Every invalid field
+    /// is reported, not just the first one.
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = vec![];
+
+        let mut program = Limits {
+            errors: &mut errors,
+            prefix: String::new(),
+        };
+
+        program.check(
+            "version",
+            (OLDEST_PROGRAM_DOCUMENT_VERSION..=PROGRAM_DOCUMENT_VERSION).contains(&self.version),
+            &format!(
+                "unsupported version, expected {OLDEST_PROGRAM_DOCUMENT_VERSION} to \
+                {PROGRAM_DOCUMENT_VERSION}"
+            ),
+        );
+        program.required("name", &self.name, 50);
+        program.max("description", &self.description, 255);
+        program.max("duration", &self.duration, 50);
+        program.max("focus_areas", &self.focus_areas, 255);
+        program.max("target_audience", &self.target_audience, 50);
+        program.max("program_image", &self.program_image, 255);
+        program.check(
+            "training_days",
+            valid_training_days(&self.training_days_column()),
+            "must be ISO weekdays, 1 (Monday) through 7 (Sunday)",
+        );
+
+        for (w_idx, workout) in self.workouts.iter().enumerate() {
+            let mut w = Limits {
+                errors: &mut errors,
+                prefix: format!("workouts[{w_idx}]"),
+            };
+
+            w.required("name", &workout.name, 50);
+            w.max("description", &workout.description, 255);
+            w.max("duration", &workout.duration, 50);
+            w.max("workout_type", &workout.workout_type, 50);
+            w.max("equipment_needed", &workout.equipment_needed, 255);
+            w.max("image", &workout.image, 255);
+            w.max("video", &workout.video, 255);
+            w.check(
+                "week",
+                (1..=MAX_WORKOUT_WEEK).contains(&workout.week),
+                &format!("must be between 1 and {MAX_WORKOUT_WEEK}"),
+            );
+            w.check(
+                "blocks",
+                self.version >= 2 || workout.blocks.is_empty(),
+                "require version 2",
+            );
+            w.check(
+                "sequence",
+                !matches!(workout.sequence, Some(s) if s < 0),
+                "must not be negative",
+            );
+
+            for (b_idx, block) in workout.blocks.iter().enumerate() {
+                if let Err(err) = block.to_new_block(b_idx as i32).validate() {
+                    errors.push(FieldError::new(
+                        format!("workouts[{w_idx}].blocks[{b_idx}]"),
+                        err,
+                    ));
+                }
+            }
+
+            for (e_idx, exercise) in workout.exercises.iter().enumerate() {
+                let mut e = Limits {
+                    errors: &mut errors,
+                    prefix: format!("workouts[{w_idx}].exercises[{e_idx}]"),
+                };
+
+                e.required("name", &exercise.name, 50);
+                e.max("description", &exercise.description, 255);
+                e.max("duration", &exercise.duration, 50);
+                e.max("reps", &exercise.reps, 50);
+                e.max("rest_period", &exercise.rest_period, 50);
+                e.max("equipment", &exercise.equipment, 255);
+                e.max("muscle_groups", &exercise.muscle_groups, 255);
+                e.max("image", &exercise.image, 255);
+                e.max("video", &exercise.video, 255);
+                e.max("instructions", &exercise.instructions, 500);
+                e.max("load_unit", &exercise.load_unit, 10);
+                e.check("sets", exercise.sets >= 0, "must not be negative");
+                e.check(
+                    "load",
+                    !matches!(exercise.load, Some(load) if load.is_nan() || load < 0.0),
+                    "must not be negative",
+                );
+                e.check(
+                    "sequence",
+                    !matches!(exercise.sequence, Some(s) if s < 0),
+                    "must not be negative",
+                );
+                e.check(
+                    "block",
+                    exercise.block.is_none_or(|b| b < workout.blocks.len()),
+                    "must be the position of one of the workout's blocks",
+                );
+            }
+        }
+
+        errors
+    }
+
+    /// Parses a document, the error points at the part of the document that couldn't be read.
+    pub fn parse(body: &str, format: DocumentFormat) -> Result<ProgramDocument, FieldError> {
+        fn field_error<E: std::fmt::Display>(err: serde_path_to_error::Error<E>) -> FieldError {
+            let path = match err.path().to_string() {
+                root if root == "." => String::new(),
+                path => path,
+            };
+            FieldError::new(path, err.inner().to_string())
+        }
+
+        match format {
+            DocumentFormat::Json => {
+                let de = &mut serde_json::Deserializer::from_str(body);
+                serde_path_to_error::deserialize(de).map_err(field_error)
+            }
+            DocumentFormat::Yaml => {
+                let de = serde_yaml::Deserializer::from_str(body);
+                serde_path_to_error::deserialize(de).map_err(field_error)
+            }
+        }
+    }
+
+    pub fn render(&self, format: DocumentFormat) -> Result<String, String> {
+        match format {
+            DocumentFormat::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+            DocumentFormat::Yaml => serde_yaml::to_string(self).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// `training_days` the way it's stored on `programs`.
+    pub fn training_days_column(&self) -> Vec<Option<i32>> {
+        self.training_days.iter().copied().map(Some).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_reports_document_paths() {
+        let yaml = r#"
+version: 1
+name: Strength Block
+training_days: [1, 8]
+workouts:
+  - name: Lower Body
+    exercises:
+      - name: Back Squat
+        sets: -1
+      - name: ""
+"#;
+
+        let doc = ProgramDocument::parse(yaml, DocumentFormat::Yaml).unwrap();
+
+        let paths: Vec<String> = doc.validate().into_iter().map(|e| e.path).collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                "training_days",
+                "workouts[0].exercises[0].sets",
+                "workouts[0].exercises[1].name",
+            ]
+        );
+
+        let typo = r#"{"version": 1, "name": "A", "workouts": [{"name": "B", "wek": 2}]}"#;
+        let err = ProgramDocument::parse(typo, DocumentFormat::Json).unwrap_err();
+
+        assert_eq!(err.path, "workouts[0].wek");
+
+        let blocks = r#"{"version": 1, "name": "A", "workouts": [{"name": "B", "blocks": [{}]}]}"#;
+        let doc = ProgramDocument::parse(blocks, DocumentFormat::Json).unwrap();
+        let paths: Vec<String> = doc.validate().into_iter().map(|e| e.path).collect();
+
+        assert_eq!(paths, vec!["workouts[0].blocks"]);
+
+        let newer = r#"{"version": 3, "name": "A"}"#;
+        let doc = ProgramDocument::parse(newer, DocumentFormat::Json).unwrap();
+
+        assert_eq!(doc.validate()[0].path, "version");
+    }
+}
@@ -0,0 +1,223 @@
+use crate::db::models::{program::Program, workout::Workout};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// program_progress (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     updated_at -> Timestamptz,
+//     program_id -> Uuid,
+//     next_workout_id -> Nullable<Uuid>,
+//     total_workouts -> Int4,
+//     completed_workouts -> Int4,
+//     current_streak -> Int4,
+//     longest_streak -> Int4,
+//     last_completed_at -> Nullable<Timestamptz>,
+//     weeks -> Jsonb,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+#[diesel(table_name = crate::schema::program_progress, check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(Program))]
+pub struct ProgramProgress {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+
+    // Relationships
+    pub program_id: uuid::Uuid,
+    pub next_workout_id: Option<uuid::Uuid>,
+
+    // Fields
+    pub total_workouts: i32,
+    pub completed_workouts: i32,
+    pub current_streak: i32,
+    pub longest_streak: i32,
+    pub last_completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub weeks: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct WeekProgress {
+    pub week: i32,
+    pub total_workouts: i32,
+    pub completed_workouts: i32,
+    pub percent_complete: f64,
+}
+
+#[derive(Insertable, AsChangeset, Debug)]
+#[diesel(table_name = crate::schema::program_progress)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewProgramProgress {
+    pub program_id: uuid::Uuid,
+    pub next_workout_id: Option<uuid::Uuid>,
+    pub total_workouts: i32,
+    pub completed_workouts: i32,
+    pub current_streak: i32,
+    pub longest_streak: i32,
+    pub last_completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub weeks: serde_json::Value,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl NewProgramProgress {
+    /// Works out the progress of a program from its workouts, which must be ordered by week and
+    /// sequence.
+    ///
+    /// Streaks count workouts completed back to back in program order, skipping a workout breaks
+    /// the streak. The current streak is the run ending at the last completed workout and the
+    /// next workout due is the first incomplete one after it, or the first incomplete one if the
+    /// client hasn't completed anything after the skipped workouts.
+    pub fn from_workouts(program_id: uuid::Uuid, workouts: &[Workout]) -> NewProgramProgress {
+        let mut weeks: Vec<WeekProgress> = vec![];
+
+        for workout in workouts {
+            let week = match weeks.iter_mut().find(|w| w.week == workout.week) {
+                Some(week) => week,
+                None => {
+                    weeks.push(WeekProgress {
+                        week: workout.week,
+                        total_workouts: 0,
+                        completed_workouts: 0,
+                        percent_complete: 0.0,
+                    });
+                    weeks.last_mut().expect("just pushed")
+                }
+            };
+
+            week.total_workouts += 1;
+            if workout.complete {
+                week.completed_workouts += 1;
+            }
+        }
+
+        for week in weeks.iter_mut() {
+            week.percent_complete = percent(week.completed_workouts, week.total_workouts);
+        }
+
+        let mut run = 0;
+        let mut longest_streak = 0;
+        for workout in workouts {
+            run = if workout.complete { run + 1 } else { 0 };
+            longest_streak = longest_streak.max(run);
+        }
+
+        let last_completed = workouts.iter().rposition(|w| w.complete);
+
+        let current_streak = match last_completed {
+            Some(last) => workouts[..=last]
+                .iter()
+                .rev()
+                .take_while(|w| w.complete)
+                .count() as i32,
+            None => 0,
+        };
+
+        let next_workout_id = workouts
+            .iter()
+            .skip(last_completed.map(|last| last + 1).unwrap_or(0))
+            .find(|w| !w.complete)
+            .or_else(|| workouts.iter().find(|w| !w.complete))
+            .map(|w| w.id);
+
+        NewProgramProgress {
+            program_id,
+            next_workout_id,
+            total_workouts: workouts.len() as i32,
+            completed_workouts: workouts.iter().filter(|w| w.complete).count() as i32,
+            current_streak,
+            longest_streak,
+            last_completed_at: workouts.iter().filter_map(|w| w.completed_at).max(),
+            weeks: serde_json::to_value(weeks).unwrap_or_default(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+}
+
+pub fn percent(completed: i32, total: i32) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+
+    (f64::from(completed) * 1000.0 / f64::from(total)).round() / 10.0
+}
+
+/// The next workout due along with the date it's planned for, if the program has a schedule.
+#[derive(Serialize)]
+pub struct NextWorkout {
+    #[serde(flatten)]
+    pub workout: Workout,
+    pub date: Option<chrono::NaiveDate>,
+}
+
+#[derive(Serialize)]
+pub struct ProgramProgressResponse {
+    pub program_id: uuid::Uuid,
+    pub complete: bool,
+    pub total_workouts: i32,
+    pub completed_workouts: i32,
+    pub percent_complete: f64,
+    pub weeks: serde_json::Value,
+    pub current_streak: i32,
+    pub longest_streak: i32,
+    pub last_completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub next_workout: Option<NextWorkout>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::IntensityChoices;
+
+    fn workout(week: i32, complete: bool) -> Workout {
+        Workout {
+            id: uuid::Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            program_id: None,
+            owner_id: None,
+            name: String::new(),
+            description: String::new(),
+            duration: String::new(),
+            sequence: 0,
+            week,
+            intensity: IntensityChoices::Low,
+            workout_type: String::new(),
+            equipment_needed: String::new(),
+            image: String::new(),
+            video: String::new(),
+            template: false,
+            slug: String::new(),
+            complete,
+            scheduled_date: None,
+            completed_at: None,
+            archived_at: None,
+        }
+    }
+
+    #[test]
+    fn test_progress_from_workouts() {
+        // the client skipped the third workout and kept going
+        let workouts = vec![
+            workout(1, true),
+            workout(1, true),
+            workout(1, false),
+            workout(2, true),
+            workout(2, false),
+            workout(2, false),
+        ];
+
+        let progress = NewProgramProgress::from_workouts(uuid::Uuid::new_v4(), &workouts);
+
+        assert_eq!(progress.total_workouts, 6);
+        assert_eq!(progress.completed_workouts, 3);
+        assert_eq!(progress.longest_streak, 2);
+        assert_eq!(progress.current_streak, 1);
+        assert_eq!(progress.next_workout_id, Some(workouts[4].id));
+
+        let weeks: Vec<WeekProgress> = serde_json::from_value(progress.weeks).unwrap();
+        assert_eq!(weeks[0].percent_complete, 66.7);
+        assert_eq!(weeks[1].percent_complete, 33.3);
+    }
+}
@@ -0,0 +1,249 @@
+use crate::db::models::{program::Program, user::User};
+use diesel::prelude::*;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+// program_revisions (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     program_id -> Uuid,
+//     author_id -> Nullable<Uuid>,
+//     revision -> Int4,
+//     snapshot -> Jsonb,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+#[diesel(table_name = crate::schema::program_revisions, check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(Program))]
+#[diesel(belongs_to(User, foreign_key=author_id))]
+pub struct ProgramRevision {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    // Relationships
+    pub program_id: uuid::Uuid,
+    pub author_id: Option<uuid::Uuid>,
+
+    // Fields
+    pub revision: i32,
+    pub snapshot: serde_json::Value,
+}
+
+/// Revision without the snapshot, used when listing revisions.
+#[derive(Debug, Clone, Queryable, Selectable, Serialize)]
+#[diesel(table_name = crate::schema::program_revisions, check_for_backend(diesel::pg::Pg))]
+pub struct ProgramRevisionSummary {
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub program_id: uuid::Uuid,
+    pub author_id: Option<uuid::Uuid>,
+    pub revision: i32,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct EntityDiff {
+    pub id: Value,
+    pub name: Value,
+    pub kind: ChangeKind,
+    pub changes: Vec<FieldChange>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exercises: Vec<EntityDiff>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ProgramDiff {
+    pub from_revision: i32,
+    pub to_revision: i32,
+    pub program: Vec<FieldChange>,
+    pub workouts: Vec<EntityDiff>,
+}
+
+impl ProgramDiff {
+    /// Structural diff between two program snapshots. Workouts and exercises are matched on their
+    /// id, so a renamed workout shows up as changed rather than removed and added.
+    pub fn between(from: &ProgramRevision, to: &ProgramRevision) -> ProgramDiff {
+        ProgramDiff {
+            from_revision: from.revision,
+            to_revision: to.revision,
+            program: field_changes(&from.snapshot, &to.snapshot),
+            workouts: diff_children(&from.snapshot, &to.snapshot, "workouts", "exercises"),
+        }
+    }
+}
+
+fn as_object(val: &Value) -> Map<String, Value> {
+    val.as_object().cloned().unwrap_or_default()
+}
+
+/// Changes between the scalar fields of two objects, nested lists are diffed separately.
+fn field_changes(from: &Value, to: &Value) -> Vec<FieldChange> {
+    let from = as_object(from);
+    let to = as_object(to);
+
+    let mut fields: Vec<&String> = from.keys().chain(to.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let before = from.get(field).cloned().unwrap_or(Value::Null);
+            let after = to.get(field).cloned().unwrap_or(Value::Null);
+
+            if before == after || before.is_array() || after.is_array() {
+                return None;
+            }
+
+            Some(FieldChange {
+                field: field.clone(),
+                from: before,
+                to: after,
+            })
+        })
+        .collect()
+}
+
+fn diff_children(from: &Value, to: &Value, key: &str, nested_key: &str) -> Vec<EntityDiff> {
+    let children = |val: &Value| -> Vec<Value> {
+        val.get(key)
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    let from_children = children(from);
+    let to_children = children(to);
+
+    let find = |list: &[Value], id: &Value| list.iter().find(|c| c.get("id") == Some(id)).cloned();
+
+    let mut diffs = vec![];
+
+    for before in &from_children {
+        let id = before.get("id").cloned().unwrap_or(Value::Null);
+
+        match find(&to_children, &id) {
+            Some(after) => {
+                let changes = field_changes(before, &after);
+                let nested = diff_children(before, &after, nested_key, "");
+
+                if !changes.is_empty() || !nested.is_empty() {
+                    diffs.push(EntityDiff {
+                        name: after.get("name").cloned().unwrap_or(Value::Null),
+                        id,
+                        kind: ChangeKind::Changed,
+                        changes,
+                        exercises: nested,
+                    });
+                }
+            }
+            None => diffs.push(EntityDiff {
+                name: before.get("name").cloned().unwrap_or(Value::Null),
+                id,
+                kind: ChangeKind::Removed,
+                changes: vec![],
+                exercises: vec![],
+            }),
+        }
+    }
+
+    for after in &to_children {
+        let id = after.get("id").cloned().unwrap_or(Value::Null);
+
+        if find(&from_children, &id).is_none() {
+            diffs.push(EntityDiff {
+                name: after.get("name").cloned().unwrap_or(Value::Null),
+                id,
+                kind: ChangeKind::Added,
+                changes: vec![],
+                exercises: vec![],
+            });
+        }
+    }
+
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn revision(revision: i32, snapshot: Value) -> ProgramRevision {
+        ProgramRevision {
+            id: uuid::Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            program_id: uuid::Uuid::new_v4(),
+            author_id: None,
+            revision,
+            snapshot,
+        }
+    }
+
+    #[test]
+    fn test_program_diff_between_snapshots() {
+        let from = revision(
+            1,
+            json!({
+                "name": "Block A",
+                "workouts": [
+                    {"id": "w1", "name": "Legs", "sequence": 1, "exercises": [
+                        {"id": "e1", "name": "Squat", "sets": 3},
+                    ]},
+                    {"id": "w2", "name": "Push", "sequence": 2, "exercises": []},
+                ]
+            }),
+        );
+        let to = revision(
+            2,
+            json!({
+                "name": "Block B",
+                "workouts": [
+                    {"id": "w1", "name": "Legs", "sequence": 1, "exercises": [
+                        {"id": "e1", "name": "Squat", "sets": 5},
+                    ]},
+                    {"id": "w3", "name": "Pull", "sequence": 2, "exercises": []},
+                ]
+            }),
+        );
+
+        let diff = ProgramDiff::between(&from, &to);
+
+        assert_eq!(
+            diff.program,
+            vec![FieldChange {
+                field: "name".into(),
+                from: json!("Block A"),
+                to: json!("Block B"),
+            }]
+        );
+
+        let kinds: Vec<(&Value, &ChangeKind)> =
+            diff.workouts.iter().map(|w| (&w.id, &w.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (&json!("w1"), &ChangeKind::Changed),
+                (&json!("w2"), &ChangeKind::Removed),
+                (&json!("w3"), &ChangeKind::Added),
+            ]
+        );
+
+        assert_eq!(diff.workouts[0].exercises[0].changes[0].field, "sets");
+    }
+}
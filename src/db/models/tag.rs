@@ -0,0 +1,85 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Which free-text field of a program or workout the tag replaces. `FocusArea` and `Audience`
+/// tag programs, `WorkoutType` and `Equipment` tag workouts.
+#[derive(diesel_derive_enum::DbEnum, Debug, Serialize, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::TagCategories"]
+pub enum TagCategories {
+    FocusArea,
+    Audience,
+    WorkoutType,
+    Equipment,
+}
+
+impl TagCategories {
+    pub fn for_programs(&self) -> bool {
+        matches!(self, TagCategories::FocusArea | TagCategories::Audience)
+    }
+}
+
+// tags (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     category -> TagCategories,
+//     #[max_length = 50]
+//     name -> Varchar,
+//     #[max_length = 60]
+//     slug -> Varchar,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable)]
+#[diesel(table_name = crate::schema::tags, check_for_backend(diesel::pg::Pg))]
+pub struct Tag {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    // Fields
+    pub category: TagCategories,
+    pub name: String,
+    pub slug: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NewTag {
+    pub category: TagCategories,
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PatchTag {
+    pub name: String,
+}
+
+/// Replaces every tag of a program or workout.
+#[derive(Deserialize, Debug)]
+pub struct SetTags {
+    pub tag_ids: Vec<uuid::Uuid>,
+}
+
+/// How tags of the same category are combined when filtering, different categories are always
+/// combined with AND.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    /// Has at least one of the tags
+    #[default]
+    Any,
+    /// Has every one of the tags
+    All,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FacetCount {
+    #[serde(flatten)]
+    pub tag: Tag,
+    pub count: i64,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct FacetParams {
+    /// Adds the facet counts of every matched row to a listing, not only of the returned page
+    #[serde(default)]
+    pub facets: bool,
+}
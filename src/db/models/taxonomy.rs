@@ -0,0 +1,204 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Whether the exercise mainly trains the muscle or only assists with it.
+#[derive(diesel_derive_enum::DbEnum, Debug, Serialize, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::MuscleRoles"]
+pub enum MuscleRoles {
+    Primary,
+    Secondary,
+}
+
+// muscles (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     #[max_length = 50]
+//     name -> Varchar,
+//     #[max_length = 60]
+//     slug -> Varchar,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable)]
+#[diesel(table_name = crate::schema::muscles, check_for_backend(diesel::pg::Pg))]
+pub struct Muscle {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    // Fields
+    pub name: String,
+    pub slug: String,
+}
+
+// equipment (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     #[max_length = 50]
+//     name -> Varchar,
+//     #[max_length = 60]
+//     slug -> Varchar,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable)]
+#[diesel(table_name = crate::schema::equipment, check_for_backend(diesel::pg::Pg))]
+pub struct Equipment {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    // Fields
+    pub name: String,
+    pub slug: String,
+}
+
+/// Body to create or rename a muscle or a piece of equipment.
+#[derive(Deserialize, Debug)]
+pub struct TaxonomyName {
+    pub name: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CatalogMuscle {
+    #[serde(flatten)]
+    pub muscle: Muscle,
+    pub role: MuscleRoles,
+}
+
+/// Replaces the muscles of a catalog entry. A muscle can't be both primary and secondary.
+#[derive(Deserialize, Debug)]
+pub struct SetCatalogMuscles {
+    pub primary_ids: Vec<uuid::Uuid>,
+    #[serde(default)]
+    pub secondary_ids: Vec<uuid::Uuid>,
+}
+
+/// Replaces the equipment a catalog entry needs, or the equipment a client has.
+#[derive(Deserialize, Debug)]
+pub struct SetEquipment {
+    pub equipment_ids: Vec<uuid::Uuid>,
+}
+
+/// Sets done for a muscle. Secondary sets count half towards `weighted_sets`.
+#[derive(Serialize, Debug, Clone)]
+pub struct MuscleVolume {
+    #[serde(flatten)]
+    pub muscle: Muscle,
+    pub exercises: i64,
+    pub primary_sets: i64,
+    pub secondary_sets: i64,
+    pub weighted_sets: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct WeekVolume {
+    pub week: i32,
+    pub muscles: Vec<MuscleVolume>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ProgramVolume {
+    pub total: Vec<MuscleVolume>,
+    pub weeks: Vec<WeekVolume>,
+}
+
+/// Sums the sets of every exercise and muscle pair, most trained muscles first.
+pub fn summarize_volume(rows: &[(Muscle, MuscleRoles, i32)]) -> Vec<MuscleVolume> {
+    let mut res: Vec<MuscleVolume> = vec![];
+
+    for (muscle, role, sets) in rows {
+        let idx = match res.iter().position(|v| v.muscle.id == muscle.id) {
+            Some(idx) => idx,
+            None => {
+                res.push(MuscleVolume {
+                    muscle: muscle.clone(),
+                    exercises: 0,
+                    primary_sets: 0,
+                    secondary_sets: 0,
+                    weighted_sets: 0.0,
+                });
+                res.len() - 1
+            }
+        };
+
+        let volume = &mut res[idx];
+        let sets = i64::from((*sets).max(0));
+
+        volume.exercises += 1;
+
+        match role {
+            MuscleRoles::Primary => volume.primary_sets += sets,
+            MuscleRoles::Secondary => volume.secondary_sets += sets,
+        }
+
+        volume.weighted_sets = volume.primary_sets as f64 + volume.secondary_sets as f64 / 2.0;
+    }
+
+    res.sort_by(|a, b| {
+        b.weighted_sets
+            .total_cmp(&a.weighted_sets)
+            .then_with(|| a.muscle.name.cmp(&b.muscle.name))
+    });
+
+    res
+}
+
+/// Volume of the whole program and of each of its weeks, from the rows of `load_volume_rows`.
+pub fn summarize_program_volume(rows: Vec<(i32, Muscle, MuscleRoles, i32)>) -> ProgramVolume {
+    let mut weeks: Vec<i32> = rows.iter().map(|(week, ..)| *week).collect();
+    weeks.sort();
+    weeks.dedup();
+
+    let weeks = weeks
+        .into_iter()
+        .map(|week| WeekVolume {
+            week,
+            muscles: summarize_volume(
+                &rows
+                    .iter()
+                    .filter(|(w, ..)| *w == week)
+                    .map(|(_, muscle, role, sets)| (muscle.clone(), *role, *sets))
+                    .collect::<Vec<_>>(),
+            ),
+        })
+        .collect();
+
+    let total = summarize_volume(
+        &rows
+            .into_iter()
+            .map(|(_, muscle, role, sets)| (muscle, role, sets))
+            .collect::<Vec<_>>(),
+    );
+
+    ProgramVolume { total, weeks }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::name_slug;
+
+    #[test]
+    fn test_summarize_volume() {
+        let muscle = |name: &str| Muscle {
+            id: uuid::Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            name: name.into(),
+            slug: name_slug(name),
+        };
+        let (chest, triceps) = (muscle("Chest"), muscle("Triceps"));
+
+        let res = summarize_volume(&[
+            (chest.clone(), MuscleRoles::Primary, 3),
+            (triceps.clone(), MuscleRoles::Secondary, 3),
+            (triceps.clone(), MuscleRoles::Primary, 2),
+            (chest.clone(), MuscleRoles::Secondary, 4),
+        ]);
+
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].muscle.slug, "chest");
+        assert_eq!((res[0].primary_sets, res[0].secondary_sets), (3, 4));
+        assert_eq!(res[0].weighted_sets, 5.0);
+        assert_eq!(res[1].exercises, 2);
+        assert_eq!(res[1].weighted_sets, 3.5);
+    }
+}
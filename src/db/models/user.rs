@@ -97,6 +97,7 @@ pub struct User {
     pub training_specializations: String,
     pub goals: String,
     pub weight: i32,
+    pub timezone: String,
 }
 
 #[derive(Insertable, Deserialize, Debug, AsChangeset)]
@@ -125,6 +126,10 @@ pub struct NewUser {
     // Client
     pub goals: String,
     pub weight: i32,
+
+    /// IANA timezone, e.g. `America/Chicago`. Left unchanged when missing.
+    #[serde(default)]
+    pub timezone: Option<String>,
 }
 
 #[derive(Debug, Clone, Queryable, Selectable, Serialize)]
@@ -1,11 +1,23 @@
-use super::{exercise::Exercise, IntensityChoices};
+use super::{exercise::ExerciseWithCatalog, workout_block::WorkoutBlock, IntensityChoices};
 #[allow(unused_imports)]
 use crate::db::models::{program::Program, user::User};
-use diesel::prelude::*;
+use diesel::{associations::BelongsTo, prelude::*};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+#[derive(
+    Debug,
+    Clone,
+    Queryable,
+    Selectable,
+    Insertable,
+    AsChangeset,
+    Serialize,
+    Deserialize,
+    Identifiable,
+    Associations,
+)]
 #[diesel(table_name = crate::schema::workouts, check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
 #[diesel(belongs_to(Program))]
 #[diesel(belongs_to(User, foreign_key=owner_id))]
 pub struct Workout {
@@ -30,13 +42,42 @@ pub struct Workout {
     pub template: bool,
     pub slug: String,
     pub complete: bool,
+    pub scheduled_date: Option<chrono::NaiveDate>,
+    #[serde(default)]
+    pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct WorkoutWithExercises {
     #[serde(flatten)]
     pub workout: Workout,
-    pub exercises: Vec<Exercise>,
+    // Defaulted so revisions from before blocks still load
+    #[serde(default)]
+    pub blocks: Vec<WorkoutBlock>,
+    pub exercises: Vec<ExerciseWithCatalog>,
+}
+
+impl BelongsTo<Program> for WorkoutWithExercises {
+    type ForeignKey = uuid::Uuid;
+    type ForeignKeyColumn = crate::schema::workouts::program_id;
+
+    fn foreign_key(&self) -> Option<&uuid::Uuid> {
+        self.workout.program_id.as_ref()
+    }
+
+    fn foreign_key_column() -> Self::ForeignKeyColumn {
+        crate::schema::workouts::program_id
+    }
+}
+
+/// A workout resolved to the date it's planned for, based on its program's schedule.
+#[derive(Serialize)]
+pub struct ScheduledWorkout {
+    #[serde(flatten)]
+    pub workout: Workout,
+    pub date: chrono::NaiveDate,
 }
 
 #[derive(Insertable, Deserialize, Debug, AsChangeset)]
@@ -54,6 +95,7 @@ pub struct PatchWorkout {
     pub video: Option<String>,
     pub template: Option<bool>,
     pub complete: Option<bool>,
+    pub scheduled_date: Option<chrono::NaiveDate>,
 }
 
 #[derive(Insertable, Deserialize, Debug, AsChangeset)]
@@ -0,0 +1,34 @@
+use super::workout::Workout;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    diesel_derive_enum::DbEnum, Debug, Serialize, Clone, Copy, Deserialize, PartialEq, Eq, Hash,
+)]
+#[ExistingTypePath = "crate::schema::sql_types::WorkoutAlertKinds"]
+pub enum WorkoutAlertKinds {
+    Reminder,
+    Missed,
+}
+
+// workout_alerts (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     workout_id -> Uuid,
+//     kind -> WorkoutAlertKinds,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+#[diesel(table_name = crate::schema::workout_alerts, check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(Workout))]
+pub struct WorkoutAlert {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    // Relationships
+    pub workout_id: uuid::Uuid,
+
+    // Fields
+    pub kind: WorkoutAlertKinds,
+}
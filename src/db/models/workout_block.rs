@@ -0,0 +1,151 @@
+use super::workout::Workout;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    diesel_derive_enum::DbEnum, Debug, Serialize, Clone, Copy, Deserialize, PartialEq, Eq, Default,
+)]
+#[ExistingTypePath = "crate::schema::sql_types::BlockTypes"]
+pub enum BlockTypes {
+    #[default]
+    Straight,
+    Superset,
+    Circuit,
+    Emom,
+    Interval,
+}
+
+// workout_blocks (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     workout_id -> Uuid,
+//     block_type -> BlockTypes,
+//     #[max_length = 50]
+//     name -> Varchar,
+//     sequence -> Int4,
+//     rounds -> Int4,
+//     work_seconds -> Nullable<Int4>,
+//     rest_seconds -> Nullable<Int4>,
+//     round_rest_seconds -> Nullable<Int4>,
+// }
+
+/// Exercises of a workout that are done together, e.g. an A1/A2 superset for 3 rounds or a
+/// 40s on / 20s off interval block. The exercises point at their block and keep their sequence
+/// inside of it.
+#[derive(
+    Debug,
+    Clone,
+    Queryable,
+    Selectable,
+    Insertable,
+    AsChangeset,
+    Serialize,
+    Deserialize,
+    Identifiable,
+    Associations,
+)]
+#[diesel(table_name = crate::schema::workout_blocks, check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+#[diesel(belongs_to(Workout))]
+pub struct WorkoutBlock {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    // Relationships
+    pub workout_id: uuid::Uuid,
+
+    // Fields
+    pub block_type: BlockTypes,
+    pub name: String,
+    pub sequence: i32,
+    pub rounds: i32,
+    /// Length of an interval, or of each EMOM slot
+    pub work_seconds: Option<i32>,
+    /// Rest between the exercises of a round, or after each interval
+    pub rest_seconds: Option<i32>,
+    pub round_rest_seconds: Option<i32>,
+}
+
+#[derive(Insertable, AsChangeset, Deserialize, Debug)]
+#[diesel(table_name = crate::schema::workout_blocks)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewWorkoutBlock {
+    #[serde(default)]
+    pub block_type: BlockTypes,
+    #[serde(default)]
+    pub name: String,
+    pub sequence: i32,
+    #[serde(default = "default_rounds")]
+    pub rounds: i32,
+    #[serde(default)]
+    pub work_seconds: Option<i32>,
+    #[serde(default)]
+    pub rest_seconds: Option<i32>,
+    #[serde(default)]
+    pub round_rest_seconds: Option<i32>,
+}
+
+fn default_rounds() -> i32 {
+    1
+}
+
+pub const MAX_BLOCK_ROUNDS: i32 = 100;
+
+impl NewWorkoutBlock {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.chars().count() > 50 {
+            return Err("name can be at most 50 characters long.".into());
+        }
+
+        if self.sequence < 0 {
+            return Err("sequence can't be negative.".into());
+        }
+
+        if !(1..=MAX_BLOCK_ROUNDS).contains(&self.rounds) {
+            return Err(format!("rounds must be between 1 and {MAX_BLOCK_ROUNDS}."));
+        }
+
+        if self.work_seconds.is_some_and(|s| s < 1) {
+            return Err("work_seconds must be at least 1.".into());
+        }
+
+        if self.rest_seconds.is_some_and(|s| s < 0)
+            || self.round_rest_seconds.is_some_and(|s| s < 0)
+        {
+            return Err("Rest times can't be negative.".into());
+        }
+
+        if matches!(self.block_type, BlockTypes::Emom | BlockTypes::Interval)
+            && self.work_seconds.is_none()
+        {
+            return Err("EMOM and interval blocks need work_seconds.".into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_block() {
+        let block = |block_type, rounds, work_seconds| NewWorkoutBlock {
+            block_type,
+            name: "A".into(),
+            sequence: 0,
+            rounds,
+            work_seconds,
+            rest_seconds: Some(20),
+            round_rest_seconds: None,
+        };
+
+        assert!(block(BlockTypes::Superset, 3, None).validate().is_ok());
+        assert!(block(BlockTypes::Interval, 8, Some(40)).validate().is_ok());
+        assert!(block(BlockTypes::Interval, 8, None).validate().is_err());
+        assert!(block(BlockTypes::Emom, 10, Some(0)).validate().is_err());
+        assert!(block(BlockTypes::Circuit, 0, None).validate().is_err());
+    }
+}
@@ -1,6 +1,10 @@
 use super::workout::Workout;
 #[allow(unused_imports)]
 use crate::db::models::{program::Program, user::User};
+use crate::util::health_units::{
+    parse_distance_meters, parse_duration_secs, parse_energy_kcal, parse_heart_rate_bpm,
+    Measurement,
+};
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -18,25 +22,120 @@ pub struct WorkoutData {
     pub hk_workout_id: String,
     pub hk_location_type: String,
     pub hk_workout_activity_type: String,
-    pub hk_workout_duration_secs: String,
+    pub hk_workout_duration_secs: Option<f64>,
     pub hk_workout_start_date: Option<chrono::DateTime<chrono::Utc>>,
     pub hk_workout_end_date: Option<chrono::DateTime<chrono::Utc>>,
-    pub hk_workout_distance: String,
-    pub hk_workout_avg_heart_rate: String,
-    pub hk_workout_active_energy_burned: String,
+    pub hk_workout_distance_meters: Option<f64>,
+    pub hk_workout_avg_heart_rate_bpm: Option<f64>,
+    pub hk_workout_active_energy_burned_kcal: Option<f64>,
 }
 
-#[derive(Insertable, Deserialize, Debug, AsChangeset)]
-#[diesel(table_name = crate::schema::workout_data)]
+/// The measurements can be numbers in the unit of their column or strings with a unit, see
+/// `health_units`. They are stored normalized.
+#[derive(Deserialize, Debug)]
 pub struct NewWorkoutData {
     // pub workout_id: Option<uuid::Uuid>,
     pub hk_workout_id: String,
     pub hk_location_type: String,
     pub hk_workout_activity_type: String,
-    pub hk_workout_duration_secs: String,
+    pub hk_workout_duration_secs: Option<Measurement>,
+    pub hk_workout_start_date: Option<chrono::DateTime<chrono::Utc>>,
+    pub hk_workout_end_date: Option<chrono::DateTime<chrono::Utc>>,
+    /// Meters unless a unit is given
+    pub hk_workout_distance: Option<Measurement>,
+    /// Beats per minute
+    pub hk_workout_avg_heart_rate: Option<Measurement>,
+    /// Kilocalories unless a unit is given
+    pub hk_workout_active_energy_burned: Option<Measurement>,
+}
+
+#[derive(Insertable, Debug, PartialEq)]
+#[diesel(table_name = crate::schema::workout_data)]
+pub struct NormalizedWorkoutData {
+    pub hk_workout_id: String,
+    pub hk_location_type: String,
+    pub hk_workout_activity_type: String,
+    pub hk_workout_duration_secs: Option<f64>,
     pub hk_workout_start_date: Option<chrono::DateTime<chrono::Utc>>,
     pub hk_workout_end_date: Option<chrono::DateTime<chrono::Utc>>,
-    pub hk_workout_distance: String,
-    pub hk_workout_avg_heart_rate: String,
-    pub hk_workout_active_energy_burned: String,
+    pub hk_workout_distance_meters: Option<f64>,
+    pub hk_workout_avg_heart_rate_bpm: Option<f64>,
+    pub hk_workout_active_energy_burned_kcal: Option<f64>,
+}
+
+/// Two days, longer workouts are a clock or unit mistake.
+const MAX_DURATION_SECS: f64 = 172_800.0;
+const MAX_DISTANCE_METERS: f64 = 1_000_000.0;
+const HEART_RATE_BPM: std::ops::RangeInclusive<f64> = 20.0..=250.0;
+const MAX_ENERGY_KCAL: f64 = 20_000.0;
+
+impl NewWorkoutData {
+    /// Converts the measurements to the units of their columns and checks they are plausible.
+    pub fn normalize(self) -> Result<NormalizedWorkoutData, String> {
+        fn normalize_field(
+            value: &Option<Measurement>,
+            field: &str,
+            parse: fn(&str) -> Option<f64>,
+        ) -> Result<Option<f64>, String> {
+            match value {
+                Some(measurement) => measurement.normalize(field, parse),
+                None => Ok(None),
+            }
+        }
+
+        let duration = normalize_field(
+            &self.hk_workout_duration_secs,
+            "hk_workout_duration_secs",
+            parse_duration_secs,
+        )?;
+        let distance = normalize_field(
+            &self.hk_workout_distance,
+            "hk_workout_distance",
+            parse_distance_meters,
+        )?;
+        let heart_rate = normalize_field(
+            &self.hk_workout_avg_heart_rate,
+            "hk_workout_avg_heart_rate",
+            parse_heart_rate_bpm,
+        )?;
+        let energy = normalize_field(
+            &self.hk_workout_active_energy_burned,
+            "hk_workout_active_energy_burned",
+            parse_energy_kcal,
+        )?;
+
+        if duration.is_some_and(|d| !(0.0..=MAX_DURATION_SECS).contains(&d)) {
+            return Err("hk_workout_duration_secs must be between 0 and 48 hours.".into());
+        }
+
+        if distance.is_some_and(|d| !(0.0..=MAX_DISTANCE_METERS).contains(&d)) {
+            return Err("hk_workout_distance must be between 0 and 1000 km.".into());
+        }
+
+        if heart_rate.is_some_and(|hr| !HEART_RATE_BPM.contains(&hr)) {
+            return Err("hk_workout_avg_heart_rate must be between 20 and 250 bpm.".into());
+        }
+
+        if energy.is_some_and(|e| !(0.0..=MAX_ENERGY_KCAL).contains(&e)) {
+            return Err("hk_workout_active_energy_burned must be between 0 and 20000 kcal.".into());
+        }
+
+        if let (Some(start), Some(end)) = (self.hk_workout_start_date, self.hk_workout_end_date) {
+            if start > end {
+                return Err("hk_workout_start_date must not be after hk_workout_end_date.".into());
+            }
+        }
+
+        Ok(NormalizedWorkoutData {
+            hk_workout_id: self.hk_workout_id,
+            hk_location_type: self.hk_location_type,
+            hk_workout_activity_type: self.hk_workout_activity_type,
+            hk_workout_duration_secs: duration,
+            hk_workout_start_date: self.hk_workout_start_date,
+            hk_workout_end_date: self.hk_workout_end_date,
+            hk_workout_distance_meters: distance,
+            hk_workout_avg_heart_rate_bpm: heart_rate,
+            hk_workout_active_energy_burned_kcal: energy,
+        })
+    }
 }
@@ -0,0 +1,195 @@
+use super::{user::PublicUser, workout::Workout};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Serialize, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::DiscomfortKinds"]
+pub enum DiscomfortKinds {
+    Discomfort,
+    Pain,
+}
+
+// workout_feedback (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     updated_at -> Timestamptz,
+//     workout_id -> Uuid,
+//     user_id -> Uuid,
+//     session_rpe -> Int4,
+//     enjoyment -> Int4,
+//     notes -> Text,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+#[diesel(table_name = crate::schema::workout_feedback, check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(Workout))]
+pub struct WorkoutFeedback {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+
+    // Relationships
+    pub workout_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    // Fields
+    /// 1 (very easy) to 10 (maximal effort)
+    pub session_rpe: i32,
+    /// 1 to 5 stars
+    pub enjoyment: i32,
+    pub notes: String,
+}
+
+// exercise_discomfort (id) {
+//     id -> Uuid,
+//     feedback_id -> Uuid,
+//     exercise_id -> Uuid,
+//     kind -> DiscomfortKinds,
+//     #[max_length = 255]
+//     note -> Varchar,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+#[diesel(table_name = crate::schema::exercise_discomfort, check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(WorkoutFeedback, foreign_key = feedback_id))]
+pub struct ExerciseDiscomfort {
+    pub id: uuid::Uuid,
+
+    // Relationships
+    pub feedback_id: uuid::Uuid,
+    pub exercise_id: uuid::Uuid,
+
+    // Fields
+    pub kind: DiscomfortKinds,
+    pub note: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NewExerciseDiscomfort {
+    pub exercise_id: uuid::Uuid,
+    pub kind: DiscomfortKinds,
+    #[serde(default)]
+    pub note: String,
+}
+
+/// Submitting feedback for a workout again replaces the earlier feedback.
+#[derive(Deserialize, Debug)]
+pub struct NewWorkoutFeedback {
+    pub session_rpe: i32,
+    pub enjoyment: i32,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub discomfort: Vec<NewExerciseDiscomfort>,
+}
+
+impl NewWorkoutFeedback {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=10).contains(&self.session_rpe) {
+            return Err("session_rpe must be between 1 and 10.".into());
+        }
+
+        if !(1..=5).contains(&self.enjoyment) {
+            return Err("enjoyment must be between 1 and 5.".into());
+        }
+
+        if self.discomfort.iter().any(|d| d.note.chars().count() > 255) {
+            return Err("Discomfort notes can be at most 255 characters long.".into());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+pub struct FeedbackWithDiscomfort {
+    #[serde(flatten)]
+    pub feedback: WorkoutFeedback,
+    pub discomfort: Vec<ExerciseDiscomfort>,
+    pub user: PublicUser,
+}
+
+/// Average session RPE from which a workout is considered too hard.
+pub const TOO_HARD_SESSION_RPE: f64 = 8.5;
+
+/// Feedback of one workout of a program, averaged over everyone who rated it.
+#[derive(Serialize, Debug)]
+pub struct WorkoutFeedbackSummary {
+    pub workout_id: uuid::Uuid,
+    pub name: String,
+    pub week: i32,
+    pub sequence: i32,
+    pub responses: usize,
+    pub avg_session_rpe: Option<f64>,
+    pub avg_enjoyment: Option<f64>,
+    pub pain_flags: usize,
+    pub discomfort_flags: usize,
+    pub too_hard: bool,
+}
+
+/// Summarizes the feedback of each workout, `feedback` is grouped by workout in the same order as
+/// `workouts` and holds the discomfort flags of each response.
+pub fn summarize_feedback(
+    workouts: &[Workout],
+    feedback: &[Vec<(WorkoutFeedback, Vec<ExerciseDiscomfort>)>],
+) -> Vec<WorkoutFeedbackSummary> {
+    workouts
+        .iter()
+        .zip(feedback)
+        .map(|(workout, responses)| {
+            let count = responses.len();
+            let avg = |value: fn(&WorkoutFeedback) -> i32| {
+                (count > 0).then(|| {
+                    let sum: i32 = responses.iter().map(|(f, _)| value(f)).sum();
+                    (f64::from(sum) / count as f64 * 10.0).round() / 10.0
+                })
+            };
+
+            let flags = responses.iter().flat_map(|(_, d)| d);
+            let pain_flags = flags
+                .clone()
+                .filter(|d| d.kind == DiscomfortKinds::Pain)
+                .count();
+            let discomfort_flags = flags
+                .filter(|d| d.kind == DiscomfortKinds::Discomfort)
+                .count();
+
+            let avg_session_rpe = avg(|f| f.session_rpe);
+
+            WorkoutFeedbackSummary {
+                workout_id: workout.id,
+                name: workout.name.clone(),
+                week: workout.week,
+                sequence: workout.sequence,
+                responses: count,
+                avg_session_rpe,
+                avg_enjoyment: avg(|f| f.enjoyment),
+                pain_flags,
+                discomfort_flags,
+                too_hard: avg_session_rpe.is_some_and(|rpe| rpe >= TOO_HARD_SESSION_RPE),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_feedback() {
+        let feedback = |session_rpe, enjoyment| NewWorkoutFeedback {
+            session_rpe,
+            enjoyment,
+            notes: String::new(),
+            discomfort: vec![],
+        };
+
+        assert!(feedback(1, 1).validate().is_ok());
+        assert!(feedback(10, 5).validate().is_ok());
+        assert!(feedback(0, 3).validate().is_err());
+        assert!(feedback(11, 3).validate().is_err());
+        assert!(feedback(7, 6).validate().is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use super::{
+    exercise::{Exercise, ExerciseWithCatalog},
+    personal_record::PersonalRecord,
+    workout::Workout,
+};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// workout_sessions (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     workout_id -> Uuid,
+//     user_id -> Uuid,
+//     started_at -> Timestamptz,
+//     finished_at -> Nullable<Timestamptz>,
+//     notes -> Text,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+#[diesel(table_name = crate::schema::workout_sessions, check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(Workout))]
+pub struct WorkoutSession {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    // Relationships
+    pub workout_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    // Fields
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub notes: String,
+}
+
+// set_logs (id) {
+//     id -> Uuid,
+//     created_at -> Timestamptz,
+//     session_id -> Uuid,
+//     exercise_id -> Uuid,
+//     set_number -> Int4,
+//     reps -> Int4,
+//     load -> Nullable<Float8>,
+//     #[max_length = 10]
+//     load_unit -> Varchar,
+//     rpe -> Nullable<Float8>,
+//     notes -> Text,
+//     duration_seconds -> Nullable<Int4>,
+// }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Identifiable, Associations)]
+#[diesel(table_name = crate::schema::set_logs, check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(WorkoutSession, foreign_key = session_id))]
+#[diesel(belongs_to(Exercise))]
+pub struct SetLog {
+    // Meta
+    pub id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    // Relationships
+    pub session_id: uuid::Uuid,
+    pub exercise_id: uuid::Uuid,
+
+    // Fields
+    pub set_number: i32,
+    pub reps: i32,
+    pub load: Option<f64>,
+    pub load_unit: String,
+    pub rpe: Option<f64>,
+    pub notes: String,
+    /// For timed sets like planks or carries
+    pub duration_seconds: Option<i32>,
+}
+
+/// Logging a set number that was already logged for the exercise replaces it. Without a
+/// `set_number` the set is logged after the last one.
+#[derive(Deserialize, Debug)]
+pub struct NewSetLog {
+    pub exercise_id: uuid::Uuid,
+    pub set_number: Option<i32>,
+    pub reps: i32,
+    pub load: Option<f64>,
+    /// Falls back to the unit the exercise is prescribed in
+    pub load_unit: Option<String>,
+    pub rpe: Option<f64>,
+    #[serde(default)]
+    pub notes: String,
+    pub duration_seconds: Option<i32>,
+}
+
+#[derive(AsChangeset, Deserialize, Debug)]
+#[diesel(table_name = crate::schema::set_logs)]
+pub struct PatchSetLog {
+    pub reps: Option<i32>,
+    pub load: Option<f64>,
+    pub load_unit: Option<String>,
+    pub rpe: Option<f64>,
+    pub notes: Option<String>,
+    pub duration_seconds: Option<i32>,
+}
+
+impl PatchSetLog {
+    pub fn is_empty(&self) -> bool {
+        self.reps.is_none()
+            && self.load.is_none()
+            && self.load_unit.is_none()
+            && self.rpe.is_none()
+            && self.notes.is_none()
+            && self.duration_seconds.is_none()
+    }
+}
+
+/// A logged set and the personal records it set.
+#[derive(Serialize)]
+pub struct SetLogWithRecords {
+    #[serde(flatten)]
+    pub set: SetLog,
+    pub records: Vec<PersonalRecord>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct FinishSession {
+    pub notes: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SessionWithSets {
+    #[serde(flatten)]
+    pub session: WorkoutSession,
+    pub sets: Vec<SetLog>,
+}
+
+/// An exercise as it was prescribed next to the sets that were actually logged for it.
+#[derive(Serialize)]
+pub struct ExerciseComparison {
+    pub prescribed: ExerciseWithCatalog,
+    pub actual: Vec<SetLog>,
+}
+
+#[derive(Serialize)]
+pub struct SessionComparison {
+    #[serde(flatten)]
+    pub session: WorkoutSession,
+    pub workout: Workout,
+    pub exercises: Vec<ExerciseComparison>,
+}
+
+/// Checks the logged numbers, `None` values are left alone.
+pub fn validate_set_values(
+    reps: Option<i32>,
+    rpe: Option<f64>,
+    load: Option<f64>,
+    load_unit: Option<&str>,
+    duration_seconds: Option<i32>,
+) -> Result<(), String> {
+    if reps.is_some_and(|r| r < 0) {
+        return Err("reps can't be negative.".into());
+    }
+
+    if rpe.is_some_and(|r| !(1.0..=10.0).contains(&r)) {
+        return Err("rpe must be between 1 and 10.".into());
+    }
+
+    if load.is_some_and(|l| l < 0.0 || !l.is_finite()) {
+        return Err("load can't be negative.".into());
+    }
+
+    if load_unit.is_some_and(|u| u.chars().count() > 10) {
+        return Err("load_unit can be at most 10 characters long.".into());
+    }
+
+    if duration_seconds.is_some_and(|d| d < 0) {
+        return Err("duration_seconds can't be negative.".into());
+    }
+
+    Ok(())
+}
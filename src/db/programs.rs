@@ -0,0 +1,611 @@
+use super::{
+    catalog::{resolve_catalog_entry, restore_catalog_entry},
+    models::{
+        exercise::{Exercise, ExerciseWithCatalog},
+        program::{Program, ProgramWithWorkouts},
+        program_document::{ProgramDocument, WorkoutDocument},
+        program_progress::{NewProgramProgress, ProgramProgress},
+        program_revision::ProgramRevision,
+        workout::{ScheduledWorkout, Workout, WorkoutWithExercises},
+        workout_block::WorkoutBlock,
+    },
+    tags::{copy_program_tags, copy_workout_tags},
+    workouts::with_exercises,
+    DbConnection,
+};
+use crate::util::{calendar::plan_workout_dates, prescription::Prescription, random_slug};
+use diesel::{insert_into, pg::upsert::excluded, prelude::*};
+use std::collections::HashMap;
+
+/// Copies a program along with all of its workouts and their exercises.
+///
+/// Every copied row gets a fresh slug, the copies are never templates and all `complete` flags
+/// are reset. Archived workouts and exercises are left out, tags are copied along. This does not
+/// open a transaction itself, callers should wrap it in one so a failure halfway through doesn't
+/// leave a partial program behind.
+pub fn deep_copy_program(
+    conn: &mut DbConnection,
+    source_program_id: uuid::Uuid,
+    new_owner_id: uuid::Uuid,
+    new_client_id: Option<uuid::Uuid>,
+) -> QueryResult<Program> {
+    use crate::schema::{
+        exercises::dsl as e_dsl, programs::dsl as p_dsl, workout_blocks::dsl as b_dsl,
+        workouts::dsl as w_dsl,
+    };
+
+    let source: Program = p_dsl::programs
+        .filter(p_dsl::id.eq(source_program_id))
+        .select(Program::as_select())
+        .first(conn)?;
+
+    let new_program: Program = insert_into(p_dsl::programs)
+        .values((
+            p_dsl::owner_id.eq(new_owner_id),
+            p_dsl::name.eq(&source.name),
+            p_dsl::description.eq(&source.description),
+            p_dsl::duration.eq(&source.duration),
+            p_dsl::focus_areas.eq(&source.focus_areas),
+            p_dsl::target_audience.eq(&source.target_audience),
+            p_dsl::program_image.eq(&source.program_image),
+            p_dsl::intensity.eq(&source.intensity),
+            p_dsl::slug.eq(random_slug(&source.name)),
+            p_dsl::template.eq(false),
+            p_dsl::client_id.eq(new_client_id),
+            p_dsl::active.eq(false),
+            p_dsl::complete.eq(false),
+            p_dsl::training_days.eq(&source.training_days),
+            p_dsl::level.eq(&source.level),
+        ))
+        .returning(Program::as_returning())
+        .get_result(conn)?;
+
+    copy_program_tags(conn, source.id, new_program.id)?;
+
+    let source_workouts: Vec<Workout> = Workout::belonging_to(&source)
+        .filter(w_dsl::archived_at.is_null())
+        .order((w_dsl::week.asc(), w_dsl::sequence.asc()))
+        .select(Workout::as_select())
+        .load(conn)?;
+
+    for WorkoutWithExercises {
+        workout,
+        blocks,
+        exercises,
+    } in with_exercises(conn, source_workouts)?
+    {
+        let new_workout_id: uuid::Uuid = insert_into(w_dsl::workouts)
+            .values((
+                w_dsl::program_id.eq(new_program.id),
+                w_dsl::owner_id.eq(new_owner_id),
+                w_dsl::name.eq(&workout.name),
+                w_dsl::description.eq(&workout.description),
+                w_dsl::duration.eq(&workout.duration),
+                w_dsl::sequence.eq(workout.sequence),
+                w_dsl::week.eq(workout.week),
+                w_dsl::intensity.eq(&workout.intensity),
+                w_dsl::workout_type.eq(&workout.workout_type),
+                w_dsl::equipment_needed.eq(&workout.equipment_needed),
+                w_dsl::image.eq(&workout.image),
+                w_dsl::video.eq(&workout.video),
+                w_dsl::template.eq(false),
+                w_dsl::slug.eq(random_slug(&workout.name)),
+                w_dsl::complete.eq(false),
+            ))
+            .returning(w_dsl::id)
+            .get_result(conn)?;
+
+        copy_workout_tags(conn, workout.id, new_workout_id)?;
+
+        let mut new_block_ids = HashMap::new();
+
+        for block in blocks {
+            let new_block_id: uuid::Uuid = insert_into(b_dsl::workout_blocks)
+                .values((
+                    b_dsl::workout_id.eq(new_workout_id),
+                    b_dsl::block_type.eq(block.block_type),
+                    b_dsl::name.eq(&block.name),
+                    b_dsl::sequence.eq(block.sequence),
+                    b_dsl::rounds.eq(block.rounds),
+                    b_dsl::work_seconds.eq(block.work_seconds),
+                    b_dsl::rest_seconds.eq(block.rest_seconds),
+                    b_dsl::round_rest_seconds.eq(block.round_rest_seconds),
+                ))
+                .returning(b_dsl::id)
+                .get_result(conn)?;
+
+            new_block_ids.insert(block.id, new_block_id);
+        }
+
+        for ExerciseWithCatalog { exercise, catalog } in exercises {
+            insert_into(e_dsl::exercises)
+                .values((
+                    e_dsl::workout_id.eq(new_workout_id),
+                    e_dsl::owner_id.eq(new_owner_id),
+                    e_dsl::catalog_id.eq(exercise.catalog_id),
+                    e_dsl::block_id.eq(exercise
+                        .block_id
+                        .and_then(|b| new_block_ids.get(&b).copied())),
+                    e_dsl::duration.eq(&exercise.duration),
+                    e_dsl::reps.eq(&exercise.reps),
+                    e_dsl::sets.eq(exercise.sets),
+                    e_dsl::rest_period.eq(&exercise.rest_period),
+                    e_dsl::intensity.eq(&exercise.intensity),
+                    e_dsl::sequence.eq(exercise.sequence),
+                    e_dsl::slug.eq(random_slug(&catalog.name)),
+                    e_dsl::load.eq(exercise.load),
+                    e_dsl::load_unit.eq(&exercise.load_unit),
+                    exercise.prescription(),
+                ))
+                .execute(conn)?;
+        }
+    }
+
+    Ok(new_program)
+}
+
+/// Loads the program's workouts and resolves each of them to a calendar date.
+///
+/// Returns `None` if the program doesn't have a start date yet. Workouts that can't be placed on
+/// the calendar are left out, see `plan_workout_dates`.
+pub fn load_program_schedule(
+    conn: &mut DbConnection,
+    program: &Program,
+) -> QueryResult<Option<Vec<ScheduledWorkout>>> {
+    use crate::schema::workouts::dsl as w_dsl;
+
+    let Some(start_date) = program.start_date else {
+        return Ok(None);
+    };
+
+    let program_workouts: Vec<Workout> = Workout::belonging_to(program)
+        .filter(w_dsl::archived_at.is_null())
+        .order((w_dsl::week.asc(), w_dsl::sequence.asc()))
+        .select(Workout::as_select())
+        .load(conn)?;
+
+    let dates = plan_workout_dates(start_date, &program.training_days, &program_workouts);
+
+    let scheduled = program_workouts
+        .into_iter()
+        .zip(dates)
+        .filter_map(|(workout, date)| {
+            Some(ScheduledWorkout {
+                workout,
+                date: date?,
+            })
+        })
+        .collect();
+
+    Ok(Some(scheduled))
+}
+
+/// Loads the workouts (ordered by week and sequence) and their exercises for a single program,
+/// archived workouts and exercises are left out.
+pub fn load_program_with_workouts(
+    program: Program,
+    conn: &mut DbConnection,
+) -> QueryResult<ProgramWithWorkouts> {
+    use crate::schema::workouts::dsl as workout_dsl;
+
+    let workouts_belonging_to_program = Workout::belonging_to(&program)
+        .filter(workout_dsl::archived_at.is_null())
+        .order((workout_dsl::week.asc(), workout_dsl::sequence.asc()))
+        .select(Workout::as_select())
+        .load::<Workout>(conn)?;
+
+    Ok(ProgramWithWorkouts {
+        program,
+        workouts: with_exercises(conn, workouts_belonging_to_program)?,
+    })
+}
+
+/// Stores the current state of the program tree as a new revision.
+///
+/// Nothing is stored if the program is identical to its latest revision.
+pub fn record_program_revision(
+    conn: &mut DbConnection,
+    program_id: uuid::Uuid,
+    author_id: uuid::Uuid,
+) -> QueryResult<Option<ProgramRevision>> {
+    use crate::schema::{program_revisions::dsl as r_dsl, programs::dsl as p_dsl};
+
+    let program: Program = p_dsl::programs
+        .filter(p_dsl::id.eq(program_id))
+        .select(Program::as_select())
+        .first(conn)?;
+
+    let tree = load_program_with_workouts(program, conn)?;
+
+    let snapshot = serde_json::to_value(&tree)
+        .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+
+    let latest: Option<ProgramRevision> = r_dsl::program_revisions
+        .filter(r_dsl::program_id.eq(program_id))
+        .order(r_dsl::revision.desc())
+        .select(ProgramRevision::as_select())
+        .first(conn)
+        .optional()?;
+
+    if latest.as_ref().is_some_and(|rev| rev.snapshot == snapshot) {
+        return Ok(None);
+    }
+
+    let next_revision = latest.map(|rev| rev.revision + 1).unwrap_or(1);
+
+    let res = insert_into(r_dsl::program_revisions)
+        .values((
+            r_dsl::program_id.eq(program_id),
+            r_dsl::author_id.eq(author_id),
+            r_dsl::revision.eq(next_revision),
+            r_dsl::snapshot.eq(snapshot),
+        ))
+        .returning(ProgramRevision::as_returning())
+        .get_result(conn)?;
+
+    Ok(Some(res))
+}
+
+/// Records a revision for the program the workout belongs to, if it belongs to one.
+pub fn record_workout_program_revision(
+    conn: &mut DbConnection,
+    workout_id: uuid::Uuid,
+    author_id: uuid::Uuid,
+) -> QueryResult<()> {
+    use crate::schema::workouts::dsl as w_dsl;
+
+    let program_id: Option<uuid::Uuid> = w_dsl::workouts
+        .filter(w_dsl::id.eq(workout_id))
+        .select(w_dsl::program_id)
+        .first(conn)?;
+
+    if let Some(program_id) = program_id {
+        record_program_revision(conn, program_id, author_id)?;
+    }
+
+    Ok(())
+}
+
+/// Rolls the program tree back to the state stored in `snapshot`.
+///
+/// Workouts and exercises that were added after the snapshot are archived and the ones that were
+/// archived or deleted since are restored, recreated with their original ids if they were purged.
+/// Ownership, assignment and the marketplace listing of the program (owner, client, template,
+/// active, published and the listing metadata) are left as they are now, and so are the
+/// completion and scheduled dates of the workouts the client logged against.
+pub fn restore_program_snapshot(
+    conn: &mut DbConnection,
+    current: &Program,
+    snapshot: ProgramWithWorkouts,
+) -> QueryResult<()> {
+    use crate::schema::{
+        exercises::dsl as e_dsl, programs::dsl as p_dsl, workout_blocks::dsl as b_dsl,
+        workouts::dsl as w_dsl,
+    };
+
+    let restored_program = Program {
+        id: current.id,
+        owner_id: current.owner_id,
+        client_id: current.client_id,
+        template: current.template,
+        active: current.active,
+        published: current.published,
+        published_at: current.published_at,
+        price_tier: current.price_tier.clone(),
+        level: current.level.clone(),
+        archived_at: current.archived_at,
+        ..snapshot.program
+    };
+
+    diesel::update(p_dsl::programs.filter(p_dsl::id.eq(current.id)))
+        .set(&restored_program)
+        .execute(conn)?;
+
+    let snapshot_workout_ids: Vec<uuid::Uuid> =
+        snapshot.workouts.iter().map(|w| w.workout.id).collect();
+
+    let snapshot_exercise_ids: Vec<uuid::Uuid> = snapshot
+        .workouts
+        .iter()
+        .flat_map(|w| w.exercises.iter().map(|e| e.exercise.id))
+        .collect();
+
+    let current_workout_ids: Vec<uuid::Uuid> = w_dsl::workouts
+        .filter(w_dsl::program_id.eq(current.id))
+        .select(w_dsl::id)
+        .load(conn)?;
+
+    let now = chrono::Utc::now();
+
+    diesel::update(
+        e_dsl::exercises.filter(
+            e_dsl::workout_id
+                .eq_any(&current_workout_ids)
+                .and(e_dsl::id.ne_all(&snapshot_exercise_ids))
+                .and(e_dsl::archived_at.is_null()),
+        ),
+    )
+    .set(e_dsl::archived_at.eq(now))
+    .execute(conn)?;
+
+    diesel::update(
+        w_dsl::workouts.filter(
+            w_dsl::program_id
+                .eq(current.id)
+                .and(w_dsl::id.ne_all(&snapshot_workout_ids))
+                .and(w_dsl::archived_at.is_null()),
+        ),
+    )
+    .set(w_dsl::archived_at.eq(now))
+    .execute(conn)?;
+
+    for WorkoutWithExercises {
+        workout,
+        blocks,
+        exercises,
+    } in snapshot.workouts
+    {
+        let workout = Workout {
+            program_id: Some(current.id),
+            ..workout
+        };
+
+        insert_into(w_dsl::workouts)
+            .values(&workout)
+            .on_conflict(w_dsl::id)
+            .do_update()
+            .set((
+                w_dsl::program_id.eq(excluded(w_dsl::program_id)),
+                w_dsl::owner_id.eq(excluded(w_dsl::owner_id)),
+                w_dsl::name.eq(excluded(w_dsl::name)),
+                w_dsl::description.eq(excluded(w_dsl::description)),
+                w_dsl::duration.eq(excluded(w_dsl::duration)),
+                w_dsl::sequence.eq(excluded(w_dsl::sequence)),
+                w_dsl::week.eq(excluded(w_dsl::week)),
+                w_dsl::intensity.eq(excluded(w_dsl::intensity)),
+                w_dsl::workout_type.eq(excluded(w_dsl::workout_type)),
+                w_dsl::equipment_needed.eq(excluded(w_dsl::equipment_needed)),
+                w_dsl::image.eq(excluded(w_dsl::image)),
+                w_dsl::video.eq(excluded(w_dsl::video)),
+                w_dsl::template.eq(excluded(w_dsl::template)),
+                w_dsl::slug.eq(excluded(w_dsl::slug)),
+                w_dsl::archived_at.eq(excluded(w_dsl::archived_at)),
+            ))
+            .execute(conn)?;
+
+        // blocks aren't archived, the exercises of removed blocks end up without one
+        let block_ids: Vec<uuid::Uuid> = blocks.iter().map(|b| b.id).collect();
+
+        diesel::delete(
+            b_dsl::workout_blocks.filter(
+                b_dsl::workout_id
+                    .eq(workout.id)
+                    .and(b_dsl::id.ne_all(&block_ids)),
+            ),
+        )
+        .execute(conn)?;
+
+        for block in blocks {
+            let block = WorkoutBlock {
+                workout_id: workout.id,
+                ..block
+            };
+
+            insert_into(b_dsl::workout_blocks)
+                .values(&block)
+                .on_conflict(b_dsl::id)
+                .do_update()
+                .set(&block)
+                .execute(conn)?;
+        }
+
+        for ExerciseWithCatalog { exercise, catalog } in exercises {
+            let mut exercise = Exercise {
+                workout_id: Some(workout.id),
+                catalog_id: restore_catalog_entry(conn, current.owner_id, catalog)?,
+                ..exercise
+            };
+            exercise.reparse_prescription();
+
+            insert_into(e_dsl::exercises)
+                .values(&exercise)
+                .on_conflict(e_dsl::id)
+                .do_update()
+                .set(&exercise)
+                .execute(conn)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Recomputes the progress of a program from its (non-archived) workouts and keeps
+/// `programs.complete` in sync, a program is complete once all of its workouts are and stops being
+/// complete as soon as one of them isn't anymore.
+///
+/// Should run in the same transaction as the change to the workouts.
+pub fn sync_program_progress(
+    conn: &mut DbConnection,
+    program_id: uuid::Uuid,
+) -> QueryResult<ProgramProgress> {
+    use crate::schema::{
+        program_progress::dsl as pp_dsl, programs::dsl as p_dsl, workouts::dsl as w_dsl,
+    };
+
+    let program_workouts: Vec<Workout> = w_dsl::workouts
+        .filter(
+            w_dsl::program_id
+                .eq(program_id)
+                .and(w_dsl::archived_at.is_null()),
+        )
+        .order((w_dsl::week.asc(), w_dsl::sequence.asc()))
+        .select(Workout::as_select())
+        .load(conn)?;
+
+    let progress = NewProgramProgress::from_workouts(program_id, &program_workouts);
+
+    let is_complete =
+        progress.total_workouts > 0 && progress.completed_workouts == progress.total_workouts;
+
+    diesel::update(p_dsl::programs)
+        .filter(p_dsl::id.eq(program_id))
+        .set(p_dsl::complete.eq(is_complete))
+        .execute(conn)?;
+
+    let res = insert_into(pp_dsl::program_progress)
+        .values(&progress)
+        .on_conflict(pp_dsl::program_id)
+        .do_update()
+        .set(&progress)
+        .returning(ProgramProgress::as_returning())
+        .get_result(conn)?;
+
+    Ok(res)
+}
+
+/// Creates a template program with its workouts and exercises from an imported document.
+///
+/// The document should already be validated, this does not open a transaction itself so callers
+/// should wrap it in one.
+pub fn import_program_document(
+    conn: &mut DbConnection,
+    doc: &ProgramDocument,
+    new_owner_id: uuid::Uuid,
+) -> QueryResult<Program> {
+    use crate::schema::programs::dsl as p_dsl;
+
+    let new_program: Program = insert_into(p_dsl::programs)
+        .values((
+            p_dsl::owner_id.eq(new_owner_id),
+            p_dsl::name.eq(&doc.name),
+            p_dsl::description.eq(&doc.description),
+            p_dsl::duration.eq(&doc.duration),
+            p_dsl::focus_areas.eq(&doc.focus_areas),
+            p_dsl::target_audience.eq(&doc.target_audience),
+            p_dsl::program_image.eq(&doc.program_image),
+            p_dsl::intensity.eq(&doc.intensity),
+            p_dsl::level.eq(&doc.level),
+            p_dsl::slug.eq(random_slug(&doc.name)),
+            p_dsl::template.eq(true),
+            p_dsl::training_days.eq(doc.training_days_column()),
+        ))
+        .returning(Program::as_returning())
+        .get_result(conn)?;
+
+    insert_workout_documents(conn, new_program.id, new_owner_id, &doc.workouts)?;
+
+    Ok(new_program)
+}
+
+/// Creates workouts (and their exercises) in a program from their document form. A missing
+/// `sequence` falls back to the position in the list, exercises the catalog doesn't know yet are
+/// added to it.
+pub fn insert_workout_documents(
+    conn: &mut DbConnection,
+    program_id: uuid::Uuid,
+    owner_id: uuid::Uuid,
+    workouts: &[WorkoutDocument],
+) -> QueryResult<()> {
+    use crate::schema::{
+        exercises::dsl as e_dsl, workout_blocks::dsl as b_dsl, workouts::dsl as w_dsl,
+    };
+
+    for (w_idx, workout) in workouts.iter().enumerate() {
+        let new_workout_id: uuid::Uuid = insert_into(w_dsl::workouts)
+            .values((
+                w_dsl::program_id.eq(program_id),
+                w_dsl::owner_id.eq(owner_id),
+                w_dsl::name.eq(&workout.name),
+                w_dsl::description.eq(&workout.description),
+                w_dsl::duration.eq(&workout.duration),
+                w_dsl::sequence.eq(workout.sequence.unwrap_or(w_idx as i32)),
+                w_dsl::week.eq(workout.week),
+                w_dsl::intensity.eq(&workout.intensity),
+                w_dsl::workout_type.eq(&workout.workout_type),
+                w_dsl::equipment_needed.eq(&workout.equipment_needed),
+                w_dsl::image.eq(&workout.image),
+                w_dsl::video.eq(&workout.video),
+                w_dsl::template.eq(false),
+                w_dsl::slug.eq(random_slug(&workout.name)),
+            ))
+            .returning(w_dsl::id)
+            .get_result(conn)?;
+
+        let mut block_ids: Vec<uuid::Uuid> = vec![];
+
+        for (b_idx, block) in workout.blocks.iter().enumerate() {
+            block_ids.push(
+                insert_into(b_dsl::workout_blocks)
+                    .values((
+                        block.to_new_block(b_idx as i32),
+                        b_dsl::workout_id.eq(new_workout_id),
+                    ))
+                    .returning(b_dsl::id)
+                    .get_result(conn)?,
+            );
+        }
+
+        for (e_idx, exercise) in workout.exercises.iter().enumerate() {
+            let catalog_id = resolve_catalog_entry(conn, owner_id, exercise)?;
+            let (_, prescription) =
+                Prescription::parse(&exercise.reps, &exercise.duration, &exercise.rest_period);
+
+            insert_into(e_dsl::exercises)
+                .values((
+                    e_dsl::workout_id.eq(new_workout_id),
+                    e_dsl::owner_id.eq(owner_id),
+                    e_dsl::catalog_id.eq(catalog_id),
+                    e_dsl::block_id.eq(exercise.block.and_then(|b| block_ids.get(b).copied())),
+                    e_dsl::duration.eq(&exercise.duration),
+                    e_dsl::reps.eq(&exercise.reps),
+                    e_dsl::sets.eq(exercise.sets),
+                    e_dsl::rest_period.eq(&exercise.rest_period),
+                    e_dsl::intensity.eq(&exercise.intensity),
+                    e_dsl::sequence.eq(exercise.sequence.unwrap_or(e_idx as i32)),
+                    e_dsl::slug.eq(random_slug(&exercise.name)),
+                    e_dsl::load.eq(exercise.load),
+                    e_dsl::load_unit.eq(&exercise.load_unit),
+                    prescription,
+                ))
+                .execute(conn)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct PurgedRows {
+    pub programs: usize,
+    pub workouts: usize,
+    pub exercises: usize,
+}
+
+/// Deletes programs, workouts and exercises for good once they have been archived for longer
+/// than the retention period. Deleting a program or workout takes its children with it.
+pub fn purge_archived(
+    conn: &mut DbConnection,
+    archived_before: chrono::DateTime<chrono::Utc>,
+) -> QueryResult<PurgedRows> {
+    use crate::schema::{exercises::dsl as e_dsl, programs::dsl as p_dsl, workouts::dsl as w_dsl};
+
+    conn.transaction(|conn| {
+        let programs = diesel::delete(p_dsl::programs)
+            .filter(p_dsl::archived_at.lt(archived_before))
+            .execute(conn)?;
+
+        let workouts = diesel::delete(w_dsl::workouts)
+            .filter(w_dsl::archived_at.lt(archived_before))
+            .execute(conn)?;
+
+        let exercises = diesel::delete(e_dsl::exercises)
+            .filter(e_dsl::archived_at.lt(archived_before))
+            .execute(conn)?;
+
+        Ok(PurgedRows {
+            programs,
+            workouts,
+            exercises,
+        })
+    })
+}
@@ -0,0 +1,141 @@
+use super::{
+    models::{
+        personal_record::{record_candidates, PersonalRecord, RecordKinds},
+        workout_session::SetLog,
+    },
+    DbConnection,
+};
+use crate::schema::{exercises, personal_records, set_logs, workout_sessions};
+use diesel::{insert_into, prelude::*};
+
+/// Small enough to ignore float noise from unit conversions, a record has to be beaten.
+const RECORD_EPSILON: f64 = 1e-6;
+
+/// Records the personal records the set beats and returns them. The records a previous version of
+/// the set held are dropped first, see `drop_set_records`, so correcting a set re-evaluates it.
+/// Should be run inside a transaction.
+pub fn update_records(
+    conn: &mut DbConnection,
+    user_id: uuid::Uuid,
+    catalog_id: uuid::Uuid,
+    set: &SetLog,
+) -> QueryResult<Vec<PersonalRecord>> {
+    drop_set_records(conn, user_id, catalog_id, set.id)?;
+
+    let mut res = vec![];
+
+    for (kind, reps, value) in
+        record_candidates(set.reps, set.load, &set.load_unit, set.duration_seconds)
+    {
+        let best: Option<f64> = personal_records::table
+            .filter(personal_records::user_id.eq(user_id))
+            .filter(personal_records::catalog_id.eq(catalog_id))
+            .filter(personal_records::kind.eq(kind))
+            .filter(personal_records::reps.eq(reps))
+            .select(diesel::dsl::max(personal_records::value))
+            .first(conn)?;
+
+        if best.is_some_and(|best| value <= best + RECORD_EPSILON) {
+            continue;
+        }
+
+        let record = insert_into(personal_records::table)
+            .values((
+                personal_records::user_id.eq(user_id),
+                personal_records::catalog_id.eq(catalog_id),
+                personal_records::set_log_id.eq(set.id),
+                personal_records::kind.eq(kind),
+                personal_records::reps.eq(reps),
+                personal_records::value.eq(value),
+                personal_records::previous_value.eq(best),
+            ))
+            .returning(PersonalRecord::as_returning())
+            .get_result(conn)?;
+
+        res.push(record);
+    }
+
+    Ok(res)
+}
+
+/// Drops the records a set holds, before it's corrected or deleted. The best of the user's other
+/// sets of the catalog entry becomes the record again wherever the dropped one was ahead of it.
+pub fn drop_set_records(
+    conn: &mut DbConnection,
+    user_id: uuid::Uuid,
+    catalog_id: uuid::Uuid,
+    set_log_id: uuid::Uuid,
+) -> QueryResult<()> {
+    let held: Vec<(RecordKinds, i32)> =
+        diesel::delete(personal_records::table.filter(personal_records::set_log_id.eq(set_log_id)))
+            .returning((personal_records::kind, personal_records::reps))
+            .get_results(conn)?;
+
+    if held.is_empty() {
+        return Ok(());
+    }
+
+    // oldest first, so the set that got there first keeps ties
+    let other_sets: Vec<SetLog> = set_logs::table
+        .inner_join(workout_sessions::table)
+        .inner_join(exercises::table)
+        .filter(workout_sessions::user_id.eq(user_id))
+        .filter(exercises::catalog_id.eq(catalog_id))
+        .filter(set_logs::id.ne(set_log_id))
+        .order_by((set_logs::created_at.asc(), set_logs::id.asc()))
+        .select(SetLog::as_select())
+        .load(conn)?;
+
+    for (kind, reps) in held {
+        let mut best_set: Option<(&SetLog, f64)> = None;
+
+        for other in &other_sets {
+            let value = record_candidates(
+                other.reps,
+                other.load,
+                &other.load_unit,
+                other.duration_seconds,
+            )
+            .into_iter()
+            .find(|(k, r, _)| *k == kind && *r == reps)
+            .map(|(.., value)| value);
+
+            if let Some(value) = value {
+                if best_set.is_none_or(|(_, best)| value > best + RECORD_EPSILON) {
+                    best_set = Some((other, value));
+                }
+            }
+        }
+
+        let Some((other, value)) = best_set else {
+            continue;
+        };
+
+        let current: Option<f64> = personal_records::table
+            .filter(personal_records::user_id.eq(user_id))
+            .filter(personal_records::catalog_id.eq(catalog_id))
+            .filter(personal_records::kind.eq(kind))
+            .filter(personal_records::reps.eq(reps))
+            .select(diesel::dsl::max(personal_records::value))
+            .first(conn)?;
+
+        if current.is_some_and(|current| value <= current + RECORD_EPSILON) {
+            continue;
+        }
+
+        insert_into(personal_records::table)
+            .values((
+                personal_records::user_id.eq(user_id),
+                personal_records::catalog_id.eq(catalog_id),
+                personal_records::set_log_id.eq(other.id),
+                personal_records::kind.eq(kind),
+                personal_records::reps.eq(reps),
+                personal_records::value.eq(value),
+                personal_records::previous_value.eq(current),
+                personal_records::achieved_at.eq(other.created_at),
+            ))
+            .execute(conn)?;
+    }
+
+    Ok(())
+}
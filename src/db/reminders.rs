@@ -0,0 +1,235 @@
+use super::{
+    models::{
+        notification::NewNotification, program::Program, workout::ScheduledWorkout,
+        workout_alert::WorkoutAlertKinds,
+    },
+    programs::load_program_schedule,
+    DbConnection,
+};
+use crate::{
+    error::BoxedAppError,
+    settings::RemindersConfig,
+    types::AppResult,
+    util::calendar::{due_workout_alert, missed_recently},
+};
+use chrono_tz::Tz;
+use diesel::{insert_into, prelude::*};
+use std::collections::{HashMap, HashSet};
+
+pub const ATTENTION_STATUS: &str = "attention";
+
+#[derive(Debug, Default)]
+pub struct AlertSummary {
+    pub reminders: usize,
+    pub missed: usize,
+    pub flagged_clients: usize,
+}
+
+/// Reminds clients of the workouts planned in their active programs and tells trainers about the
+/// ones that were missed, each alert is only ever sent once per workout.
+///
+/// Clients that missed a workout since the last run and have at least `attention_missed` missed
+/// workouts are flagged with the "attention" status. The flag isn't cleared here, trainers reset
+/// the status themselves once they've followed up.
+pub fn send_workout_alerts(
+    conn: &mut DbConnection,
+    config: &RemindersConfig,
+    now: chrono::DateTime<chrono::Utc>,
+) -> AppResult<AlertSummary> {
+    use crate::schema::{
+        clients::dsl as c_dsl, programs::dsl as p_dsl, users::dsl as u_dsl,
+        workout_alerts::dsl as a_dsl,
+    };
+
+    let active_programs: Vec<(Program, uuid::Uuid, uuid::Uuid, String)> = p_dsl::programs
+        .inner_join(c_dsl::clients.on(p_dsl::client_id.eq(c_dsl::id.nullable())))
+        .inner_join(u_dsl::users.on(c_dsl::user_id.eq(u_dsl::id.nullable())))
+        .filter(
+            p_dsl::active
+                .eq(true)
+                .and(p_dsl::complete.eq(false))
+                .and(p_dsl::archived_at.is_null())
+                .and(p_dsl::start_date.is_not_null())
+                .and(c_dsl::is_active.eq(true)),
+        )
+        .select((Program::as_select(), c_dsl::id, u_dsl::id, u_dsl::timezone))
+        .load(conn)?;
+
+    let mut summary = AlertSummary::default();
+    let mut missed_per_client: HashMap<uuid::Uuid, i64> = HashMap::new();
+    let mut newly_missed_clients: HashSet<uuid::Uuid> = HashSet::new();
+
+    for (program, client_id, client_user_id, timezone) in active_programs {
+        let Some(trainer_id) = program.owner_id else {
+            continue;
+        };
+
+        let Some(schedule) = load_program_schedule(conn, &program)? else {
+            continue;
+        };
+
+        let workout_ids: Vec<uuid::Uuid> = schedule.iter().map(|s| s.workout.id).collect();
+
+        let already_sent: HashSet<(uuid::Uuid, WorkoutAlertKinds)> = a_dsl::workout_alerts
+            .filter(a_dsl::workout_id.eq_any(&workout_ids))
+            .select((a_dsl::workout_id, a_dsl::kind))
+            .load::<(uuid::Uuid, WorkoutAlertKinds)>(conn)?
+            .into_iter()
+            .collect();
+
+        let tz: Tz = timezone.parse().unwrap_or(Tz::UTC);
+
+        for scheduled in schedule.iter().filter(|s| !s.workout.complete) {
+            let Some(kind) = due_workout_alert(
+                scheduled.date,
+                tz,
+                now,
+                config.lead_hours,
+                config.grace_hours,
+            ) else {
+                continue;
+            };
+
+            if kind == WorkoutAlertKinds::Missed {
+                *missed_per_client.entry(client_id).or_default() += 1;
+            }
+
+            // so workouts missed long ago don't all get reported at once after the job is deployed
+            if kind == WorkoutAlertKinds::Missed
+                && !missed_recently(
+                    scheduled.date,
+                    tz,
+                    now,
+                    config.grace_hours,
+                    config.missed_window_hours,
+                )
+            {
+                continue;
+            }
+
+            if already_sent.contains(&(scheduled.workout.id, kind)) {
+                continue;
+            }
+
+            let notification = match kind {
+                WorkoutAlertKinds::Reminder => {
+                    reminder_notification(&program, scheduled, trainer_id, client_user_id)
+                }
+                WorkoutAlertKinds::Missed => {
+                    missed_notification(&program, scheduled, client_id, client_user_id, trainer_id)
+                }
+            };
+
+            let sent = conn.transaction::<_, BoxedAppError, _>(|conn| {
+                let inserted = insert_into(a_dsl::workout_alerts)
+                    .values((
+                        a_dsl::workout_id.eq(scheduled.workout.id),
+                        a_dsl::kind.eq(kind),
+                    ))
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+
+                // another run got to it first
+                if inserted == 0 {
+                    return Ok(false);
+                }
+
+                notification.send(conn)?;
+
+                Ok(true)
+            })?;
+
+            if !sent {
+                continue;
+            }
+
+            match kind {
+                WorkoutAlertKinds::Reminder => summary.reminders += 1,
+                WorkoutAlertKinds::Missed => {
+                    summary.missed += 1;
+                    newly_missed_clients.insert(client_id);
+                }
+            }
+        }
+    }
+
+    let flag_client_ids: Vec<uuid::Uuid> = newly_missed_clients
+        .into_iter()
+        .filter(|c_id| {
+            missed_per_client.get(c_id).copied().unwrap_or_default() >= config.attention_missed
+        })
+        .collect();
+
+    summary.flagged_clients = diesel::update(c_dsl::clients)
+        .filter(
+            c_dsl::id
+                .eq_any(flag_client_ids)
+                .and(c_dsl::status.ne(ATTENTION_STATUS)),
+        )
+        .set(c_dsl::status.eq(ATTENTION_STATUS))
+        .execute(conn)?;
+
+    Ok(summary)
+}
+
+fn reminder_notification(
+    program: &Program,
+    scheduled: &ScheduledWorkout,
+    trainer_id: uuid::Uuid,
+    client_user_id: uuid::Uuid,
+) -> NewNotification {
+    let d = serde_json::json!({
+        "program_id": program.id,
+        "workout_id": scheduled.workout.id,
+        "date": scheduled.date,
+    });
+
+    NewNotification::new(
+        trainer_id,
+        client_user_id,
+        "You have a workout coming up".into(),
+        truncate(format!(
+            "{} is planned for {}.",
+            scheduled.workout.name,
+            scheduled.date.format("%A, %B %-d")
+        )),
+        "workout".into(),
+        "unread".into(),
+        Some(d),
+    )
+}
+
+fn missed_notification(
+    program: &Program,
+    scheduled: &ScheduledWorkout,
+    client_id: uuid::Uuid,
+    client_user_id: uuid::Uuid,
+    trainer_id: uuid::Uuid,
+) -> NewNotification {
+    let d = serde_json::json!({
+        "program_id": program.id,
+        "workout_id": scheduled.workout.id,
+        "client_id": client_id,
+        "date": scheduled.date,
+    });
+
+    NewNotification::new(
+        client_user_id,
+        trainer_id,
+        "A client missed a workout".into(),
+        truncate(format!(
+            "{} from {} wasn't completed on {}.",
+            scheduled.workout.name,
+            program.name,
+            scheduled.date.format("%A, %B %-d")
+        )),
+        "client".into(),
+        "unread".into(),
+        Some(d),
+    )
+}
+
+/// Notification content is limited to 255 characters.
+fn truncate(content: String) -> String {
+    content.chars().take(255).collect()
+}
@@ -0,0 +1,228 @@
+use super::{
+    models::tag::{FacetCount, Tag, TagCategories, TagMatch},
+    DbConnection,
+};
+use crate::{
+    error::bad_request,
+    schema::{program_tags, programs, tags, workout_tags, workouts},
+    types::AppResult,
+};
+use diesel::{dsl::count, insert_into, pg::Pg, prelude::*};
+
+/// Keeps the programs tagged with the slugs of `category`, either any of them or all of them.
+pub fn filter_tagged_programs<'a>(
+    mut query: programs::BoxedQuery<'a, Pg>,
+    category: TagCategories,
+    slugs: Vec<String>,
+    tag_match: TagMatch,
+) -> programs::BoxedQuery<'a, Pg> {
+    let tagged = |matching: Vec<String>| {
+        program_tags::table
+            .inner_join(tags::table)
+            .filter(tags::category.eq(category).and(tags::slug.eq_any(matching)))
+            .select(program_tags::program_id)
+    };
+
+    match tag_match {
+        TagMatch::Any => query.filter(programs::id.eq_any(tagged(slugs))),
+        TagMatch::All => {
+            for slug in slugs {
+                query = query.filter(programs::id.eq_any(tagged(vec![slug])));
+            }
+            query
+        }
+    }
+}
+
+/// Keeps the workouts tagged with the slugs of `category`, either any of them or all of them.
+pub fn filter_tagged_workouts<'a>(
+    mut query: workouts::BoxedQuery<'a, Pg>,
+    category: TagCategories,
+    slugs: Vec<String>,
+    tag_match: TagMatch,
+) -> workouts::BoxedQuery<'a, Pg> {
+    let tagged = |matching: Vec<String>| {
+        workout_tags::table
+            .inner_join(tags::table)
+            .filter(tags::category.eq(category).and(tags::slug.eq_any(matching)))
+            .select(workout_tags::workout_id)
+    };
+
+    match tag_match {
+        TagMatch::Any => query.filter(workouts::id.eq_any(tagged(slugs))),
+        TagMatch::All => {
+            for slug in slugs {
+                query = query.filter(workouts::id.eq_any(tagged(vec![slug])));
+            }
+            query
+        }
+    }
+}
+
+/// How many of the programs matched by `query` carry each tag, tags without programs are left out.
+pub fn program_facets(
+    conn: &mut DbConnection,
+    query: programs::BoxedQuery<'_, Pg>,
+) -> QueryResult<Vec<FacetCount>> {
+    let counts: Vec<(Tag, i64)> = program_tags::table
+        .inner_join(tags::table)
+        .filter(program_tags::program_id.eq_any(query.select(programs::id)))
+        .group_by(tags::id)
+        .select((Tag::as_select(), count(program_tags::program_id)))
+        .order_by((tags::category.asc(), tags::name.asc()))
+        .load(conn)?;
+
+    Ok(counts
+        .into_iter()
+        .map(|(tag, count)| FacetCount { tag, count })
+        .collect())
+}
+
+/// How many of the workouts matched by `query` carry each tag, tags without workouts are left out.
+pub fn workout_facets(
+    conn: &mut DbConnection,
+    query: workouts::BoxedQuery<'_, Pg>,
+) -> QueryResult<Vec<FacetCount>> {
+    let counts: Vec<(Tag, i64)> = workout_tags::table
+        .inner_join(tags::table)
+        .filter(workout_tags::workout_id.eq_any(query.select(workouts::id)))
+        .group_by(tags::id)
+        .select((Tag::as_select(), count(workout_tags::workout_id)))
+        .order_by((tags::category.asc(), tags::name.asc()))
+        .load(conn)?;
+
+    Ok(counts
+        .into_iter()
+        .map(|(tag, count)| FacetCount { tag, count })
+        .collect())
+}
+
+pub fn load_program_tags(conn: &mut DbConnection, program_id: uuid::Uuid) -> QueryResult<Vec<Tag>> {
+    program_tags::table
+        .inner_join(tags::table)
+        .filter(program_tags::program_id.eq(program_id))
+        .order_by((tags::category.asc(), tags::name.asc()))
+        .select(Tag::as_select())
+        .load(conn)
+}
+
+pub fn load_workout_tags(conn: &mut DbConnection, workout_id: uuid::Uuid) -> QueryResult<Vec<Tag>> {
+    workout_tags::table
+        .inner_join(tags::table)
+        .filter(workout_tags::workout_id.eq(workout_id))
+        .order_by((tags::category.asc(), tags::name.asc()))
+        .select(Tag::as_select())
+        .load(conn)
+}
+
+/// Makes sure every tag exists and can be put on a program (`for_programs`) or a workout.
+pub fn guard_tag_ids(
+    conn: &mut DbConnection,
+    tag_ids: &[uuid::Uuid],
+    for_programs: bool,
+) -> AppResult<()> {
+    let categories: Vec<TagCategories> = tags::table
+        .filter(tags::id.eq_any(tag_ids))
+        .select(tags::category)
+        .load(conn)?;
+
+    let mut unique_ids = tag_ids.to_vec();
+    unique_ids.sort();
+    unique_ids.dedup();
+
+    if categories.len() != unique_ids.len() {
+        return Err(bad_request("Unknown tag."));
+    }
+
+    if categories.iter().any(|c| c.for_programs() != for_programs) {
+        return Err(bad_request(if for_programs {
+            "Programs can only be tagged with focus_area and audience tags."
+        } else {
+            "Workouts can only be tagged with workout_type and equipment tags."
+        }));
+    }
+
+    Ok(())
+}
+
+/// Replaces the tags of the program. Should be run inside a transaction.
+pub fn replace_program_tags(
+    conn: &mut DbConnection,
+    program_id: uuid::Uuid,
+    tag_ids: &[uuid::Uuid],
+) -> QueryResult<()> {
+    diesel::delete(program_tags::table.filter(program_tags::program_id.eq(program_id)))
+        .execute(conn)?;
+
+    let rows: Vec<_> = tag_ids
+        .iter()
+        .map(|t_id| {
+            (
+                program_tags::program_id.eq(program_id),
+                program_tags::tag_id.eq(*t_id),
+            )
+        })
+        .collect();
+
+    insert_into(program_tags::table)
+        .values(&rows)
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Replaces the tags of the workout. Should be run inside a transaction.
+pub fn replace_workout_tags(
+    conn: &mut DbConnection,
+    workout_id: uuid::Uuid,
+    tag_ids: &[uuid::Uuid],
+) -> QueryResult<()> {
+    diesel::delete(workout_tags::table.filter(workout_tags::workout_id.eq(workout_id)))
+        .execute(conn)?;
+
+    let rows: Vec<_> = tag_ids
+        .iter()
+        .map(|t_id| {
+            (
+                workout_tags::workout_id.eq(workout_id),
+                workout_tags::tag_id.eq(*t_id),
+            )
+        })
+        .collect();
+
+    insert_into(workout_tags::table)
+        .values(&rows)
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Copies the tags of one program onto another, used when programs are deep copied.
+pub fn copy_program_tags(
+    conn: &mut DbConnection,
+    from_program_id: uuid::Uuid,
+    to_program_id: uuid::Uuid,
+) -> QueryResult<()> {
+    let tag_ids: Vec<uuid::Uuid> = program_tags::table
+        .filter(program_tags::program_id.eq(from_program_id))
+        .select(program_tags::tag_id)
+        .load(conn)?;
+
+    replace_program_tags(conn, to_program_id, &tag_ids)
+}
+
+/// Copies the tags of one workout onto another, used when programs are deep copied.
+pub fn copy_workout_tags(
+    conn: &mut DbConnection,
+    from_workout_id: uuid::Uuid,
+    to_workout_id: uuid::Uuid,
+) -> QueryResult<()> {
+    let tag_ids: Vec<uuid::Uuid> = workout_tags::table
+        .filter(workout_tags::workout_id.eq(from_workout_id))
+        .select(workout_tags::tag_id)
+        .load(conn)?;
+
+    replace_workout_tags(conn, to_workout_id, &tag_ids)
+}
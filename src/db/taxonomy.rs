@@ -0,0 +1,289 @@
+use super::{
+    models::taxonomy::{CatalogMuscle, Equipment, Muscle, MuscleRoles, SetCatalogMuscles},
+    DbConnection,
+};
+use crate::{
+    error::bad_request,
+    schema::{
+        catalog_equipment, catalog_muscles, client_equipment, clients, equipment, exercises,
+        muscles, workouts,
+    },
+    types::AppResult,
+    util::parse_slugs,
+};
+use diesel::{insert_into, prelude::*};
+
+pub fn load_catalog_muscles(
+    conn: &mut DbConnection,
+    catalog_id: uuid::Uuid,
+) -> QueryResult<Vec<CatalogMuscle>> {
+    let res: Vec<(Muscle, MuscleRoles)> = catalog_muscles::table
+        .inner_join(muscles::table)
+        .filter(catalog_muscles::catalog_id.eq(catalog_id))
+        .order_by((catalog_muscles::role.asc(), muscles::name.asc()))
+        .select((Muscle::as_select(), catalog_muscles::role))
+        .load(conn)?;
+
+    Ok(res
+        .into_iter()
+        .map(|(muscle, role)| CatalogMuscle { muscle, role })
+        .collect())
+}
+
+/// Catalog entry, muscle and role of every muscle linked to the entries.
+pub fn load_muscle_links(
+    conn: &mut DbConnection,
+    catalog_ids: &[uuid::Uuid],
+) -> QueryResult<Vec<(uuid::Uuid, uuid::Uuid, MuscleRoles)>> {
+    catalog_muscles::table
+        .filter(catalog_muscles::catalog_id.eq_any(catalog_ids))
+        .select((
+            catalog_muscles::catalog_id,
+            catalog_muscles::muscle_id,
+            catalog_muscles::role,
+        ))
+        .load(conn)
+}
+
+pub fn load_catalog_equipment(
+    conn: &mut DbConnection,
+    catalog_id: uuid::Uuid,
+) -> QueryResult<Vec<Equipment>> {
+    catalog_equipment::table
+        .inner_join(equipment::table)
+        .filter(catalog_equipment::catalog_id.eq(catalog_id))
+        .order_by(equipment::name.asc())
+        .select(Equipment::as_select())
+        .load(conn)
+}
+
+fn unique_ids(ids: &[uuid::Uuid]) -> Vec<uuid::Uuid> {
+    let mut res = ids.to_vec();
+    res.sort();
+    res.dedup();
+    res
+}
+
+/// Makes sure every muscle exists and is either primary or secondary, not both.
+pub fn guard_muscle_ids(conn: &mut DbConnection, body: &SetCatalogMuscles) -> AppResult<()> {
+    if body
+        .primary_ids
+        .iter()
+        .any(|m| body.secondary_ids.contains(m))
+    {
+        return Err(bad_request("A muscle can't be both primary and secondary."));
+    }
+
+    let ids = unique_ids(&[body.primary_ids.as_slice(), &body.secondary_ids].concat());
+
+    let known: i64 = muscles::table
+        .filter(muscles::id.eq_any(&ids))
+        .count()
+        .get_result(conn)?;
+
+    if known as usize != ids.len() {
+        return Err(bad_request("Unknown muscle."));
+    }
+
+    Ok(())
+}
+
+pub fn guard_equipment_ids(conn: &mut DbConnection, equipment_ids: &[uuid::Uuid]) -> AppResult<()> {
+    let ids = unique_ids(equipment_ids);
+
+    let known: i64 = equipment::table
+        .filter(equipment::id.eq_any(&ids))
+        .count()
+        .get_result(conn)?;
+
+    if known as usize != ids.len() {
+        return Err(bad_request("Unknown equipment."));
+    }
+
+    Ok(())
+}
+
+/// Replaces the muscles of the catalog entry. Should be run inside a transaction.
+pub fn replace_catalog_muscles(
+    conn: &mut DbConnection,
+    catalog_id: uuid::Uuid,
+    body: &SetCatalogMuscles,
+) -> QueryResult<()> {
+    diesel::delete(catalog_muscles::table.filter(catalog_muscles::catalog_id.eq(catalog_id)))
+        .execute(conn)?;
+
+    let rows: Vec<_> = body
+        .primary_ids
+        .iter()
+        .map(|m_id| (m_id, MuscleRoles::Primary))
+        .chain(
+            body.secondary_ids
+                .iter()
+                .map(|m_id| (m_id, MuscleRoles::Secondary)),
+        )
+        .map(|(m_id, role)| {
+            (
+                catalog_muscles::catalog_id.eq(catalog_id),
+                catalog_muscles::muscle_id.eq(*m_id),
+                catalog_muscles::role.eq(role),
+            )
+        })
+        .collect();
+
+    insert_into(catalog_muscles::table)
+        .values(&rows)
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Replaces the equipment of the catalog entry. Should be run inside a transaction.
+pub fn replace_catalog_equipment(
+    conn: &mut DbConnection,
+    catalog_id: uuid::Uuid,
+    equipment_ids: &[uuid::Uuid],
+) -> QueryResult<()> {
+    diesel::delete(catalog_equipment::table.filter(catalog_equipment::catalog_id.eq(catalog_id)))
+        .execute(conn)?;
+
+    let rows: Vec<_> = equipment_ids
+        .iter()
+        .map(|e_id| {
+            (
+                catalog_equipment::catalog_id.eq(catalog_id),
+                catalog_equipment::equipment_id.eq(*e_id),
+            )
+        })
+        .collect();
+
+    insert_into(catalog_equipment::table)
+        .values(&rows)
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn load_client_equipment(
+    conn: &mut DbConnection,
+    client_id: uuid::Uuid,
+) -> QueryResult<Vec<Equipment>> {
+    client_equipment::table
+        .inner_join(equipment::table)
+        .filter(client_equipment::client_id.eq(client_id))
+        .order_by(equipment::name.asc())
+        .select(Equipment::as_select())
+        .load(conn)
+}
+
+/// Replaces the equipment the client has and marks it as recorded, from then on an empty list means
+/// bodyweight only. Should be run inside a transaction.
+pub fn replace_client_equipment(
+    conn: &mut DbConnection,
+    client_id: uuid::Uuid,
+    equipment_ids: &[uuid::Uuid],
+) -> QueryResult<()> {
+    diesel::update(clients::table.find(client_id))
+        .set(clients::equipment_recorded.eq(true))
+        .execute(conn)?;
+
+    diesel::delete(client_equipment::table.filter(client_equipment::client_id.eq(client_id)))
+        .execute(conn)?;
+
+    let rows: Vec<_> = equipment_ids
+        .iter()
+        .map(|e_id| {
+            (
+                client_equipment::client_id.eq(client_id),
+                client_equipment::equipment_id.eq(*e_id),
+            )
+        })
+        .collect();
+
+    insert_into(client_equipment::table)
+        .values(&rows)
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Links a new catalog entry to the muscles and equipment its free-text fields name, the same way
+/// the migration parsed the existing entries. Names that aren't in the reference tables are
+/// skipped, only admins add to those.
+pub fn link_catalog_from_text(
+    conn: &mut DbConnection,
+    catalog_id: uuid::Uuid,
+    muscle_groups: &str,
+    equipment_text: &str,
+) -> QueryResult<()> {
+    let muscle_slugs = parse_slugs(muscle_groups);
+
+    let known_muscles: Vec<(uuid::Uuid, String)> = muscles::table
+        .filter(muscles::slug.eq_any(&muscle_slugs))
+        .select((muscles::id, muscles::slug))
+        .load(conn)?;
+
+    let mut primary_ids = vec![];
+    let mut secondary_ids = vec![];
+
+    for (idx, muscle_slug) in muscle_slugs.iter().enumerate() {
+        let Some((m_id, _)) = known_muscles.iter().find(|(_, s)| s == muscle_slug) else {
+            continue;
+        };
+
+        if primary_ids.contains(m_id) || secondary_ids.contains(m_id) {
+            continue;
+        }
+
+        if idx == 0 {
+            primary_ids.push(*m_id);
+        } else {
+            secondary_ids.push(*m_id);
+        }
+    }
+
+    replace_catalog_muscles(
+        conn,
+        catalog_id,
+        &SetCatalogMuscles {
+            primary_ids,
+            secondary_ids,
+        },
+    )?;
+
+    let equipment_ids: Vec<uuid::Uuid> = equipment::table
+        .filter(equipment::slug.eq_any(parse_slugs(equipment_text)))
+        .select(equipment::id)
+        .load(conn)?;
+
+    replace_catalog_equipment(conn, catalog_id, &equipment_ids)
+}
+
+/// Week, muscle, role and sets of every exercise of the workouts that trains a muscle.
+pub fn load_volume_rows(
+    conn: &mut DbConnection,
+    workout_ids: &[uuid::Uuid],
+) -> QueryResult<Vec<(i32, Muscle, MuscleRoles, i32)>> {
+    exercises::table
+        .inner_join(workouts::table)
+        .inner_join(
+            catalog_muscles::table.on(catalog_muscles::catalog_id.eq(exercises::catalog_id)),
+        )
+        .inner_join(muscles::table.on(muscles::id.eq(catalog_muscles::muscle_id)))
+        .filter(exercises::workout_id.eq_any(workout_ids))
+        .filter(exercises::archived_at.is_null())
+        .order_by((
+            workouts::week.asc(),
+            workouts::sequence.asc(),
+            exercises::sequence.asc(),
+        ))
+        .select((
+            workouts::week,
+            Muscle::as_select(),
+            catalog_muscles::role,
+            exercises::sets,
+        ))
+        .load(conn)
+}
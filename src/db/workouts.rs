@@ -0,0 +1,42 @@
+use super::{
+    models::{
+        exercise::{Exercise, ExerciseWithCatalog},
+        workout::{Workout, WorkoutWithExercises},
+        workout_block::WorkoutBlock,
+    },
+    DbConnection,
+};
+use crate::schema::{exercise_catalog, exercises, workout_blocks};
+use diesel::prelude::*;
+
+/// Loads the blocks and the exercises (with their catalog entries) of the workouts, both ordered
+/// by sequence. Archived exercises are left out, the workouts keep their order.
+pub fn with_exercises(
+    conn: &mut DbConnection,
+    workouts: Vec<Workout>,
+) -> QueryResult<Vec<WorkoutWithExercises>> {
+    let blocks = WorkoutBlock::belonging_to(&workouts)
+        .order((workout_blocks::sequence.asc(), workout_blocks::id.asc()))
+        .select(WorkoutBlock::as_select())
+        .load::<WorkoutBlock>(conn)?
+        .grouped_by(&workouts);
+
+    let exercises = Exercise::belonging_to(&workouts)
+        .inner_join(exercise_catalog::table)
+        .filter(exercises::archived_at.is_null())
+        .order(exercises::sequence.asc())
+        .select(ExerciseWithCatalog::as_select())
+        .load::<ExerciseWithCatalog>(conn)?
+        .grouped_by(&workouts);
+
+    Ok(workouts
+        .into_iter()
+        .zip(blocks)
+        .zip(exercises)
+        .map(|((workout, blocks), exercises)| WorkoutWithExercises {
+            workout,
+            blocks,
+            exercises,
+        })
+        .collect())
+}
@@ -78,6 +78,50 @@ pub fn custom(status: StatusCode, detail: impl Into<Cow<'static, str>>) -> Boxed
     })
 }
 
+//////////////////////////////// FieldErrors
+/// Validation error pointing at a field of the request body, `path` uses the same notation as
+/// serde, e.g. `workouts[1].exercises[0].name`.
+#[derive(Debug, Clone, serde::Serialize, PartialEq)]
+pub struct FieldError {
+    pub path: String,
+    pub detail: String,
+}
+
+impl FieldError {
+    pub fn new(path: impl Into<String>, detail: impl Into<String>) -> FieldError {
+        FieldError {
+            path: path.into(),
+            detail: detail.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FieldErrors {
+    errors: Vec<FieldError>,
+}
+
+impl AppError for FieldErrors {
+    fn response(&self) -> axum::response::Response {
+        let json = serde_json::json!({ "errors": self.errors });
+        (StatusCode::BAD_REQUEST, Json(json)).into_response()
+    }
+}
+
+impl fmt::Display for FieldErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for error in &self.errors {
+            writeln!(f, "{}: {}", error.path, error.detail)?;
+        }
+        Ok(())
+    }
+}
+
+/// 400 listing every invalid field at once.
+pub fn field_errors(errors: Vec<FieldError>) -> BoxedAppError {
+    Box::new(FieldErrors { errors })
+}
+
 pub fn unauthorized() -> BoxedAppError {
     custom(
         StatusCode::UNAUTHORIZED,
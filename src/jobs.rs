@@ -0,0 +1,86 @@
+//! Background jobs that run alongside the API server.
+
+use crate::{
+    db::{programs::purge_archived, reminders::send_workout_alerts},
+    server::AppState,
+};
+use std::{sync::Arc, time::Duration};
+
+pub fn spawn_jobs(state: Arc<AppState>) {
+    if state.settings.retention.enabled {
+        tokio::spawn(retention_job(state.clone()));
+    }
+
+    if state.settings.reminders.enabled {
+        tokio::spawn(reminders_job(state.clone()));
+    }
+}
+
+/// Purges archived programs, workouts and exercises once they are older than the retention
+/// period.
+async fn retention_job(state: Arc<AppState>) {
+    let retention = state.settings.retention.clone();
+
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(retention.interval_minutes.max(1) * 60));
+
+    loop {
+        interval.tick().await;
+
+        let state = state.clone();
+
+        let res = tokio::task::spawn_blocking(move || {
+            let archived_before =
+                chrono::Utc::now() - chrono::Duration::days(retention.archived_days);
+
+            purge_archived(&mut state.db_pool.get_conn(), archived_before)
+        })
+        .await;
+
+        match res {
+            Ok(Ok(purged)) => tracing::info!(
+                programs = purged.programs,
+                workouts = purged.workouts,
+                exercises = purged.exercises,
+                "Purged archived rows"
+            ),
+            Ok(Err(e)) => tracing::error!("Failed to purge archived rows: {e}"),
+            Err(e) => tracing::error!("Retention job panicked: {e}"),
+        }
+    }
+}
+
+/// Sends workout reminders and missed-workout notices, see `send_workout_alerts`.
+async fn reminders_job(state: Arc<AppState>) {
+    let reminders = state.settings.reminders.clone();
+
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(reminders.interval_minutes.max(1) * 60));
+
+    loop {
+        interval.tick().await;
+
+        let state = state.clone();
+        let reminders = reminders.clone();
+
+        let res = tokio::task::spawn_blocking(move || {
+            send_workout_alerts(
+                &mut state.db_pool.get_conn(),
+                &reminders,
+                chrono::Utc::now(),
+            )
+        })
+        .await;
+
+        match res {
+            Ok(Ok(summary)) => tracing::info!(
+                reminders = summary.reminders,
+                missed = summary.missed,
+                flagged_clients = summary.flagged_clients,
+                "Sent workout alerts"
+            ),
+            Ok(Err(e)) => tracing::error!("Failed to send workout alerts: {e}"),
+            Err(e) => tracing::error!("Reminders job panicked: {e}"),
+        }
+    }
+}
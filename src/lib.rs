@@ -2,6 +2,7 @@ pub mod api;
 pub mod auth;
 pub mod db;
 pub mod error;
+pub mod jobs;
 pub mod pagination;
 pub mod schema;
 pub mod server;
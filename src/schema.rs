@@ -1,6 +1,18 @@
 // @generated automatically by Diesel CLI.
 
 pub mod sql_types {
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "block_types"))]
+    pub struct BlockTypes;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "collaborator_roles"))]
+    pub struct CollaboratorRoles;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "discomfort_kinds"))]
+    pub struct DiscomfortKinds;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "intensity_choices"))]
     pub struct IntensityChoices;
@@ -9,9 +21,37 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "invite_states"))]
     pub struct InviteStates;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "movement_patterns"))]
+    pub struct MovementPatterns;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "muscle_roles"))]
+    pub struct MuscleRoles;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "price_tiers"))]
+    pub struct PriceTiers;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "program_levels"))]
+    pub struct ProgramLevels;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "record_kinds"))]
+    pub struct RecordKinds;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "tag_categories"))]
+    pub struct TagCategories;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "user_type"))]
     pub struct UserType;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "workout_alert_kinds"))]
+    pub struct WorkoutAlertKinds;
 }
 
 diesel::table! {
@@ -23,6 +63,24 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    catalog_equipment (catalog_id, equipment_id) {
+        catalog_id -> Uuid,
+        equipment_id -> Uuid,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::MuscleRoles;
+
+    catalog_muscles (catalog_id, muscle_id) {
+        catalog_id -> Uuid,
+        muscle_id -> Uuid,
+        role -> MuscleRoles,
+    }
+}
+
 diesel::table! {
     certifications (id) {
         id -> Uuid,
@@ -34,6 +92,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::CollaboratorRoles;
+
+    client_collaborators (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        client_id -> Uuid,
+        user_id -> Uuid,
+        granted_by -> Nullable<Uuid>,
+        role -> CollaboratorRoles,
+    }
+}
+
+diesel::table! {
+    client_equipment (client_id, equipment_id) {
+        client_id -> Uuid,
+        equipment_id -> Uuid,
+    }
+}
+
 diesel::table! {
     client_forms (id) {
         id -> Uuid,
@@ -68,30 +147,52 @@ diesel::table! {
         status -> Varchar,
         invite -> InviteStates,
         accepted_invite_at -> Nullable<Timestamptz>,
+        #[max_length = 64]
+        calendar_token -> Nullable<Varchar>,
+        equipment_recorded -> Bool,
+    }
+}
+
+diesel::table! {
+    comments (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        edited_at -> Nullable<Timestamptz>,
+        deleted_at -> Nullable<Timestamptz>,
+        workout_id -> Uuid,
+        exercise_id -> Nullable<Uuid>,
+        parent_id -> Nullable<Uuid>,
+        author_id -> Uuid,
+        body -> Text,
+    }
+}
+
+diesel::table! {
+    equipment (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        #[max_length = 50]
+        name -> Varchar,
+        #[max_length = 60]
+        slug -> Varchar,
     }
 }
 
 diesel::table! {
     use diesel::sql_types::*;
+    use super::sql_types::MovementPatterns;
     use super::sql_types::IntensityChoices;
 
-    exercises (id) {
+    exercise_catalog (id) {
         id -> Uuid,
         created_at -> Timestamptz,
-        workout_id -> Nullable<Uuid>,
         owner_id -> Nullable<Uuid>,
         #[max_length = 50]
         name -> Varchar,
+        #[max_length = 60]
+        slug -> Varchar,
         #[max_length = 255]
         description -> Varchar,
-        #[max_length = 50]
-        duration -> Varchar,
-        #[max_length = 50]
-        reps -> Varchar,
-        sets -> Int4,
-        #[max_length = 50]
-        rest_period -> Varchar,
-        intensity -> IntensityChoices,
         #[max_length = 255]
         equipment -> Varchar,
         #[max_length = 255]
@@ -102,9 +203,61 @@ diesel::table! {
         video -> Varchar,
         #[max_length = 500]
         instructions -> Varchar,
+        movement_pattern -> Nullable<MovementPatterns>,
+        intensity -> Nullable<IntensityChoices>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::DiscomfortKinds;
+
+    exercise_discomfort (id) {
+        id -> Uuid,
+        feedback_id -> Uuid,
+        exercise_id -> Uuid,
+        kind -> DiscomfortKinds,
+        #[max_length = 255]
+        note -> Varchar,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::IntensityChoices;
+
+    exercises (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        workout_id -> Nullable<Uuid>,
+        owner_id -> Nullable<Uuid>,
+        #[max_length = 50]
+        duration -> Varchar,
+        #[max_length = 50]
+        reps -> Varchar,
+        sets -> Int4,
+        #[max_length = 50]
+        rest_period -> Varchar,
+        intensity -> IntensityChoices,
         sequence -> Int4,
         #[max_length = 255]
         slug -> Varchar,
+        load -> Nullable<Float8>,
+        #[max_length = 10]
+        load_unit -> Varchar,
+        archived_at -> Nullable<Timestamptz>,
+        catalog_id -> Uuid,
+        reps_min -> Nullable<Int4>,
+        reps_max -> Nullable<Int4>,
+        amrap -> Bool,
+        duration_seconds -> Nullable<Int4>,
+        tut_seconds -> Nullable<Int4>,
+        #[max_length = 20]
+        tempo -> Varchar,
+        distance_meters -> Nullable<Float8>,
+        rest_seconds -> Nullable<Int4>,
+        percent_1rm -> Nullable<Float8>,
+        block_id -> Nullable<Uuid>,
     }
 }
 
@@ -121,6 +274,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    muscles (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        #[max_length = 50]
+        name -> Varchar,
+        #[max_length = 60]
+        slug -> Varchar,
+    }
+}
+
 diesel::table! {
     notifications (id) {
         id -> Uuid,
@@ -140,9 +304,91 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::RecordKinds;
+
+    personal_records (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        user_id -> Uuid,
+        catalog_id -> Uuid,
+        set_log_id -> Uuid,
+        kind -> RecordKinds,
+        reps -> Int4,
+        value -> Float8,
+        previous_value -> Nullable<Float8>,
+        achieved_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::PriceTiers;
+
+    program_claims (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        program_id -> Nullable<Uuid>,
+        user_id -> Uuid,
+        copy_program_id -> Nullable<Uuid>,
+        price_tier -> PriceTiers,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::CollaboratorRoles;
+
+    program_collaborators (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        program_id -> Uuid,
+        user_id -> Uuid,
+        granted_by -> Nullable<Uuid>,
+        role -> CollaboratorRoles,
+    }
+}
+
+diesel::table! {
+    program_progress (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        program_id -> Uuid,
+        next_workout_id -> Nullable<Uuid>,
+        total_workouts -> Int4,
+        completed_workouts -> Int4,
+        current_streak -> Int4,
+        longest_streak -> Int4,
+        last_completed_at -> Nullable<Timestamptz>,
+        weeks -> Jsonb,
+    }
+}
+
+diesel::table! {
+    program_revisions (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        program_id -> Uuid,
+        author_id -> Nullable<Uuid>,
+        revision -> Int4,
+        snapshot -> Jsonb,
+    }
+}
+
+diesel::table! {
+    program_tags (program_id, tag_id) {
+        program_id -> Uuid,
+        tag_id -> Uuid,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::IntensityChoices;
+    use super::sql_types::PriceTiers;
+    use super::sql_types::ProgramLevels;
 
     programs (id) {
         id -> Uuid,
@@ -167,6 +413,45 @@ diesel::table! {
         client_id -> Nullable<Uuid>,
         active -> Bool,
         complete -> Bool,
+        start_date -> Nullable<Date>,
+        training_days -> Array<Nullable<Int4>>,
+        published -> Bool,
+        published_at -> Nullable<Timestamptz>,
+        price_tier -> PriceTiers,
+        level -> ProgramLevels,
+        archived_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    set_logs (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        session_id -> Uuid,
+        exercise_id -> Uuid,
+        set_number -> Int4,
+        reps -> Int4,
+        load -> Nullable<Float8>,
+        #[max_length = 10]
+        load_unit -> Varchar,
+        rpe -> Nullable<Float8>,
+        notes -> Text,
+        duration_seconds -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::TagCategories;
+
+    tags (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        category -> TagCategories,
+        #[max_length = 50]
+        name -> Varchar,
+        #[max_length = 60]
+        slug -> Varchar,
     }
 }
 
@@ -208,6 +493,39 @@ diesel::table! {
         #[max_length = 255]
         goals -> Varchar,
         weight -> Int4,
+        #[max_length = 64]
+        timezone -> Varchar,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::WorkoutAlertKinds;
+
+    workout_alerts (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        workout_id -> Uuid,
+        kind -> WorkoutAlertKinds,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::BlockTypes;
+
+    workout_blocks (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        workout_id -> Uuid,
+        block_type -> BlockTypes,
+        #[max_length = 50]
+        name -> Varchar,
+        sequence -> Int4,
+        rounds -> Int4,
+        work_seconds -> Nullable<Int4>,
+        rest_seconds -> Nullable<Int4>,
+        round_rest_seconds -> Nullable<Int4>,
     }
 }
 
@@ -222,16 +540,56 @@ diesel::table! {
         hk_location_type -> Varchar,
         #[max_length = 50]
         hk_workout_activity_type -> Varchar,
-        #[max_length = 50]
-        hk_workout_duration_secs -> Varchar,
         hk_workout_start_date -> Nullable<Timestamptz>,
         hk_workout_end_date -> Nullable<Timestamptz>,
+        hk_workout_duration_secs -> Nullable<Float8>,
+        hk_workout_distance_meters -> Nullable<Float8>,
+        hk_workout_avg_heart_rate_bpm -> Nullable<Float8>,
+        hk_workout_active_energy_burned_kcal -> Nullable<Float8>,
+    }
+}
+
+diesel::table! {
+    workout_data_unparsed (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        workout_data_id -> Uuid,
         #[max_length = 50]
-        hk_workout_distance -> Varchar,
-        #[max_length = 50]
-        hk_workout_avg_heart_rate -> Varchar,
+        field -> Varchar,
         #[max_length = 50]
-        hk_workout_active_energy_burned -> Varchar,
+        value -> Varchar,
+    }
+}
+
+diesel::table! {
+    workout_feedback (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        workout_id -> Uuid,
+        user_id -> Uuid,
+        session_rpe -> Int4,
+        enjoyment -> Int4,
+        notes -> Text,
+    }
+}
+
+diesel::table! {
+    workout_sessions (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        workout_id -> Uuid,
+        user_id -> Uuid,
+        started_at -> Timestamptz,
+        finished_at -> Nullable<Timestamptz>,
+        notes -> Text,
+    }
+}
+
+diesel::table! {
+    workout_tags (workout_id, tag_id) {
+        workout_id -> Uuid,
+        tag_id -> Uuid,
     }
 }
 
@@ -265,30 +623,93 @@ diesel::table! {
         #[max_length = 255]
         slug -> Varchar,
         complete -> Bool,
+        scheduled_date -> Nullable<Date>,
+        completed_at -> Nullable<Timestamptz>,
+        archived_at -> Nullable<Timestamptz>,
     }
 }
 
+diesel::joinable!(catalog_equipment -> equipment (equipment_id));
+diesel::joinable!(catalog_equipment -> exercise_catalog (catalog_id));
+diesel::joinable!(catalog_muscles -> exercise_catalog (catalog_id));
+diesel::joinable!(catalog_muscles -> muscles (muscle_id));
 diesel::joinable!(certifications -> users (user_id));
+diesel::joinable!(client_collaborators -> clients (client_id));
+diesel::joinable!(client_equipment -> clients (client_id));
+diesel::joinable!(client_equipment -> equipment (equipment_id));
 diesel::joinable!(client_forms -> clients (client_id));
+diesel::joinable!(comments -> exercises (exercise_id));
+diesel::joinable!(comments -> users (author_id));
+diesel::joinable!(comments -> workouts (workout_id));
+diesel::joinable!(exercise_catalog -> users (owner_id));
+diesel::joinable!(exercise_discomfort -> exercises (exercise_id));
+diesel::joinable!(exercise_discomfort -> workout_feedback (feedback_id));
+diesel::joinable!(exercises -> exercise_catalog (catalog_id));
 diesel::joinable!(exercises -> users (owner_id));
+diesel::joinable!(exercises -> workout_blocks (block_id));
 diesel::joinable!(exercises -> workouts (workout_id));
 diesel::joinable!(feedback -> users (user_id));
+diesel::joinable!(personal_records -> exercise_catalog (catalog_id));
+diesel::joinable!(personal_records -> set_logs (set_log_id));
+diesel::joinable!(personal_records -> users (user_id));
+diesel::joinable!(program_claims -> users (user_id));
+diesel::joinable!(program_collaborators -> programs (program_id));
+diesel::joinable!(program_progress -> programs (program_id));
+diesel::joinable!(program_progress -> workouts (next_workout_id));
+diesel::joinable!(program_revisions -> programs (program_id));
+diesel::joinable!(program_revisions -> users (author_id));
+diesel::joinable!(program_tags -> programs (program_id));
+diesel::joinable!(program_tags -> tags (tag_id));
 diesel::joinable!(programs -> clients (client_id));
 diesel::joinable!(programs -> users (owner_id));
+diesel::joinable!(set_logs -> exercises (exercise_id));
+diesel::joinable!(set_logs -> workout_sessions (session_id));
+diesel::joinable!(workout_alerts -> workouts (workout_id));
+diesel::joinable!(workout_blocks -> workouts (workout_id));
 diesel::joinable!(workout_data -> workouts (workout_id));
+diesel::joinable!(workout_data_unparsed -> workout_data (workout_data_id));
+diesel::joinable!(workout_feedback -> users (user_id));
+diesel::joinable!(workout_feedback -> workouts (workout_id));
+diesel::joinable!(workout_sessions -> users (user_id));
+diesel::joinable!(workout_sessions -> workouts (workout_id));
+diesel::joinable!(workout_tags -> tags (tag_id));
+diesel::joinable!(workout_tags -> workouts (workout_id));
 diesel::joinable!(workouts -> programs (program_id));
 diesel::joinable!(workouts -> users (owner_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     betacode,
+    catalog_equipment,
+    catalog_muscles,
     certifications,
+    client_collaborators,
+    client_equipment,
     client_forms,
     clients,
+    comments,
+    equipment,
+    exercise_catalog,
+    exercise_discomfort,
     exercises,
     feedback,
+    muscles,
     notifications,
+    personal_records,
+    program_claims,
+    program_collaborators,
+    program_progress,
+    program_revisions,
+    program_tags,
     programs,
+    set_logs,
+    tags,
     users,
+    workout_alerts,
+    workout_blocks,
     workout_data,
+    workout_data_unparsed,
+    workout_feedback,
+    workout_sessions,
+    workout_tags,
     workouts,
 );
@@ -1,9 +1,11 @@
 use crate::{
     api::{
         common::{api_fallback, healthcheck},
+        feeds::feed_routes,
         v1_routes,
     },
     auth::auth_middleware,
+    jobs::spawn_jobs,
     telemetry,
 };
 use anyhow::Result;
@@ -109,6 +111,7 @@ fn build_router(state: Arc<AppState>) -> Router {
     axum::Router::new()
         .nest("/v1", v1_routes())
         .route_layer(from_fn_with_state(state.clone(), auth_middleware))
+        .nest("/feeds", feed_routes())
         .layer(trace_layer)
         .with_state(Arc::clone(&state))
         .route("/", get(healthcheck))
@@ -138,6 +141,8 @@ pub async fn start() -> Result<()> {
 
     tracing::info!("Nautilus Ready on port {port}");
 
+    spawn_jobs(state.clone());
+
     let app = build_router(state);
 
     // let listener = tokio::net::TcpListener::bind(format!("{}:{}", "0.0.0.0", "5050"))
@@ -26,11 +26,63 @@ pub struct ServerConfig {
     pub allowed_origins: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[allow(unused)]
+pub struct RetentionConfig {
+    pub enabled: bool,
+    // archived programs, workouts and exercises are purged after this many days
+    pub archived_days: i64,
+    pub interval_minutes: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        RetentionConfig {
+            enabled: true,
+            archived_days: 30,
+            interval_minutes: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[allow(unused)]
+pub struct RemindersConfig {
+    pub enabled: bool,
+    pub interval_minutes: u64,
+    // clients are reminded this many hours before the start of the (local) day a workout is planned
+    pub lead_hours: i64,
+    // a workout counts as missed this many hours after the end of the day it was planned for
+    pub grace_hours: i64,
+    // missed workouts are only reported to trainers for this many hours, older ones (e.g. from
+    // before the job ran for the first time) still count towards the attention flag
+    pub missed_window_hours: i64,
+    // clients with this many missed workouts in their active programs are flagged for attention
+    pub attention_missed: i64,
+}
+
+impl Default for RemindersConfig {
+    fn default() -> Self {
+        RemindersConfig {
+            enabled: true,
+            interval_minutes: 15,
+            lead_hours: 12,
+            grace_hours: 24,
+            missed_window_hours: 24,
+            attention_missed: 2,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[allow(unused)]
 pub struct Settings {
     pub server: ServerConfig,
     pub tracing: TracingConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub reminders: RemindersConfig,
     pub environment: String,
     pub auth_domain: String,
     pub auth_audience: String,
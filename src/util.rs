@@ -1,8 +1,18 @@
+pub mod calendar;
 pub mod extractors;
+pub mod health_units;
+pub mod prescription;
+pub mod progression;
+pub mod substitution;
 #[cfg(test)]
 pub mod tests;
+use lazy_static::lazy_static;
 use regex::Regex;
 
+lazy_static! {
+    static ref NAME_SLUG_SEPARATOR: Regex = Regex::new(r"[^a-z0-9]+").unwrap();
+}
+
 pub fn format_slug(base: String) -> String {
     let s = base.replace(' ', "-");
     let re = Regex::new(r"[^a-zA-Z0-9-]").unwrap();
@@ -16,3 +26,78 @@ pub fn clean_username(username: String) -> String {
     let res = re.replace_all(&s, "");
     res.to_lowercase().to_string()
 }
+
+/// Slug of a tag, muscle, equipment or catalog name, lowercase words separated by a single `-`.
+/// Their migrations backfilled the existing slugs the same way.
+pub fn name_slug(name: &str) -> String {
+    NAME_SLUG_SEPARATOR
+        .replace_all(&name.trim().to_lowercase(), "-")
+        .trim_matches('-')
+        .to_string()
+}
+
+/// Splits a comma separated query parameter into slugs, see `name_slug`.
+pub fn parse_slugs(param: &str) -> Vec<String> {
+    param
+        .split(',')
+        .map(name_slug)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Slug with a random suffix, for rows that are copied and would otherwise collide with the
+/// original's slug.
+pub fn random_slug(base: &str) -> String {
+    format!(
+        "{}-{}",
+        format_slug(base.to_string()),
+        rand::random::<u32>()
+    )
+}
+
+/// `ILIKE` pattern matching `search` anywhere, with the LIKE wildcards escaped so they're matched
+/// literally.
+pub fn contains_pattern(search: &str) -> String {
+    format!(
+        "%{}%",
+        search
+            .trim()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    )
+}
+
+/// First id that shows up more than once, used to validate bulk reorder requests.
+pub fn find_duplicate(ids: &[uuid::Uuid]) -> Option<uuid::Uuid> {
+    let mut seen = std::collections::HashSet::new();
+
+    ids.iter().find(|id| !seen.insert(**id)).copied()
+}
+
+/// Deserializes a field that can be left out, `null` or a value into `None`, `Some(None)` and
+/// `Some(Some(_))`, so patches can clear nullable columns. Use with `#[serde(default)]`.
+pub fn double_option<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: serde::Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    serde::Deserialize::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod slug_tests {
+    use super::*;
+
+    #[test]
+    fn test_name_slug() {
+        assert_eq!(name_slug("  Push/Pull bands "), "push-pull-bands");
+        assert_eq!(name_slug(" Front  Delts "), "front-delts");
+        assert_eq!(name_slug("HIIT!"), "hiit");
+        assert_eq!(
+            parse_slugs("Barbell, ,bench"),
+            vec!["barbell".to_string(), "bench".to_string()]
+        );
+        assert!(parse_slugs(" , ").is_empty());
+    }
+}
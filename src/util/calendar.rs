@@ -0,0 +1,391 @@
+use crate::db::models::{workout::Workout, workout_alert::WorkoutAlertKinds};
+use chrono::{DateTime, Datelike, Days, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Turns the program's `training_days` (ISO weekdays, 1 = Monday ... 7 = Sunday) into a sorted
+/// list of weekdays. An empty list means the client can train any day of the week.
+pub fn training_weekdays(training_days: &[Option<i32>]) -> Vec<u32> {
+    let mut days: Vec<u32> = training_days
+        .iter()
+        .flatten()
+        .filter(|d| (1..=7).contains(*d))
+        .map(|d| *d as u32)
+        .collect();
+
+    days.sort_unstable();
+    days.dedup();
+
+    if days.is_empty() {
+        return (1..=7).collect();
+    }
+
+    days
+}
+
+pub fn valid_training_days(training_days: &[Option<i32>]) -> bool {
+    training_days
+        .iter()
+        .all(|d| d.is_some_and(|d| (1..=7).contains(&d)))
+}
+
+/// Highest week a workout can be planned in, ten years of weekly training.
+pub const MAX_WORKOUT_WEEK: i32 = 520;
+
+/// First date on or after `date` that falls on one of the training weekdays, `None` past the end
+/// of the calendar.
+pub fn next_training_day(date: NaiveDate, weekdays: &[u32]) -> Option<NaiveDate> {
+    let mut candidate = date;
+
+    while !weekdays.contains(&candidate.weekday().number_from_monday()) {
+        candidate = candidate.succ_opt()?;
+    }
+
+    Some(candidate)
+}
+
+/// Resolves every workout to a calendar date.
+///
+/// `workouts` must already be ordered by week and sequence. Week N starts `7 * (N - 1)` days
+/// after the program's start date and its workouts are placed on the training days of that
+/// week in order, spilling into the following days if a week has more workouts than training
+/// days. A workout's `scheduled_date` always wins over the derived date, and the workouts after
+/// it are pushed so they never land on or before it.
+///
+/// Workouts that would land past the end of the calendar get `None` and don't push the others.
+pub fn plan_workout_dates(
+    start_date: NaiveDate,
+    training_days: &[Option<i32>],
+    workouts: &[Workout],
+) -> Vec<Option<NaiveDate>> {
+    let weekdays = training_weekdays(training_days);
+    let mut last_date: Option<NaiveDate> = None;
+
+    workouts
+        .iter()
+        .map(|workout| {
+            let date = match workout.scheduled_date {
+                Some(scheduled_date) => scheduled_date,
+                None => {
+                    let weeks_in = u64::try_from(workout.week.max(1) - 1).ok()?;
+                    let week_start = start_date.checked_add_days(Days::new(weeks_in * 7))?;
+
+                    let earliest = match last_date {
+                        Some(last) if last >= week_start => last.succ_opt()?,
+                        _ => week_start,
+                    };
+
+                    next_training_day(earliest, &weekdays)?
+                }
+            };
+
+            last_date = Some(date);
+
+            Some(date)
+        })
+        .collect()
+}
+
+/// Moves the planned dates of the workouts that are not complete forward so the first of them
+/// lands on (or after) `from`. The gaps between them are kept, each date is snapped to the next
+/// training day and never lands on or before the previous one.
+///
+/// Returns `None` for workouts that keep their current date.
+pub fn reschedule_from(
+    from: NaiveDate,
+    training_days: &[Option<i32>],
+    workouts: &[Workout],
+    planned: &[NaiveDate],
+) -> Vec<Option<NaiveDate>> {
+    let weekdays = training_weekdays(training_days);
+
+    let first_incomplete = workouts
+        .iter()
+        .zip(planned)
+        .find(|(workout, _)| !workout.complete)
+        .map(|(_, date)| *date);
+
+    let shift = match first_incomplete {
+        Some(first) if first < from => from - first,
+        _ => return vec![None; workouts.len()],
+    };
+
+    let mut last_date: Option<NaiveDate> = None;
+
+    workouts
+        .iter()
+        .zip(planned)
+        .map(|(workout, date)| {
+            if workout.complete {
+                return None;
+            }
+
+            let mut new_date = next_training_day(date.checked_add_signed(shift)?, &weekdays)?;
+
+            if let Some(last) = last_date {
+                if new_date <= last {
+                    new_date = next_training_day(last.succ_opt()?, &weekdays)?;
+                }
+            }
+
+            last_date = Some(new_date);
+
+            Some(new_date)
+        })
+        .collect()
+}
+
+pub struct CalendarEvent {
+    pub uid: String,
+    pub date: NaiveDate,
+    pub summary: String,
+    pub description: String,
+}
+
+/// Renders all day events into an iCalendar (RFC 5545) document.
+pub fn render_ics(
+    calendar_name: &str,
+    events: &[CalendarEvent],
+    stamp: chrono::DateTime<chrono::Utc>,
+) -> String {
+    let dtstamp = stamp.format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut lines: Vec<String> = vec![
+        "BEGIN:VCALENDAR".into(),
+        "VERSION:2.0".into(),
+        "PRODID:-//Trainton//Nautilus//EN".into(),
+        "CALSCALE:GREGORIAN".into(),
+        "METHOD:PUBLISH".into(),
+        format!("X-WR-CALNAME:{}", escape_text(calendar_name)),
+    ];
+
+    for event in events {
+        lines.push("BEGIN:VEVENT".into());
+        lines.push(format!("UID:{}", event.uid));
+        lines.push(format!("DTSTAMP:{dtstamp}"));
+        lines.push(format!(
+            "DTSTART;VALUE=DATE:{}",
+            event.date.format("%Y%m%d")
+        ));
+        lines.push(format!(
+            "DTEND;VALUE=DATE:{}",
+            (event.date + Duration::days(1)).format("%Y%m%d")
+        ));
+        lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+        if !event.description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape_text(&event.description)));
+        }
+        lines.push("END:VEVENT".into());
+    }
+
+    lines.push("END:VCALENDAR".into());
+
+    lines
+        .iter()
+        .map(|line| fold_line(line))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+        + "\r\n"
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Content lines can't be longer than 75 octets, longer ones continue on the next line after a
+/// single space.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut line_len = 0;
+
+    for c in line.chars() {
+        if line_len + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            line_len = 1;
+        }
+        folded.push(c);
+        line_len += c.len_utf8();
+    }
+
+    folded
+}
+
+/// Start of the day in the given timezone. Falls back to reading midnight as UTC on the rare
+/// days where a DST change skips midnight.
+fn local_day_start(date: NaiveDate, tz: Tz) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .map(|start| start.with_timezone(&Utc))
+        .unwrap_or_else(|| midnight.and_utc())
+}
+
+/// Which alert is due for an incomplete workout planned on `date`, in the client's timezone.
+///
+/// The reminder is due from `lead_hours` before the planned day starts until the day is over,
+/// the workout is missed once `grace_hours` have passed since the end of the day.
+pub fn due_workout_alert(
+    date: NaiveDate,
+    tz: Tz,
+    now: DateTime<Utc>,
+    lead_hours: i64,
+    grace_hours: i64,
+) -> Option<WorkoutAlertKinds> {
+    let day_start = local_day_start(date, tz);
+    let day_end = local_day_start(date.succ_opt()?, tz);
+
+    if now >= day_end + Duration::hours(grace_hours) {
+        Some(WorkoutAlertKinds::Missed)
+    } else if now >= day_start - Duration::hours(lead_hours) && now < day_end {
+        Some(WorkoutAlertKinds::Reminder)
+    } else {
+        None
+    }
+}
+
+/// Whether an incomplete workout planned on `date` became missed less than `window_hours` ago,
+/// older misses are no longer reported.
+pub fn missed_recently(
+    date: NaiveDate,
+    tz: Tz,
+    now: DateTime<Utc>,
+    grace_hours: i64,
+    window_hours: i64,
+) -> bool {
+    let Some(next_day) = date.succ_opt() else {
+        return false;
+    };
+    let missed_at = local_day_start(next_day, tz) + Duration::hours(grace_hours);
+
+    now >= missed_at && now < missed_at + Duration::hours(window_hours)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::IntensityChoices;
+
+    fn workout(week: i32, sequence: i32, complete: bool) -> Workout {
+        Workout {
+            id: uuid::Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            program_id: None,
+            owner_id: None,
+            name: format!("Week {week} Day {sequence}"),
+            description: String::new(),
+            duration: String::new(),
+            sequence,
+            week,
+            intensity: IntensityChoices::Low,
+            workout_type: String::new(),
+            equipment_needed: String::new(),
+            image: String::new(),
+            video: String::new(),
+            template: false,
+            slug: String::new(),
+            complete,
+            scheduled_date: None,
+            completed_at: None,
+            archived_at: None,
+        }
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_plan_and_reschedule_workout_dates() {
+        // Monday, Wednesday, Friday starting on Monday 2024-05-06
+        let days = vec![Some(1), Some(3), Some(5)];
+        let workouts = vec![
+            workout(1, 1, true),
+            workout(1, 2, false),
+            workout(2, 1, false),
+            workout(2, 2, false),
+        ];
+
+        let planned = plan_workout_dates(date(2024, 5, 6), &days, &workouts);
+
+        assert_eq!(
+            planned,
+            vec![
+                Some(date(2024, 5, 6)),
+                Some(date(2024, 5, 8)),
+                Some(date(2024, 5, 13)),
+                Some(date(2024, 5, 15))
+            ]
+        );
+
+        let planned: Vec<NaiveDate> = planned.into_iter().flatten().collect();
+
+        // client missed Wednesday, move everything left to start on Thursday
+        let moved = reschedule_from(date(2024, 5, 9), &days, &workouts, &planned);
+
+        assert_eq!(
+            moved,
+            vec![
+                None,
+                Some(date(2024, 5, 10)),
+                Some(date(2024, 5, 15)),
+                Some(date(2024, 5, 17))
+            ]
+        );
+
+        // a week past the end of the calendar leaves the workout out instead of panicking
+        let far = vec![workout(1, 1, false), workout(i32::MAX, 1, false)];
+
+        assert_eq!(
+            plan_workout_dates(date(2024, 5, 6), &days, &far),
+            vec![Some(date(2024, 5, 6)), None]
+        );
+    }
+
+    #[test]
+    fn test_due_workout_alert_uses_local_day() {
+        let tz: Tz = "America/Chicago".parse().unwrap();
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let due = |now| due_workout_alert(date(2024, 5, 8), tz, now, 12, 24);
+
+        // the local day starts at 05:00 UTC, reminders go out from noon the day before
+        assert_eq!(due(at("2024-05-07T16:59:00Z")), None);
+        assert_eq!(
+            due(at("2024-05-07T17:00:00Z")),
+            Some(WorkoutAlertKinds::Reminder)
+        );
+        assert_eq!(
+            due(at("2024-05-09T04:59:00Z")),
+            Some(WorkoutAlertKinds::Reminder)
+        );
+        // between the end of the day and the end of the grace period
+        assert_eq!(due(at("2024-05-09T05:00:00Z")), None);
+        assert_eq!(
+            due(at("2024-05-10T05:00:00Z")),
+            Some(WorkoutAlertKinds::Missed)
+        );
+
+        let recent = |now| missed_recently(date(2024, 5, 8), tz, now, 24, 24);
+        assert!(recent(at("2024-05-10T05:00:00Z")));
+        assert!(!recent(at("2024-05-11T05:00:00Z")));
+    }
+
+    #[test]
+    fn test_render_ics_escapes_and_folds() {
+        let events = vec![CalendarEvent {
+            uid: "abc@trainton.com".into(),
+            date: date(2024, 5, 6),
+            summary: "Legs, Glutes; Core".into(),
+            description: "x".repeat(100),
+        }];
+
+        let ics = render_ics("Training", &events, chrono::Utc::now());
+
+        assert!(ics.contains("SUMMARY:Legs\\, Glutes\\; Core\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20240506\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= 76));
+    }
+}
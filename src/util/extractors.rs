@@ -182,6 +182,13 @@ pub struct QueryHmExt(
     #[from_request(via(QueryExtractor))] pub std::collections::HashMap<String, String>,
 );
 
+impl QueryHmExt {
+    /// Archived rows are left out of listings unless `include_archived=true` is passed.
+    pub fn include_archived(&self) -> bool {
+        self.0.get("include_archived").map(String::as_str) == Some("true")
+    }
+}
+
 // We define our own `Path` extractor that customizes the error from `axum::extract::Path`
 pub struct QueryExtractor<T>(pub T);
 
@@ -0,0 +1,172 @@
+//! Units of the HealthKit measurements stored with a workout.
+//!
+//! Every measurement is stored as a number in a single unit, seconds, meters, beats per minute and
+//! kilocalories. Values can be sent as a number in that unit or as a string carrying its unit:
+//!
+//! - duration: `1830.5`, `30:30`, `1:02:03`, `30 min 30s`, `1.5 h`
+//! - distance: `5000`, `5 km`, `3.1 mi`, `400 yd`, `1200 ft`
+//! - heart rate: `142`, `142 bpm`, `142 count/min`
+//! - energy: `350`, `350 kcal`, `350 Cal`, `1464 kJ`
+//!
+//! Anything else doesn't parse rather than being guessed at. The migration that typed the columns
+//! uses the same rules.
+use regex::Regex;
+use serde::Deserialize;
+
+const METERS_PER_MILE: f64 = 1609.344;
+const METERS_PER_YARD: f64 = 0.9144;
+const METERS_PER_FOOT: f64 = 0.3048;
+const KJ_PER_KCAL: f64 = 4.184;
+
+/// A number in the unit of the field, or a string with its own unit.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Measurement {
+    Value(f64),
+    Text(String),
+}
+
+impl Measurement {
+    /// The value in the unit of the field. Blank strings are `None`, HealthKit leaves out what it
+    /// didn't measure.
+    pub fn normalize(
+        &self,
+        field: &str,
+        parse: fn(&str) -> Option<f64>,
+    ) -> Result<Option<f64>, String> {
+        match self {
+            Self::Value(value) => Ok(Some(*value)),
+            Self::Text(text) if text.trim().is_empty() => Ok(None),
+            Self::Text(text) => parse(text)
+                .map(Some)
+                .ok_or_else(|| format!("{field} has an unknown format or unit.")),
+        }
+    }
+}
+
+/// Sums `<amount> <unit>` pairs, `units` gives the factor of each unit. An empty unit is the unit
+/// of the field.
+fn parse_with_units(value: &str, units: &[(&[&str], f64)]) -> Option<f64> {
+    let value = value.trim().to_lowercase();
+    let re = Regex::new(r"(\d+(?:\.\d+)?)\s*([a-z/]*)").unwrap();
+
+    if value.is_empty() || !re.replace_all(&value, "").trim().is_empty() {
+        return None;
+    }
+
+    let mut total = 0.0;
+
+    for caps in re.captures_iter(&value) {
+        let amount: f64 = caps[1].parse().ok()?;
+        let (_, factor) = units.iter().find(|(names, _)| names.contains(&&caps[2]))?;
+
+        total += amount * factor;
+    }
+
+    Some(total)
+}
+
+/// Seconds, `1:02:03` and `30:30` are hours or minutes first.
+pub fn parse_duration_secs(value: &str) -> Option<f64> {
+    let value = value.trim();
+
+    if value.contains(':') {
+        let parts: Vec<&str> = value.split(':').collect();
+
+        if parts.len() > 3
+            || parts[1..]
+                .iter()
+                .any(|p| p.split('.').next().is_none_or(|whole| whole.len() != 2))
+        {
+            return None;
+        }
+
+        return parts.iter().try_fold(0.0, |total, part| {
+            let amount: f64 = part.parse().ok()?;
+            Some(total * 60.0 + amount)
+        });
+    }
+
+    parse_with_units(
+        value,
+        &[
+            (&["", "s", "sec", "secs", "second", "seconds"], 1.0),
+            (&["m", "min", "mins", "minute", "minutes"], 60.0),
+            (&["h", "hr", "hrs", "hour", "hours"], 3600.0),
+        ],
+    )
+}
+
+pub fn parse_distance_meters(value: &str) -> Option<f64> {
+    parse_with_units(
+        value,
+        &[
+            (&["", "m", "meter", "meters", "metre", "metres"], 1.0),
+            (&["km", "kilometer", "kilometers"], 1000.0),
+            (&["mi", "mile", "miles"], METERS_PER_MILE),
+            (&["yd", "yard", "yards"], METERS_PER_YARD),
+            (&["ft", "foot", "feet"], METERS_PER_FOOT),
+        ],
+    )
+}
+
+pub fn parse_heart_rate_bpm(value: &str) -> Option<f64> {
+    parse_with_units(value, &[(&["", "bpm", "count/min"], 1.0)])
+}
+
+/// Food and fitness "Calories" are kilocalories, so `cal` is too.
+pub fn parse_energy_kcal(value: &str) -> Option<f64> {
+    parse_with_units(
+        value,
+        &[
+            (&["", "kcal", "cal", "cals", "calories"], 1.0),
+            (&["kj"], 1.0 / KJ_PER_KCAL),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(parse_duration_secs("1830.5"), Some(1830.5));
+        assert_eq!(parse_duration_secs("30:30"), Some(1830.0));
+        assert_eq!(parse_duration_secs("1:02:03"), Some(3723.0));
+        assert_eq!(parse_duration_secs("30 min 30s"), Some(1830.0));
+        assert_eq!(parse_duration_secs("1.5 h"), Some(5400.0));
+        assert_eq!(parse_duration_secs("1:2"), None);
+        assert_eq!(parse_duration_secs("about an hour"), None);
+
+        assert_eq!(parse_distance_meters("5 km"), Some(5000.0));
+        assert_eq!(parse_distance_meters("1 mi"), Some(METERS_PER_MILE));
+        assert_eq!(parse_distance_meters(" 400 "), Some(400.0));
+        assert_eq!(parse_distance_meters("5 furlongs"), None);
+        assert_eq!(parse_distance_meters("5,2 km"), None);
+
+        assert_eq!(parse_heart_rate_bpm("142 count/min"), Some(142.0));
+        assert_eq!(parse_heart_rate_bpm("142 BPM"), Some(142.0));
+
+        assert_eq!(parse_energy_kcal("350 Cal"), Some(350.0));
+        assert!((parse_energy_kcal("1464 kJ").unwrap() - 349.9).abs() < 0.1);
+        assert_eq!(parse_energy_kcal(""), None);
+
+        assert_eq!(
+            Measurement::Text("3.1 mi".into()).normalize("distance", parse_distance_meters),
+            Ok(Some(3.1 * METERS_PER_MILE))
+        );
+        assert_eq!(
+            Measurement::Value(12.0).normalize("distance", parse_distance_meters),
+            Ok(Some(12.0))
+        );
+        assert_eq!(
+            Measurement::Text(" ".into()).normalize("distance", parse_distance_meters),
+            Ok(None)
+        );
+        assert_eq!(
+            Measurement::Text("far".into()).normalize("distance", parse_distance_meters),
+            Err("distance has an unknown format or unit.".to_string())
+        );
+    }
+}
@@ -0,0 +1,352 @@
+//! Typed form of the `reps`, `duration` and `rest_period` strings of an exercise.
+//!
+//! The strings stay the display form, the structured fields are always what parsing them gives.
+//! What the parser understands:
+//!
+//! - reps: `10`, `8-12`, `8 to 12`, `10+` and `AMRAP`/`max`/`failure`, optionally prefixed with
+//!   the number of sets (`3x8-12`), followed by a load (`@ 75%`) and a tempo (`tempo 3-1-1-0`)
+//! - duration: `45s`, `1 min 30s`, `1:30`, time under tension (`TUT 40s`) and distance
+//!   (`400m`, `1.5 km`, `1 mi`)
+//! - rest: like duration, a bare number is seconds (`90`, `90 sec`, `2 min`)
+//!
+//! `m` is always meters, minutes have to be spelled `min`.
+use diesel::prelude::*;
+use regex::Regex;
+
+const METERS_PER_MILE: f64 = 1609.344;
+
+#[derive(Debug, Clone, Default, PartialEq, Insertable)]
+#[diesel(table_name = crate::schema::exercises)]
+pub struct Prescription {
+    pub reps_min: Option<i32>,
+    pub reps_max: Option<i32>,
+    pub amrap: bool,
+    pub duration_seconds: Option<i32>,
+    pub tut_seconds: Option<i32>,
+    pub tempo: String,
+    pub distance_meters: Option<f64>,
+    pub rest_seconds: Option<i32>,
+    pub percent_1rm: Option<f64>,
+}
+
+impl Prescription {
+    /// Parses the legacy strings of an exercise. Also returns the number of sets when the reps
+    /// carry it (`3x8-12`). Parts that aren't understood are left empty.
+    pub fn parse(reps: &str, duration: &str, rest_period: &str) -> (Option<i32>, Self) {
+        let mut reps = reps.trim().to_lowercase();
+        let mut duration = duration.trim().to_lowercase();
+        let mut res = Self::default();
+
+        let sets_re = Regex::new(r"^(\d+)\s*[x×]\s*").unwrap();
+        let sets = take(&sets_re, &mut reps).and_then(|c| c[0].parse().ok());
+
+        let percent_re = Regex::new(r"@\s*(\d+(?:\.\d+)?)\s*%").unwrap();
+        res.percent_1rm = take(&percent_re, &mut reps).and_then(|c| c[0].parse().ok());
+
+        let tempo_re = Regex::new(r"tempo\s*:?\s*([0-9x])-?([0-9x])-?([0-9x])-?([0-9x])").unwrap();
+        res.tempo = take(&tempo_re, &mut reps)
+            .or_else(|| take(&tempo_re, &mut duration))
+            .map(|c| c.join("-"))
+            .unwrap_or_default();
+
+        let distance_re = Regex::new(r"(\d+(?:\.\d+)?)\s*(km|mi|m)\b").unwrap();
+        res.distance_meters = take(&distance_re, &mut reps)
+            .or_else(|| take(&distance_re, &mut duration))
+            .and_then(|c| {
+                let value: f64 = c[0].parse().ok()?;
+
+                Some(match c[1].as_str() {
+                    "km" => value * 1000.0,
+                    "mi" => value * METERS_PER_MILE,
+                    _ => value,
+                })
+            });
+
+        let open_ended_re = Regex::new(r"(\d+)\s*\+").unwrap();
+        let amrap_re = Regex::new(r"\b(amrap|max|failure)\b").unwrap();
+        let range_re = Regex::new(r"(\d+)\s*(?:-|–|to)\s*(\d+)").unwrap();
+        let fixed_re = Regex::new(r"\d+").unwrap();
+
+        if let Some(caps) = open_ended_re.captures(&reps) {
+            res.reps_min = caps[1].parse().ok();
+            res.amrap = true;
+        } else if amrap_re.is_match(&reps) {
+            res.amrap = true;
+        } else if let Some(caps) = range_re.captures(&reps) {
+            res.reps_min = caps[1].parse().ok();
+            res.reps_max = caps[2].parse().ok();
+        } else if let Some(m) = fixed_re.find(&reps) {
+            res.reps_min = m.as_str().parse().ok();
+            res.reps_max = res.reps_min;
+        }
+
+        let tut_re = Regex::new(r"tut\s*:?\s*((?:\d+(?:\.\d+)?\s*[a-z]*\s*)+)").unwrap();
+        res.tut_seconds = take(&tut_re, &mut duration).and_then(|c| parse_seconds(&c[0]));
+        res.duration_seconds = parse_seconds(&duration);
+        res.rest_seconds = parse_seconds(rest_period);
+
+        (sets, res)
+    }
+
+    /// Renders the reps, duration and rest strings, `parse` gives back the same prescription.
+    pub fn display(&self) -> (String, String, String) {
+        let mut reps = vec![];
+
+        match (self.reps_min, self.reps_max, self.amrap) {
+            (Some(min), _, true) => reps.push(format!("{min}+")),
+            (None, _, true) => reps.push("AMRAP".to_string()),
+            (Some(min), Some(max), false) if min != max => reps.push(format!("{min}-{max}")),
+            (Some(min), _, false) | (None, Some(min), false) => reps.push(min.to_string()),
+            (None, None, false) => {}
+        }
+
+        if let Some(percent) = self.percent_1rm {
+            reps.push(format!("@ {percent}%"));
+        }
+
+        if !self.tempo.is_empty() {
+            reps.push(format!("tempo {}", self.tempo));
+        }
+
+        let mut duration = vec![];
+
+        if let Some(seconds) = self.duration_seconds {
+            duration.push(format_seconds(seconds));
+        }
+
+        if let Some(seconds) = self.tut_seconds {
+            duration.push(format!("TUT {}", format_seconds(seconds)));
+        }
+
+        if let Some(meters) = self.distance_meters {
+            duration.push(format_distance(meters));
+        }
+
+        (
+            reps.join(" "),
+            duration.join(", "),
+            self.rest_seconds.map(format_seconds).unwrap_or_default(),
+        )
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let non_negative = [
+            ("reps_min", self.reps_min),
+            ("reps_max", self.reps_max),
+            ("duration_seconds", self.duration_seconds),
+            ("tut_seconds", self.tut_seconds),
+            ("rest_seconds", self.rest_seconds),
+        ];
+
+        for (field, value) in non_negative {
+            if value.is_some_and(|v| v < 0) {
+                return Err(format!("{field} can't be negative."));
+            }
+        }
+
+        if let (Some(min), Some(max)) = (self.reps_min, self.reps_max) {
+            if min > max {
+                return Err("reps_min can't be greater than reps_max.".into());
+            }
+        }
+
+        if self.amrap && self.reps_max.is_some() {
+            return Err("AMRAP sets can't have reps_max.".into());
+        }
+
+        if self
+            .distance_meters
+            .is_some_and(|d| !d.is_finite() || d < 0.0)
+        {
+            return Err("distance_meters can't be negative.".into());
+        }
+
+        if self
+            .percent_1rm
+            .is_some_and(|p| !p.is_finite() || p <= 0.0 || p > 100.0)
+        {
+            return Err("percent_1rm must be between 0 and 100.".into());
+        }
+
+        if !self.tempo.is_empty()
+            && !Regex::new(r"^[0-9x]-[0-9x]-[0-9x]-[0-9x]$")
+                .unwrap()
+                .is_match(&self.tempo)
+        {
+            return Err("tempo must look like 3-1-1-0.".into());
+        }
+
+        Ok(())
+    }
+
+    /// Whether any of the fields of the reps string is set.
+    pub fn has_reps(&self) -> bool {
+        self.reps_min.is_some()
+            || self.reps_max.is_some()
+            || self.amrap
+            || self.percent_1rm.is_some()
+            || !self.tempo.is_empty()
+    }
+
+    /// Whether any of the fields of the duration string is set.
+    pub fn has_duration(&self) -> bool {
+        self.duration_seconds.is_some()
+            || self.tut_seconds.is_some()
+            || self.distance_meters.is_some()
+    }
+}
+
+/// Removes the first match of `re` from `value` and returns its capture groups.
+fn take(re: &Regex, value: &mut String) -> Option<Vec<String>> {
+    let caps = re.captures(value)?;
+    let groups = caps
+        .iter()
+        .skip(1)
+        .map(|c| c.map(|c| c.as_str().to_string()).unwrap_or_default())
+        .collect();
+    let range = caps.get(0)?.range();
+
+    value.replace_range(range, " ");
+
+    Some(groups)
+}
+
+/// `90`, `1:30`, `90 sec` and `1 min 30s` are all 90 seconds.
+pub fn parse_seconds(value: &str) -> Option<i32> {
+    let value = value.trim().to_lowercase();
+
+    if let Some(seconds) = parse_digits(&value) {
+        return Some(seconds);
+    }
+
+    if let Some((minutes, seconds)) = value.split_once(':') {
+        if let (Some(m), Some(s)) = (parse_digits(minutes), parse_digits(seconds)) {
+            if seconds.len() == 2 {
+                return m.checked_mul(60)?.checked_add(s);
+            }
+        }
+    }
+
+    let re =
+        Regex::new(r"(\d+(?:\.\d+)?)\s*(hours?|hrs?|h|minutes?|mins?|seconds?|secs?|s)\b").unwrap();
+
+    let mut total = None;
+
+    for caps in re.captures_iter(&value) {
+        let amount: f64 = caps[1].parse().unwrap_or_default();
+        let unit = match caps[2].chars().next() {
+            Some('h') => 3600.0,
+            Some('m') => 60.0,
+            _ => 1.0,
+        };
+
+        total = Some(total.unwrap_or(0.0) + amount * unit);
+    }
+
+    total
+        .map(|t: f64| t.round())
+        .filter(|t| *t <= i32::MAX as f64)
+        .map(|t| t as i32)
+}
+
+/// Plain digits only, `parse` would also take a sign.
+fn parse_digits(value: &str) -> Option<i32> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    value.parse().ok()
+}
+
+pub fn format_seconds(seconds: i32) -> String {
+    match (seconds / 60, seconds % 60) {
+        (0, s) => format!("{s}s"),
+        (m, 0) => format!("{m} min"),
+        (m, s) => format!("{m} min {s}s"),
+    }
+}
+
+fn format_distance(meters: f64) -> String {
+    if meters >= 1000.0 {
+        format!("{} km", meters / 1000.0)
+    } else {
+        format!("{meters}m")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_legacy_strings() {
+        let (sets, p) = Prescription::parse("3x8-12", "", "90 sec");
+        assert_eq!(sets, Some(3));
+        assert_eq!(
+            (p.reps_min, p.reps_max, p.amrap),
+            (Some(8), Some(12), false)
+        );
+        assert_eq!(p.rest_seconds, Some(90));
+
+        let (sets, p) = Prescription::parse("5 reps @ 75% tempo 3-1-1-0", "TUT 40s", "2 min");
+        assert_eq!(sets, None);
+        assert_eq!((p.reps_min, p.reps_max), (Some(5), Some(5)));
+        assert_eq!(p.percent_1rm, Some(75.0));
+        assert_eq!(p.tempo, "3-1-1-0");
+        assert_eq!(p.tut_seconds, Some(40));
+        assert_eq!(p.duration_seconds, None);
+        assert_eq!(p.rest_seconds, Some(120));
+
+        let (_, p) = Prescription::parse("AMRAP", "45s", "1:30");
+        assert!(p.amrap);
+        assert_eq!(p.duration_seconds, Some(45));
+        assert_eq!(p.rest_seconds, Some(90));
+
+        let (_, p) = Prescription::parse("", "1.5 km", "");
+        assert_eq!(p.distance_meters, Some(1500.0));
+        assert_eq!(p.duration_seconds, None);
+
+        let (_, p) = Prescription::parse("10+", "1 min 30s", "until ready");
+        assert_eq!((p.reps_min, p.reps_max, p.amrap), (Some(10), None, true));
+        assert_eq!(p.duration_seconds, Some(90));
+        assert_eq!(p.rest_seconds, None);
+
+        assert_eq!(parse_seconds("99999999:00"), None);
+        assert_eq!(parse_seconds("9999999 hours"), None);
+        assert_eq!(parse_seconds("-5"), None);
+        assert_eq!(parse_seconds("+1:30"), None);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let prescriptions = [
+            Prescription {
+                reps_min: Some(8),
+                reps_max: Some(12),
+                percent_1rm: Some(72.5),
+                tempo: "3-1-x-0".into(),
+                rest_seconds: Some(90),
+                ..Default::default()
+            },
+            Prescription {
+                reps_min: Some(10),
+                amrap: true,
+                duration_seconds: Some(45),
+                tut_seconds: Some(150),
+                distance_meters: Some(400.0),
+                ..Default::default()
+            },
+            Prescription {
+                amrap: true,
+                distance_meters: Some(2500.0),
+                rest_seconds: Some(180),
+                ..Default::default()
+            },
+        ];
+
+        for p in prescriptions {
+            let (reps, duration, rest) = p.display();
+            assert_eq!(Prescription::parse(&reps, &duration, &rest).1, p);
+        }
+    }
+}
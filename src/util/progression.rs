@@ -0,0 +1,407 @@
+use crate::{
+    db::models::{
+        exercise::{Exercise, ExerciseWithCatalog},
+        program_document::{block_position, BlockDocument, ExerciseDocument, WorkoutDocument},
+        workout::WorkoutWithExercises,
+        IntensityChoices,
+    },
+    util::calendar::MAX_WORKOUT_WEEK,
+};
+use regex::Regex;
+use serde::Deserialize;
+
+/// Rules used to build the rest of a training block out of its base week.
+///
+/// Progression is linear from the base week: in the Nth progression week the load is
+/// `base * (1 + N * load_increase_percent / 100)` and `added_sets`/`added_reps` are added N times.
+/// Deload weeks don't count as a progression step.
+#[derive(Deserialize, Debug)]
+pub struct ProgressionRules {
+    /// Week the generated weeks are copied from
+    #[serde(default = "default_base_week")]
+    pub base_week: i32,
+    /// Length of the block including the base week
+    pub weeks: i32,
+    #[serde(default)]
+    pub load_increase_percent: f64,
+    #[serde(default)]
+    pub added_sets: i32,
+    /// Added to every number in `reps`, so `8-12` becomes `9-13`
+    #[serde(default)]
+    pub added_reps: i32,
+    /// Every Nth week of the block is a deload week
+    pub deload_every: Option<i32>,
+    /// Load of a deload week as a percentage of the week before it
+    #[serde(default = "default_deload_percent")]
+    pub deload_load_percent: f64,
+    /// Steps the intensity from low to high over the block, deload weeks are always low
+    #[serde(default)]
+    pub intensity_ramp: bool,
+    /// Replace workouts that already exist in the generated weeks
+    #[serde(default)]
+    pub replace_existing: bool,
+}
+
+fn default_base_week() -> i32 {
+    1
+}
+
+fn default_deload_percent() -> f64 {
+    60.0
+}
+
+pub const MAX_BLOCK_WEEKS: i32 = 52;
+/// Most sets or reps a single progression step can add
+pub const MAX_ADDED_PER_STEP: i32 = 20;
+
+impl ProgressionRules {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(2..=MAX_BLOCK_WEEKS).contains(&self.weeks) {
+            return Err(format!("weeks must be between 2 and {MAX_BLOCK_WEEKS}."));
+        }
+
+        // the last generated week has to fit on the calendar too
+        if !(1..=MAX_WORKOUT_WEEK - self.weeks + 1).contains(&self.base_week) {
+            return Err(format!(
+                "The block has to end by week {MAX_WORKOUT_WEEK}, base_week must be between 1 \
+                and {}.",
+                MAX_WORKOUT_WEEK - self.weeks + 1
+            ));
+        }
+
+        if self.deload_every.is_some_and(|n| n < 2) {
+            return Err("deload_every must be at least 2.".into());
+        }
+
+        if !(0.0..=100.0).contains(&self.deload_load_percent) {
+            return Err("deload_load_percent must be between 0 and 100.".into());
+        }
+
+        if self.load_increase_percent < -100.0 || self.added_sets < 0 || self.added_reps < 0 {
+            return Err("Progression rules can't remove sets or reps.".into());
+        }
+
+        if self.added_sets > MAX_ADDED_PER_STEP || self.added_reps > MAX_ADDED_PER_STEP {
+            return Err(format!(
+                "added_sets and added_reps can be at most {MAX_ADDED_PER_STEP}."
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Weeks of the program that get generated, the base week itself is left alone.
+    pub fn generated_weeks(&self) -> std::ops::RangeInclusive<i32> {
+        (self.base_week + 1)..=(self.base_week + self.weeks - 1)
+    }
+
+    fn is_deload(&self, block_week: i32) -> bool {
+        self.deload_every.is_some_and(|n| block_week % n == 0)
+    }
+}
+
+/// Generates every week of the block after the base week. `base` should be the workouts of the
+/// base week with their exercises, ordered by sequence.
+pub fn generate_progression(
+    base: &[&WorkoutWithExercises],
+    rules: &ProgressionRules,
+) -> Vec<WorkoutDocument> {
+    let total_steps = (2..=rules.weeks).filter(|w| !rules.is_deload(*w)).count() as i32;
+
+    let mut steps = 0;
+    let mut generated = vec![];
+
+    // week 1 of the block is the base week
+    for block_week in 2..=rules.weeks {
+        let deload = rules.is_deload(block_week);
+
+        if !deload {
+            steps += 1;
+        }
+
+        let load_factor = if deload {
+            (1.0 + f64::from(steps) * rules.load_increase_percent / 100.0)
+                * rules.deload_load_percent
+                / 100.0
+        } else {
+            1.0 + f64::from(steps) * rules.load_increase_percent / 100.0
+        };
+
+        let intensity = if deload {
+            Some(IntensityChoices::Low)
+        } else if rules.intensity_ramp {
+            Some(ramp_intensity(steps, total_steps))
+        } else {
+            None
+        };
+
+        for (w_idx, base_workout) in base.iter().enumerate() {
+            let workout = &base_workout.workout;
+
+            let exercises = base_workout
+                .exercises
+                .iter()
+                .enumerate()
+                .map(
+                    |(e_idx, ExerciseWithCatalog { exercise, catalog })| ExerciseDocument {
+                        name: catalog.name.clone(),
+                        description: catalog.description.clone(),
+                        duration: exercise.duration.clone(),
+                        reps: if deload {
+                            exercise.reps.clone()
+                        } else {
+                            progress_reps(exercise, rules.added_reps * steps)
+                        },
+                        sets: if deload {
+                            exercise.sets
+                        } else {
+                            exercise.sets.saturating_add(rules.added_sets * steps)
+                        },
+                        rest_period: exercise.rest_period.clone(),
+                        intensity: intensity.clone().unwrap_or(exercise.intensity.clone()),
+                        equipment: catalog.equipment.clone(),
+                        muscle_groups: catalog.muscle_groups.clone(),
+                        image: catalog.image.clone(),
+                        video: catalog.video.clone(),
+                        instructions: catalog.instructions.clone(),
+                        sequence: Some(e_idx as i32),
+                        load: exercise.load.map(|load| round_load(load * load_factor)),
+                        load_unit: exercise.load_unit.clone(),
+                        block: block_position(&base_workout.blocks, exercise.block_id),
+                        catalog_id: Some(exercise.catalog_id),
+                    },
+                )
+                .collect();
+
+            generated.push(WorkoutDocument {
+                name: workout.name.clone(),
+                description: workout.description.clone(),
+                duration: workout.duration.clone(),
+                week: rules.base_week + block_week - 1,
+                sequence: Some(w_idx as i32),
+                intensity: intensity.clone().unwrap_or(workout.intensity.clone()),
+                workout_type: workout.workout_type.clone(),
+                equipment_needed: workout.equipment_needed.clone(),
+                image: workout.image.clone(),
+                video: workout.video.clone(),
+                blocks: base_workout
+                    .blocks
+                    .iter()
+                    .map(BlockDocument::from)
+                    .collect(),
+                exercises,
+            });
+        }
+    }
+
+    generated
+}
+
+/// Splits the progression steps of the block into thirds: low, medium and high.
+fn ramp_intensity(step: i32, total_steps: i32) -> IntensityChoices {
+    let third = f64::from(step) / f64::from(total_steps.max(1));
+
+    if third <= 1.0 / 3.0 {
+        IntensityChoices::Low
+    } else if third <= 2.0 / 3.0 {
+        IntensityChoices::Medium
+    } else {
+        IntensityChoices::High
+    }
+}
+
+/// Adds to the structured reps and renders them again, so loads and tempos in the string are
+/// left alone. Falls back to the numbers of the string when the reps couldn't be parsed.
+fn progress_reps(exercise: &Exercise, added: i32) -> String {
+    if added == 0 {
+        return exercise.reps.clone();
+    }
+
+    let mut prescription = exercise.prescription();
+
+    if prescription.reps_min.is_none() {
+        return add_reps(&exercise.reps, added);
+    }
+
+    prescription.reps_min = prescription.reps_min.map(|n| n.saturating_add(added));
+    prescription.reps_max = prescription.reps_max.map(|n| n.saturating_add(added));
+
+    prescription.display().0
+}
+
+fn add_reps(reps: &str, added: i32) -> String {
+    if added == 0 {
+        return reps.to_string();
+    }
+
+    let re = Regex::new(r"\d+").unwrap();
+
+    re.replace_all(reps, |caps: &regex::Captures| {
+        let n: i32 = caps[0].parse().unwrap_or_default();
+        n.saturating_add(added).to_string()
+    })
+    .to_string()
+}
+
+fn round_load(load: f64) -> f64 {
+    (load * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{
+        exercise_catalog::CatalogExercise,
+        workout::Workout,
+        workout_block::{BlockTypes, WorkoutBlock},
+    };
+
+    fn base_workout() -> WorkoutWithExercises {
+        let workout = Workout {
+            id: uuid::Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            program_id: None,
+            owner_id: None,
+            name: "Lower Body".into(),
+            description: String::new(),
+            duration: String::new(),
+            sequence: 0,
+            week: 1,
+            intensity: IntensityChoices::Low,
+            workout_type: String::new(),
+            equipment_needed: String::new(),
+            image: String::new(),
+            video: String::new(),
+            template: false,
+            slug: String::new(),
+            complete: false,
+            scheduled_date: None,
+            completed_at: None,
+            archived_at: None,
+        };
+
+        let catalog = CatalogExercise {
+            id: uuid::Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            owner_id: None,
+            name: "Back Squat".into(),
+            slug: "back-squat".into(),
+            description: String::new(),
+            equipment: String::new(),
+            muscle_groups: String::new(),
+            image: String::new(),
+            video: String::new(),
+            instructions: String::new(),
+            movement_pattern: None,
+            intensity: None,
+        };
+
+        let block = WorkoutBlock {
+            id: uuid::Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            workout_id: workout.id,
+            block_type: BlockTypes::Superset,
+            name: "A".into(),
+            sequence: 0,
+            rounds: 3,
+            work_seconds: None,
+            rest_seconds: None,
+            round_rest_seconds: Some(90),
+        };
+
+        let exercise = Exercise {
+            id: uuid::Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            workout_id: Some(workout.id),
+            owner_id: None,
+            catalog_id: catalog.id,
+            block_id: Some(block.id),
+            duration: String::new(),
+            reps: "8-12 @ 70%".into(),
+            sets: 3,
+            rest_period: String::new(),
+            intensity: IntensityChoices::Low,
+            sequence: 0,
+            slug: String::new(),
+            load: Some(100.0),
+            load_unit: "kg".into(),
+            archived_at: None,
+            reps_min: Some(8),
+            reps_max: Some(12),
+            amrap: false,
+            duration_seconds: None,
+            tut_seconds: None,
+            tempo: String::new(),
+            distance_meters: None,
+            rest_seconds: None,
+            percent_1rm: Some(70.0),
+        };
+
+        WorkoutWithExercises {
+            workout,
+            blocks: vec![block],
+            exercises: vec![ExerciseWithCatalog { exercise, catalog }],
+        }
+    }
+
+    #[test]
+    fn test_generate_progression_with_deload() {
+        let mut rules = ProgressionRules {
+            base_week: i32::MAX,
+            weeks: 4,
+            load_increase_percent: 2.5,
+            added_sets: 1,
+            added_reps: i32::MAX,
+            deload_every: Some(4),
+            deload_load_percent: 60.0,
+            intensity_ramp: true,
+            replace_existing: false,
+        };
+
+        assert!(rules.validate().is_err());
+        rules.base_week = 1;
+        assert!(rules.validate().is_err());
+
+        let rules = ProgressionRules {
+            base_week: 1,
+            weeks: 4,
+            load_increase_percent: 2.5,
+            added_sets: 1,
+            added_reps: 1,
+            deload_every: Some(4),
+            deload_load_percent: 60.0,
+            intensity_ramp: true,
+            replace_existing: false,
+        };
+
+        let base = base_workout();
+        let weeks = generate_progression(&[&base], &rules);
+
+        let summary: Vec<(i32, Option<f64>, i32, String)> = weeks
+            .iter()
+            .map(|w| {
+                let e = &w.exercises[0];
+                (w.week, e.load, e.sets, e.reps.clone())
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (2, Some(102.5), 4, "9-13 @ 70%".into()),
+                (3, Some(105.0), 5, "10-14 @ 70%".into()),
+                // deload, 60% of the week before at the base volume
+                (4, Some(63.0), 3, "8-12 @ 70%".into()),
+            ]
+        );
+
+        assert!(weeks
+            .iter()
+            .all(|w| w.exercises[0].catalog_id == Some(base.exercises[0].catalog.id)));
+        assert!(weeks.iter().all(|w| w.exercises[0].block == Some(0)
+            && w.blocks.len() == 1
+            && w.blocks[0].rounds == 3));
+        assert!(matches!(weeks[1].intensity, IntensityChoices::High));
+        assert!(matches!(weeks[2].intensity, IntensityChoices::Low));
+    }
+}
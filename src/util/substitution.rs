@@ -0,0 +1,170 @@
+use crate::db::models::{
+    exercise_catalog::{CatalogExercise, MovementPatterns},
+    taxonomy::MuscleRoles,
+    IntensityChoices,
+};
+use serde::{Deserialize, Serialize};
+
+const MUSCLE_WEIGHT: f64 = 0.6;
+const MOVEMENT_WEIGHT: f64 = 0.25;
+const INTENSITY_WEIGHT: f64 = 0.15;
+
+pub const DEFAULT_SUBSTITUTES: i64 = 10;
+pub const MAX_SUBSTITUTES: i64 = 50;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SubstituteParams {
+    /// Only suggest equipment this client has, defaults to the client of the exercise's program
+    pub client: Option<uuid::Uuid>,
+    /// Comma separated muscle slugs to stay away from, e.g. because of an injury
+    pub avoid_muscles: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Replaces `from_catalog_id` with `to_catalog_id` in every exercise of a program.
+#[derive(Deserialize, Debug)]
+pub struct SwapExercise {
+    pub from_catalog_id: uuid::Uuid,
+    pub to_catalog_id: uuid::Uuid,
+}
+
+/// What an exercise is compared on.
+#[derive(Debug, Clone, Default)]
+pub struct ExerciseProfile {
+    pub muscles: Vec<(uuid::Uuid, MuscleRoles)>,
+    pub movement_pattern: Option<MovementPatterns>,
+    pub intensity: Option<IntensityChoices>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Substitute {
+    pub catalog: CatalogExercise,
+    /// Between 0 and 1, weighs the three scores below
+    pub score: f64,
+    pub muscle_score: f64,
+    pub same_movement: bool,
+    pub intensity_score: f64,
+}
+
+fn muscle_weight(role: MuscleRoles) -> f64 {
+    match role {
+        MuscleRoles::Primary => 2.0,
+        MuscleRoles::Secondary => 1.0,
+    }
+}
+
+fn intensity_rank(intensity: &IntensityChoices) -> i32 {
+    match intensity {
+        IntensityChoices::Low => 0,
+        IntensityChoices::Medium => 1,
+        IntensityChoices::High => 2,
+    }
+}
+
+/// Weighted overlap of the muscles, primary muscles count twice as much as secondary ones.
+fn muscle_score(source: &ExerciseProfile, candidate: &ExerciseProfile) -> f64 {
+    let weight = |profile: &ExerciseProfile, muscle_id: &uuid::Uuid| {
+        profile
+            .muscles
+            .iter()
+            .find(|(m_id, _)| m_id == muscle_id)
+            .map(|(_, role)| muscle_weight(*role))
+            .unwrap_or(0.0)
+    };
+
+    let mut shared = 0.0;
+    let mut union = 0.0;
+
+    for (muscle_id, role) in &source.muscles {
+        let other = weight(candidate, muscle_id);
+
+        shared += muscle_weight(*role).min(other);
+        union += muscle_weight(*role).max(other);
+    }
+
+    for (muscle_id, role) in &candidate.muscles {
+        if weight(source, muscle_id) == 0.0 {
+            union += muscle_weight(*role);
+        }
+    }
+
+    if union == 0.0 {
+        0.0
+    } else {
+        shared / union
+    }
+}
+
+/// 1 for the same intensity, 0.5 a step apart and 0 for low against high. Unknown intensities are
+/// in between.
+fn intensity_score(source: &ExerciseProfile, candidate: &ExerciseProfile) -> f64 {
+    match (&source.intensity, &candidate.intensity) {
+        (Some(a), Some(b)) => 1.0 - f64::from((intensity_rank(a) - intensity_rank(b)).abs()) / 2.0,
+        _ => 0.5,
+    }
+}
+
+/// Scores of `candidate` as a substitute for `source`: overall, muscles, movement and intensity.
+pub fn score_substitute(
+    source: &ExerciseProfile,
+    candidate: &ExerciseProfile,
+) -> (f64, f64, bool, f64) {
+    let muscles = muscle_score(source, candidate);
+    let same_movement =
+        source.movement_pattern.is_some() && source.movement_pattern == candidate.movement_pattern;
+    let intensity = intensity_score(source, candidate);
+
+    let score = MUSCLE_WEIGHT * muscles
+        + MOVEMENT_WEIGHT * f64::from(u8::from(same_movement))
+        + INTENSITY_WEIGHT * intensity;
+
+    (score, muscles, same_movement, intensity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_substitute() {
+        let (chest, triceps, delts) = (
+            uuid::Uuid::new_v4(),
+            uuid::Uuid::new_v4(),
+            uuid::Uuid::new_v4(),
+        );
+
+        let bench = ExerciseProfile {
+            muscles: vec![
+                (chest, MuscleRoles::Primary),
+                (triceps, MuscleRoles::Secondary),
+            ],
+            movement_pattern: Some(MovementPatterns::HorizontalPush),
+            intensity: Some(IntensityChoices::High),
+        };
+        let push_up = ExerciseProfile {
+            intensity: Some(IntensityChoices::Medium),
+            ..bench.clone()
+        };
+        let press = ExerciseProfile {
+            muscles: vec![
+                (delts, MuscleRoles::Primary),
+                (triceps, MuscleRoles::Secondary),
+            ],
+            movement_pattern: Some(MovementPatterns::VerticalPush),
+            intensity: Some(IntensityChoices::High),
+        };
+
+        let (score, muscles, same_movement, intensity) = score_substitute(&bench, &push_up);
+        assert_eq!((muscles, same_movement, intensity), (1.0, true, 0.5));
+        assert!((score - 0.925).abs() < 1e-9);
+
+        let (score, muscles, same_movement, _) = score_substitute(&bench, &press);
+        assert_eq!(muscles, 1.0 / 5.0);
+        assert!(!same_movement);
+        assert!((score - (0.6 * 0.2 + 0.15)).abs() < 1e-9);
+
+        let (_, muscles, same_movement, intensity) =
+            score_substitute(&ExerciseProfile::default(), &ExerciseProfile::default());
+        assert_eq!((muscles, same_movement, intensity), (0.0, false, 0.5));
+    }
+}
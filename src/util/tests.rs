@@ -72,6 +72,7 @@ pub fn create_test_user(conn: &mut DbConnection) -> User {
         bio: "A great user bio!".to_string(),
         gender: "Male".to_string(),
         beta_access: true,
+        timezone: None,
     };
 
     use crate::schema::users::dsl::*;